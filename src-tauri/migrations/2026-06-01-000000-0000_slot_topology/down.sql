@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_slots_frame;
+
+ALTER TABLE slots
+    DROP COLUMN IF EXISTS retired,
+    DROP COLUMN IF EXISTS frame_position,
+    DROP COLUMN IF EXISTS frame_number;
@@ -0,0 +1,16 @@
+-- Each slot records which frame it hangs on and its position within that frame.
+-- Retired slots no longer exist in the frame configuration but still held
+-- garments when the layout changed; they are removed once they are emptied.
+ALTER TABLE slots
+    ADD COLUMN IF NOT EXISTS frame_number INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS frame_position INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS retired BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Backfill the seeded slots assuming the PLC default of 5 slots per frame.
+-- The next topology sync replaces these values with the configured layout.
+UPDATE slots
+SET frame_number = ((slot_number - 1) / 5) + 1,
+    frame_position = ((slot_number - 1) % 5) + 1
+WHERE frame_number = 0;
+
+CREATE INDEX IF NOT EXISTS idx_slots_frame ON slots(frame_number, frame_position);
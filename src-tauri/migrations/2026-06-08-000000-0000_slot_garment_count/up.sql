@@ -0,0 +1,19 @@
+-- Number of garments currently hanging on each slot, kept in step with
+-- garments.slot_number on every load and unload.
+ALTER TABLE slots
+    ADD COLUMN IF NOT EXISTS garment_count INTEGER NOT NULL DEFAULT 0;
+
+UPDATE slots s
+SET garment_count = (
+    SELECT COUNT(*) FROM garments g WHERE g.slot_number = s.slot_number
+);
+
+-- item_id holds the most recently loaded garment on the slot.
+UPDATE slots s
+SET item_id = (
+    SELECT g.item_id FROM garments g
+    WHERE g.slot_number = s.slot_number
+    ORDER BY g.id DESC
+    LIMIT 1
+)
+WHERE s.item_id IS NULL;
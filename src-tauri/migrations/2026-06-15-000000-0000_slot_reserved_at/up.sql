@@ -0,0 +1,9 @@
+-- When the slot was last reserved; cleared once it is occupied or freed.
+-- Reservations older than the configured time-to-live with no garments on
+-- them are released by the reservation sweeper.
+ALTER TABLE slots
+    ADD COLUMN IF NOT EXISTS reserved_at TIMESTAMP;
+
+UPDATE slots
+SET reserved_at = updated_at
+WHERE slot_state = 'reserved';
@@ -0,0 +1,17 @@
+-- One row per change to a slot: state transitions plus garments loaded onto
+-- or taken off it. from_state is NULL when the slot was first created.
+CREATE TABLE IF NOT EXISTS slot_history (
+    id SERIAL PRIMARY KEY,
+    slot_number INT NOT NULL,
+    from_state VARCHAR,
+    to_state VARCHAR NOT NULL,
+    ticket VARCHAR,
+    item_id VARCHAR,
+    user_id INT,
+    session_id INT,
+    reason VARCHAR NOT NULL,
+    changed_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_slot_history_slot ON slot_history(slot_number, changed_at);
+CREATE INDEX IF NOT EXISTS idx_slot_history_ticket ON slot_history(ticket, changed_at);
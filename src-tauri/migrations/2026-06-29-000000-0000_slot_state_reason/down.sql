@@ -0,0 +1,3 @@
+ALTER TABLE slots
+    DROP COLUMN IF EXISTS blocked_by_layout,
+    DROP COLUMN IF EXISTS state_reason;
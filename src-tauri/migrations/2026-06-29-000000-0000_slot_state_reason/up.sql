@@ -0,0 +1,12 @@
+-- Why a slot is blocked or in error, shown to admins in the out-of-service list.
+-- blocked_by_layout marks blocks made by the frame configuration, which the
+-- topology sync may lift again; admin blocks are only lifted by an admin.
+ALTER TABLE slots
+    ADD COLUMN IF NOT EXISTS state_reason VARCHAR,
+    ADD COLUMN IF NOT EXISTS blocked_by_layout BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Until now only the topology sync blocked slots.
+UPDATE slots
+SET blocked_by_layout = TRUE,
+    state_reason = 'position disabled in frame configuration'
+WHERE slot_state = 'blocked';
@@ -0,0 +1,3 @@
+ALTER TABLE tickets DROP CONSTRAINT IF EXISTS tickets_ticket_status_check;
+ALTER TABLE garments DROP CONSTRAINT IF EXISTS garments_garment_state_check;
+ALTER TABLE slots DROP CONSTRAINT IF EXISTS slots_slot_state_check;
@@ -0,0 +1,28 @@
+-- Slot, garment and ticket states were free text. Anything outside the
+-- known values is normalised before the constraints go on: unknown slot
+-- states become errors for an admin to look at, unknown garment and ticket
+-- states go back to Not Processed.
+UPDATE slots
+SET slot_state = 'error',
+    state_reason = 'unknown state ' || slot_state
+WHERE slot_state NOT IN ('empty', 'reserved', 'occupied', 'blocked', 'error');
+
+UPDATE garments
+SET garment_state = 'Not Processed'
+WHERE garment_state NOT IN ('Not Processed', 'Processing', 'Processed');
+
+UPDATE tickets
+SET ticket_status = 'Not Processed'
+WHERE ticket_status NOT IN ('Not Processed', 'Processing', 'Complete', 'Processed');
+
+ALTER TABLE slots
+    ADD CONSTRAINT slots_slot_state_check
+    CHECK (slot_state IN ('empty', 'reserved', 'occupied', 'blocked', 'error'));
+
+ALTER TABLE garments
+    ADD CONSTRAINT garments_garment_state_check
+    CHECK (garment_state IN ('Not Processed', 'Processing', 'Processed'));
+
+ALTER TABLE tickets
+    ADD CONSTRAINT tickets_ticket_status_check
+    CHECK (ticket_status IN ('Not Processed', 'Processing', 'Complete', 'Processed'));
@@ -0,0 +1,15 @@
+-- Taken before every conveyor clear so an admin can undo it. payload holds
+-- the cleared slots, the garments on them and their tickets as JSON.
+CREATE TABLE IF NOT EXISTS conveyor_snapshots (
+    id SERIAL PRIMARY KEY,
+    scope VARCHAR NOT NULL,
+    payload TEXT NOT NULL,
+    slot_count INT NOT NULL,
+    garment_count INT NOT NULL,
+    created_by INT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    restored_at TIMESTAMP,
+    restored_by INT
+);
+
+CREATE INDEX IF NOT EXISTS idx_conveyor_snapshots_created ON conveyor_snapshots(created_at);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS inventory_audit_discrepancies;
+DROP TABLE IF EXISTS inventory_audit_scans;
+DROP TABLE IF EXISTS inventory_audits;
@@ -0,0 +1,35 @@
+-- Physical inventory audits: an operator walks the conveyor scanning what
+-- actually hangs on each slot, then compares it with the garments table.
+CREATE TABLE IF NOT EXISTS inventory_audits (
+    id SERIAL PRIMARY KEY,
+    audit_status VARCHAR NOT NULL DEFAULT 'open'
+        CHECK (audit_status IN ('open', 'finished', 'applied')),
+    started_by INT,
+    started_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP,
+    applied_by INT,
+    applied_at TIMESTAMP
+);
+
+-- A garment scanned twice keeps the slot it was scanned at last.
+CREATE TABLE IF NOT EXISTS inventory_audit_scans (
+    id SERIAL PRIMARY KEY,
+    audit_id INT NOT NULL REFERENCES inventory_audits(id) ON DELETE CASCADE,
+    slot_number INT NOT NULL,
+    item_id VARCHAR NOT NULL,
+    scanned_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (audit_id, item_id)
+);
+
+-- Stored when the audit is finished.
+CREATE TABLE IF NOT EXISTS inventory_audit_discrepancies (
+    id SERIAL PRIMARY KEY,
+    audit_id INT NOT NULL REFERENCES inventory_audits(id) ON DELETE CASCADE,
+    kind VARCHAR NOT NULL,
+    item_id VARCHAR NOT NULL,
+    expected_slot INT,
+    found_slot INT
+);
+
+CREATE INDEX IF NOT EXISTS idx_inventory_audit_scans_audit ON inventory_audit_scans(audit_id);
+CREATE INDEX IF NOT EXISTS idx_inventory_audit_discrepancies_audit ON inventory_audit_discrepancies(audit_id);
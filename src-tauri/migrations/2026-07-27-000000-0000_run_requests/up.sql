@@ -0,0 +1,12 @@
+-- One row per slot run request sent to the PLC, with how it ended.
+CREATE TABLE IF NOT EXISTS run_requests (
+    id SERIAL PRIMARY KEY,
+    target_slot INT NOT NULL,
+    outcome VARCHAR NOT NULL,
+    detail TEXT,
+    duration_ms BIGINT NOT NULL,
+    requested_by INT,
+    requested_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_requests_requested ON run_requests(requested_at);
@@ -0,0 +1,14 @@
+-- One row per PLC alarm occurrence, from raise to clear and acknowledgement.
+CREATE TABLE IF NOT EXISTS alarms (
+    id SERIAL PRIMARY KEY,
+    source VARCHAR NOT NULL,
+    code BIGINT NOT NULL,
+    message TEXT NOT NULL,
+    raised_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    cleared_at TIMESTAMP,
+    acknowledged_at TIMESTAMP,
+    acknowledged_by INT
+);
+
+CREATE INDEX IF NOT EXISTS idx_alarms_raised ON alarms(raised_at);
+CREATE INDEX IF NOT EXISTS idx_alarms_open ON alarms(source) WHERE cleared_at IS NULL;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS motion_profile_applications;
+DROP TABLE IF EXISTS motion_profiles;
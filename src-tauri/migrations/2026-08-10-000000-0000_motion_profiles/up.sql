@@ -0,0 +1,22 @@
+-- Named conveyor speed and acceleration settings an admin can apply to the PLC.
+CREATE TABLE IF NOT EXISTS motion_profiles (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL UNIQUE,
+    speed INT NOT NULL,
+    acceleration INT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- One row per profile applied to the PLC; the latest row is the active one.
+CREATE TABLE IF NOT EXISTS motion_profile_applications (
+    id SERIAL PRIMARY KEY,
+    profile_id INT REFERENCES motion_profiles(id) ON DELETE SET NULL,
+    profile_name VARCHAR NOT NULL,
+    speed INT NOT NULL,
+    acceleration INT NOT NULL,
+    applied_by INT,
+    applied_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_motion_profile_applications_applied ON motion_profile_applications(applied_at);
@@ -1,4 +1,4 @@
-use crate::{db::{conveyor_activity_repo, customer_repo}, model::{ConveyorActivity, Customer}};
+use crate::{db::{conveyor_activity_repo, customer_repo}, model::{ConveyorActivity, Customer}, slot_manager::SlotManager, slot_zoning::{self, ZoneFill}};
 
 pub fn generate_customer_report() -> Result<Vec<Customer>, String> {
     let mut conn = crate::db::connection::establish_connection()?;
@@ -18,4 +18,11 @@ pub fn generate_conveyor_activity_report() -> Result<Vec<ConveyorActivity>, Stri
     let mut conn = crate::db::connection::establish_connection()?;
     conveyor_activity_repo::get_all_conveyor_activity(&mut conn)
         .map_err(|e| e.to_string())
-}
\ No newline at end of file
+}
+
+pub fn generate_pickup_zone_report() -> Result<Vec<ZoneFill>, String> {
+    let mut conn = crate::db::connection::establish_connection()?;
+    let settings = SlotManager::allocation_settings();
+    slot_zoning::zone_fill(&mut conn, &settings.zoning)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,35 @@
+use crate::controller::ControllerKind;
+use crate::db::connection::establish_connection;
+use crate::domain::auth;
+use crate::opc::opc_client::AppState;
+use crate::settings::appsettings::ControllerSettings;
+
+#[tauri::command]
+pub fn get_controller_settings_tauri(app: tauri::AppHandle) -> ControllerSettings {
+    crate::settings::load_settings(&app).controller
+}
+
+/// Admin only. Chooses the PLC or the simulator and sets the simulator's
+/// timings. The controller is built at startup, so the app must be restarted.
+#[tauri::command]
+pub fn save_controller_settings_tauri(app: tauri::AppHandle, controller: ControllerSettings) -> Result<(), String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+
+    if ControllerKind::from_db(&controller.kind).is_none() {
+        return Err(format!("Unknown controller: {}", controller.kind));
+    }
+    if controller.travel_ms_per_slot == 0 {
+        return Err("Travel time per slot must be above zero".into());
+    }
+
+    let mut settings = crate::settings::load_settings(&app);
+    settings.controller = controller;
+    crate::settings::save_settings(&app, &settings)
+}
+
+/// The controller in use now, "opc" or "simulator".
+#[tauri::command]
+pub fn get_active_controller_tauri(state: tauri::State<'_, AppState>) -> String {
+    state.controller.kind().as_str().to_string()
+}
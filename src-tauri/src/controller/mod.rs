@@ -0,0 +1,59 @@
+pub mod controller_tauri_commands;
+pub mod opc_controller;
+pub mod simulator;
+
+use async_trait::async_trait;
+use tokio::sync::broadcast;
+
+use crate::opc::run_request::RunRequestResult;
+
+/// Which implementation drives the conveyor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControllerKind {
+    Opc,
+    Simulator,
+}
+
+impl ControllerKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ControllerKind::Opc => "opc",
+            ControllerKind::Simulator => "simulator",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "opc" => Some(Self::Opc),
+            "simulator" => Some(Self::Simulator),
+            _ => None,
+        }
+    }
+}
+
+/// Every call the app makes to the conveyor hardware. Implemented by the
+/// PLC over OPC and by the in-process simulator.
+#[async_trait]
+pub trait ConveyorController: Send + Sync {
+    fn kind(&self) -> ControllerKind;
+
+    fn is_connected(&self) -> bool;
+
+    async fn jog_forward(&self) -> Result<(), String>;
+
+    /// Runs the conveyor until `target_slot` is at the load station.
+    async fn run_to_slot(&self, target_slot: i16) -> Result<RunRequestResult, String>;
+
+    async fn set_number_of_frames(&self, num_frames: i16) -> Result<(), String>;
+
+    async fn set_slots_per_frame(&self, slots_per_frame: i16) -> Result<(), String>;
+
+    /// The slot last requested.
+    async fn target_slot(&self) -> Result<i64, String>;
+
+    /// Whether the load station hanger sensor sees a hanger right now.
+    async fn hanger_sensor(&self) -> Result<bool, String>;
+
+    /// Pushes every change of the load station hanger sensor.
+    async fn watch_hanger_sensor(&self) -> Result<broadcast::Receiver<bool>, String>;
+}
@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use open62541::{ScalarValue, VariantValue};
+use tokio::sync::broadcast;
+
+use crate::controller::{ControllerKind, ConveyorController};
+use crate::opc::{opc_client::OpcClient, opc_commands, run_request::RunRequestResult, tag_map::{self, OpcSignal}};
+
+/// Drives the PLC through the OPC client.
+pub struct OpcConveyor {
+    opc: OpcClient,
+}
+
+impl OpcConveyor {
+    pub fn new(opc: OpcClient) -> Self {
+        Self { opc }
+    }
+}
+
+#[async_trait]
+impl ConveyorController for OpcConveyor {
+    fn kind(&self) -> ControllerKind {
+        ControllerKind::Opc
+    }
+
+    fn is_connected(&self) -> bool {
+        opc_commands::check_opc_connection(&self.opc)
+    }
+
+    async fn jog_forward(&self) -> Result<(), String> {
+        opc_commands::jog_forward(&self.opc).await
+    }
+
+    async fn run_to_slot(&self, target_slot: i16) -> Result<RunRequestResult, String> {
+        opc_commands::slot_run_request(&self.opc, target_slot).await
+    }
+
+    async fn set_number_of_frames(&self, num_frames: i16) -> Result<(), String> {
+        opc_commands::set_number_of_frames(&self.opc, num_frames).await
+    }
+
+    async fn set_slots_per_frame(&self, slots_per_frame: i16) -> Result<(), String> {
+        opc_commands::set_slots_per_frame(&self.opc, slots_per_frame).await
+    }
+
+    async fn target_slot(&self) -> Result<i64, String> {
+        tag_map::read_integer(&self.opc, OpcSignal::TargetSlot).await
+    }
+
+    async fn hanger_sensor(&self) -> Result<bool, String> {
+        opc_commands::get_load_hanger_sensor(&self.opc).await
+    }
+
+    async fn watch_hanger_sensor(&self) -> Result<broadcast::Receiver<bool>, String> {
+        let tag = tag_map::tag_map().hanger_sensor;
+        tag_map::check_tag_type(OpcSignal::HangerSensor, &tag)?;
+        let mut rx = self.opc
+            .subscribe_value(tag_map::node_id(&tag)?)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let (tx, sensor_rx) = broadcast::channel(16);
+        tokio::spawn(async move {
+            loop {
+                let variant = match rx.recv().await {
+                    Ok(v) => v,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if let VariantValue::Scalar(ScalarValue::Boolean(b)) = variant.to_value() {
+                    // Stop once nobody is listening any more
+                    if tx.send(b.value()).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(sensor_rx)
+    }
+}
@@ -0,0 +1,216 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use tokio::sync::broadcast;
+
+use crate::controller::{ControllerKind, ConveyorController};
+use crate::model::RunRequestOutcome;
+use crate::opc::run_request::RunRequestResult;
+use crate::opc::telemetry::{self, ConveyorState, ConveyorStatus};
+use crate::retrieval_queue::forward_distance;
+use crate::settings::appsettings::ControllerSettings;
+
+/// Position and sensors of the simulated conveyor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulatorState {
+    pub num_frames: i16,
+    pub slots_per_frame: i16,
+    /// Slot currently at the load station, starting at 1.
+    pub position: i16,
+    pub target_slot: i16,
+    pub running: bool,
+    pub hanger_present: bool,
+}
+
+impl SimulatorState {
+    pub fn total_slots(&self) -> i16 {
+        self.num_frames.saturating_mul(self.slots_per_frame)
+    }
+
+    /// Telemetry as the PLC would report it.
+    pub fn status(&self) -> ConveyorStatus {
+        let codes = telemetry::status_codes();
+        ConveyorStatus {
+            state: if self.running { ConveyorState::Running } else { ConveyorState::Idle },
+            fault: None,
+            conveyor_go: Some(self.running),
+            conveyor_status: Some(if self.running { codes.running } else { codes.stopped }),
+            inverter_status: Some(0),
+            target_slot: Some(self.target_slot.into()),
+            offset_inches: None,
+            actual_slot: Some(self.position.into()),
+            plc_online: true,
+            updated_at: Some(Utc::now()),
+        }
+    }
+}
+
+struct Shared {
+    settings: ControllerSettings,
+    state: Mutex<SimulatorState>,
+    hanger_tx: broadcast::Sender<bool>,
+    status_tx: broadcast::Sender<ConveyorStatus>,
+    /// Only one move at a time, like the real conveyor.
+    motion: tokio::sync::Mutex<()>,
+}
+
+/// In-process stand-in for the PLC. It moves one slot per
+/// `travel_ms_per_slot`, always forward, and pulses the hanger sensor a
+/// while after each arrival as if an operator hung a garment.
+#[derive(Clone)]
+pub struct SimulatedConveyor {
+    shared: Arc<Shared>,
+}
+
+impl SimulatedConveyor {
+    pub fn new(settings: ControllerSettings, num_frames: i16, slots_per_frame: i16) -> Self {
+        let (hanger_tx, _) = broadcast::channel(16);
+        let (status_tx, _) = broadcast::channel(64);
+        let state = SimulatorState {
+            num_frames,
+            slots_per_frame,
+            position: 1,
+            target_slot: 1,
+            running: false,
+            hanger_present: false,
+        };
+        Self {
+            shared: Arc::new(Shared {
+                settings,
+                state: Mutex::new(state),
+                hanger_tx,
+                status_tx,
+                motion: tokio::sync::Mutex::const_new(()),
+            }),
+        }
+    }
+
+    pub fn state(&self) -> SimulatorState {
+        self.shared.state.lock().unwrap().clone()
+    }
+
+    /// Pushes the simulated telemetry after every change.
+    pub fn status_events(&self) -> broadcast::Receiver<ConveyorStatus> {
+        self.shared.status_tx.subscribe()
+    }
+
+    /// Places or removes a hanger at the load station.
+    pub fn set_hanger(&self, present: bool) {
+        self.update(|s| s.hanger_present = present);
+        let _ = self.shared.hanger_tx.send(present);
+    }
+
+    fn update(&self, f: impl FnOnce(&mut SimulatorState)) -> SimulatorState {
+        let state = {
+            let mut s = self.shared.state.lock().unwrap();
+            f(&mut s);
+            s.clone()
+        };
+        let _ = self.shared.status_tx.send(state.status());
+        state
+    }
+
+    /// Moves forward one slot at a time until `slots` have passed.
+    async fn travel(&self, slots: i32) {
+        let per_slot = Duration::from_millis(self.shared.settings.travel_ms_per_slot);
+        self.update(|s| s.running = true);
+        for _ in 0..slots {
+            tokio::time::sleep(per_slot).await;
+            self.update(|s| s.position = s.position % s.total_slots().max(1) + 1);
+        }
+        self.update(|s| s.running = false);
+    }
+
+    fn pulse_hanger_later(&self) {
+        let delay = self.shared.settings.hanger_delay_ms;
+        if delay == 0 {
+            return;
+        }
+        let pulse = Duration::from_millis(self.shared.settings.hanger_pulse_ms);
+        let sim = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            sim.set_hanger(true);
+            tokio::time::sleep(pulse).await;
+            sim.set_hanger(false);
+        });
+    }
+}
+
+#[async_trait]
+impl ConveyorController for SimulatedConveyor {
+    fn kind(&self) -> ControllerKind {
+        ControllerKind::Simulator
+    }
+
+    fn is_connected(&self) -> bool {
+        true
+    }
+
+    async fn jog_forward(&self) -> Result<(), String> {
+        let _motion = self.shared.motion.lock().await;
+        self.travel(1).await;
+        Ok(())
+    }
+
+    async fn run_to_slot(&self, target_slot: i16) -> Result<RunRequestResult, String> {
+        let _motion = self.shared.motion.lock().await;
+        let started = Instant::now();
+
+        let state = self.state();
+        if target_slot < 1 || target_slot > state.total_slots() {
+            return Err(format!("Slot {} is not on the simulated conveyor (1-{})", target_slot, state.total_slots()));
+        }
+        self.update(|s| s.target_slot = target_slot);
+
+        tokio::time::sleep(Duration::from_millis(self.shared.settings.start_delay_ms)).await;
+        let distance = forward_distance(state.position.into(), target_slot.into(), state.total_slots().into());
+        self.travel(distance).await;
+        self.pulse_hanger_later();
+
+        let result = RunRequestResult {
+            target_slot,
+            outcome: RunRequestOutcome::Success,
+            detail: None,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        println!("[Simulator] slot {} reached in {} ms", target_slot, result.duration_ms);
+        Ok(result)
+    }
+
+    async fn set_number_of_frames(&self, num_frames: i16) -> Result<(), String> {
+        if num_frames < 1 {
+            return Err("Number of frames must be at least 1".into());
+        }
+        self.update(|s| {
+            s.num_frames = num_frames;
+            s.position = s.position.min(s.total_slots());
+        });
+        Ok(())
+    }
+
+    async fn set_slots_per_frame(&self, slots_per_frame: i16) -> Result<(), String> {
+        if slots_per_frame < 1 {
+            return Err("Slots per frame must be at least 1".into());
+        }
+        self.update(|s| {
+            s.slots_per_frame = slots_per_frame;
+            s.position = s.position.min(s.total_slots());
+        });
+        Ok(())
+    }
+
+    async fn target_slot(&self) -> Result<i64, String> {
+        Ok(self.state().target_slot.into())
+    }
+
+    async fn hanger_sensor(&self) -> Result<bool, String> {
+        Ok(self.state().hanger_present)
+    }
+
+    async fn watch_hanger_sensor(&self) -> Result<broadcast::Receiver<bool>, String> {
+        Ok(self.shared.hanger_tx.subscribe())
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{garment_repo, slot_repo::SlotRepo, snapshot_repo::SnapshotRepo, state_error::{StateError, StateResult}, ticket_repo};
+use crate::domain::session;
+use crate::model::{ConveyorSnapshot, Garment, GarmentState, NewConveyorSnapshot, Slot, SlotState, Ticket, TicketStatus};
+
+const SNAPSHOTS_LISTED: i64 = 50;
+
+/// Which part of the conveyor a clear empties.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ClearScope {
+    All,
+    /// Inclusive slot numbers.
+    SlotRange { start: i32, end: i32 },
+    Frame { frame_number: i32 },
+    Ticket { ticket: String },
+}
+
+impl ClearScope {
+    pub fn describe(&self) -> String {
+        match self {
+            ClearScope::All => "all slots".to_string(),
+            ClearScope::SlotRange { start, end } => format!("slots {}-{}", start, end),
+            ClearScope::Frame { frame_number } => format!("frame {}", frame_number),
+            ClearScope::Ticket { ticket } => format!("ticket {}", ticket),
+        }
+    }
+
+    /// The slots to free and the garments to take off for this scope.
+    /// Blocked slots are never freed, but garments on them still come off.
+    pub fn select<'a>(&self, slots: &'a [Slot], on_conveyor: &'a [Garment]) -> (Vec<&'a Slot>, Vec<&'a Garment>) {
+        let in_scope: Vec<&Slot> = slots
+            .iter()
+            .filter(|s| match self {
+                ClearScope::All => true,
+                ClearScope::SlotRange { start, end } => (*start..=*end).contains(&s.slot_number),
+                ClearScope::Frame { frame_number } => s.frame_number == *frame_number,
+                ClearScope::Ticket { ticket } => s.assigned_ticket.as_deref() == Some(ticket.as_str()),
+            })
+            .collect();
+        let slot_numbers: HashSet<i32> = in_scope.iter().map(|s| s.slot_number).collect();
+
+        let garments: Vec<&Garment> = on_conveyor
+            .iter()
+            .filter(|g| g.slot_number != -1)
+            .filter(|g| match self {
+                ClearScope::Ticket { ticket } => g.full_invoice_number == *ticket,
+                _ => slot_numbers.contains(&g.slot_number),
+            })
+            .collect();
+
+        let to_free = in_scope
+            .into_iter()
+            .filter(|s| s.slot_state != SlotState::Blocked.as_str())
+            .filter(|s| s.slot_state != SlotState::Empty.as_str() || s.assigned_ticket.is_some() || s.garment_count > 0)
+            .collect();
+
+        (to_free, garments)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SlotSnapshot {
+    slot_number: i32,
+    slot_state: String,
+    assigned_ticket: Option<String>,
+    item_id: Option<String>,
+    garment_count: i32,
+    reserved_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GarmentSnapshot {
+    item_id: String,
+    slot_number: i32,
+    garment_state: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TicketSnapshot {
+    full_invoice_number: String,
+    garments_processed: i32,
+    ticket_status: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotPayload {
+    slots: Vec<SlotSnapshot>,
+    garments: Vec<GarmentSnapshot>,
+    tickets: Vec<TicketSnapshot>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ClearReport {
+    /// None when there was nothing to clear.
+    pub snapshot_id: Option<i32>,
+    pub freed_slots: Vec<i32>,
+    pub garments_removed: usize,
+    /// Tickets that lost every garment and went back to Not Processed.
+    pub tickets_reset: Vec<String>,
+}
+
+/// Empties the part of the conveyor in `scope`. Everything touched is
+/// snapshotted first; only tickets with garments or slots in scope change.
+pub fn clear_conveyor(conn: &mut PgConnection, scope: &ClearScope) -> StateResult<ClearReport> {
+    let reason = format!("conveyor cleared ({})", scope.describe());
+
+    conn.transaction::<ClearReport, StateError, _>(|conn| {
+        let slots = SlotRepo::list_all(conn)?;
+        let on_conveyor = garment_repo::list_garments_on_conveyor(conn)?;
+        let (to_free, garments) = scope.select(&slots, &on_conveyor);
+
+        if to_free.is_empty() && garments.is_empty() {
+            return Ok(ClearReport::default());
+        }
+
+        let invoices: Vec<String> = to_free
+            .iter()
+            .filter_map(|s| s.assigned_ticket.clone())
+            .chain(garments.iter().map(|g| g.full_invoice_number.clone()))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let tickets = ticket_repo::list_tickets_by_invoice(conn, &invoices)?;
+
+        let payload = SnapshotPayload {
+            slots: to_free
+                .iter()
+                .map(|s| SlotSnapshot {
+                    slot_number: s.slot_number,
+                    slot_state: s.slot_state.clone(),
+                    assigned_ticket: s.assigned_ticket.clone(),
+                    item_id: s.item_id.clone(),
+                    garment_count: s.garment_count,
+                    reserved_at: s.reserved_at,
+                })
+                .collect(),
+            garments: garments
+                .iter()
+                .map(|g| GarmentSnapshot {
+                    item_id: g.item_id.clone(),
+                    slot_number: g.slot_number,
+                    garment_state: g.garment_state.clone(),
+                })
+                .collect(),
+            tickets: tickets
+                .iter()
+                .map(|t| TicketSnapshot {
+                    full_invoice_number: t.full_invoice_number.clone(),
+                    garments_processed: t.garments_processed,
+                    ticket_status: t.ticket_status.clone(),
+                })
+                .collect(),
+        };
+
+        let snapshot = SnapshotRepo::insert(conn, &NewConveyorSnapshot {
+            scope: scope.describe(),
+            payload: serde_json::to_string(&payload)
+                .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?,
+            slot_count: payload.slots.len() as i32,
+            garment_count: payload.garments.len() as i32,
+            created_by: session::active_session().map(|s| s.user_id),
+        })?;
+
+        let mut report = ClearReport { snapshot_id: Some(snapshot.id), ..Default::default() };
+
+        for slot in &to_free {
+            SlotRepo::free_slot(conn, slot.slot_number, &reason)?;
+            report.freed_slots.push(slot.slot_number);
+        }
+
+        let mut removed: HashMap<&str, i32> = HashMap::new();
+        for g in &garments {
+            garment_repo::move_garment(conn, &g.item_id, -1)?;
+            if g.garment_state == GarmentState::Processing.as_str() {
+                garment_repo::set_garment_state(conn, &g.item_id, GarmentState::NotProcessed)?;
+            }
+            *removed.entry(g.full_invoice_number.as_str()).or_default() += 1;
+        }
+        report.garments_removed = garments.len();
+
+        // Finished tickets keep their status; tickets mid-load lose what came off
+        for ticket in tickets.iter().filter(|t| t.ticket_status == TicketStatus::Processing.as_str()) {
+            let invoice = ticket.full_invoice_number.as_str();
+            let remaining = garment_repo::count_ticket_garments_on_conveyor(conn, invoice)?;
+
+            if remaining == 0 && !SlotRepo::ticket_on_conveyor(conn, invoice)? {
+                ticket_repo::reset_ticket_progress(conn, invoice)?;
+                report.tickets_reset.push(invoice.to_string());
+            } else {
+                let taken = removed.get(invoice).copied().unwrap_or(0);
+                ticket_repo::set_garments_processed(conn, invoice, (ticket.garments_processed - taken).max(0))?;
+            }
+        }
+
+        Ok(report)
+    })
+}
+
+pub fn list_snapshots(conn: &mut PgConnection) -> QueryResult<Vec<ConveyorSnapshot>> {
+    SnapshotRepo::list_recent(conn, SNAPSHOTS_LISTED)
+}
+
+/// What has been used since the snapshot was taken. The rows are locked so
+/// nothing can claim them before the restore commits.
+fn restore_conflicts(conn: &mut PgConnection, payload: &SnapshotPayload) -> QueryResult<Vec<String>> {
+    let mut conflicts = Vec::new();
+
+    let slot_numbers: Vec<i32> = payload.slots.iter().map(|s| s.slot_number).collect();
+    let slots: HashMap<i32, Slot> = SlotRepo::lock(conn, &slot_numbers)?
+        .into_iter()
+        .map(|s| (s.slot_number, s))
+        .collect();
+    for saved in &payload.slots {
+        match slots.get(&saved.slot_number) {
+            None => conflicts.push(format!("slot {} no longer exists", saved.slot_number)),
+            Some(slot) if slot.slot_state != SlotState::Empty.as_str() || slot.assigned_ticket.is_some() || slot.garment_count > 0 => {
+                conflicts.push(format!("slot {} is {} again", saved.slot_number, slot.slot_state))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let item_ids: Vec<String> = payload.garments.iter().map(|g| g.item_id.clone()).collect();
+    let garments: HashMap<String, Garment> = garment_repo::lock_garments(conn, &item_ids)?
+        .into_iter()
+        .map(|g| (g.item_id.clone(), g))
+        .collect();
+    for saved in &payload.garments {
+        match garments.get(&saved.item_id) {
+            None => conflicts.push(format!("garment {} no longer exists", saved.item_id)),
+            Some(garment) if garment.slot_number != -1 => {
+                conflicts.push(format!("garment {} is back on slot {}", saved.item_id, garment.slot_number))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let invoices: Vec<String> = payload.tickets.iter().map(|t| t.full_invoice_number.clone()).collect();
+    let tickets: HashMap<String, Ticket> = ticket_repo::lock_tickets(conn, &invoices)?
+        .into_iter()
+        .map(|t| (t.full_invoice_number.clone(), t))
+        .collect();
+    for saved in &payload.tickets {
+        let Some(ticket) = tickets.get(&saved.full_invoice_number) else {
+            conflicts.push(format!("ticket {} no longer exists", saved.full_invoice_number));
+            continue;
+        };
+        let (Some(now), Some(then)) = (
+            TicketStatus::from_db(&ticket.ticket_status),
+            TicketStatus::from_db(&saved.ticket_status),
+        ) else {
+            continue;
+        };
+        if let Err(e) = now.transition(then) {
+            conflicts.push(format!("ticket {}: {}", saved.full_invoice_number, e));
+        }
+    }
+
+    Ok(conflicts)
+}
+
+/// Puts back everything a clear removed. Refused if any of the slots,
+/// garments or tickets have been used since, so nothing newer is overwritten.
+pub fn restore_snapshot(conn: &mut PgConnection, snapshot_id: i32) -> Result<(), String> {
+    let reason = format!("restored from snapshot {}", snapshot_id);
+
+    // The outer error is a failed write, the inner one a refused restore
+    conn.transaction::<Result<(), String>, StateError, _>(|conn| {
+        let Some(snapshot) = SnapshotRepo::get_for_update(conn, snapshot_id)? else {
+            return Ok(Err(format!("Snapshot {} does not exist", snapshot_id)));
+        };
+        if snapshot.restored_at.is_some() {
+            return Ok(Err(format!("Snapshot {} has already been restored", snapshot_id)));
+        }
+        let payload: SnapshotPayload = match serde_json::from_str(&snapshot.payload) {
+            Ok(payload) => payload,
+            Err(e) => return Ok(Err(format!("Snapshot {} is unreadable: {}", snapshot_id, e))),
+        };
+
+        let conflicts = restore_conflicts(conn, &payload)?;
+        if !conflicts.is_empty() {
+            return Ok(Err(format!("Cannot restore snapshot {}: {}", snapshot_id, conflicts.join("; "))));
+        }
+
+        for saved in &payload.slots {
+            let state = SlotState::from_db(&saved.slot_state).unwrap_or(SlotState::Error);
+            SlotRepo::restore(
+                conn,
+                saved.slot_number,
+                state,
+                saved.assigned_ticket.as_deref(),
+                saved.item_id.as_deref(),
+                saved.garment_count,
+                saved.reserved_at,
+                &reason,
+            )?;
+        }
+        for saved in &payload.garments {
+            garment_repo::restore_garment(conn, &saved.item_id, saved.slot_number, &saved.garment_state)?;
+        }
+        for saved in &payload.tickets {
+            ticket_repo::restore_ticket_progress(conn, &saved.full_invoice_number, saved.garments_processed, &saved.ticket_status)?;
+        }
+        SnapshotRepo::mark_restored(conn, snapshot_id, session::active_session().map(|s| s.user_id))?;
+        Ok(Ok(()))
+    })
+    .map_err(|e| e.to_string())?
+}
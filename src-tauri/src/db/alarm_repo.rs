@@ -0,0 +1,59 @@
+use diesel::prelude::*;
+use diesel::{PgConnection, QueryResult};
+
+use crate::model::{Alarm, NewAlarm};
+use crate::schema::alarms;
+
+pub struct AlarmRepo;
+
+impl AlarmRepo {
+    pub fn insert(conn: &mut PgConnection, alarm: &NewAlarm) -> QueryResult<Alarm> {
+        diesel::insert_into(alarms::table)
+            .values(alarm)
+            .get_result(conn)
+    }
+
+    /// Alarms of `alarm_source` that have not cleared yet.
+    pub fn list_uncleared(conn: &mut PgConnection, alarm_source: &str) -> QueryResult<Vec<Alarm>> {
+        use crate::schema::alarms::dsl::*;
+        alarms
+            .filter(source.eq(alarm_source))
+            .filter(cleared_at.is_null())
+            .order(id.asc())
+            .load::<Alarm>(conn)
+    }
+
+    pub fn clear(conn: &mut PgConnection, alarm_id: i32) -> QueryResult<Alarm> {
+        use crate::schema::alarms::dsl::*;
+        diesel::update(alarms.filter(id.eq(alarm_id)))
+            .set(cleared_at.eq(diesel::dsl::now.nullable()))
+            .get_result(conn)
+    }
+
+    /// Returns None if the alarm does not exist or was already acknowledged.
+    pub fn acknowledge(conn: &mut PgConnection, alarm_id: i32, user: Option<i32>) -> QueryResult<Option<Alarm>> {
+        use crate::schema::alarms::dsl::*;
+        diesel::update(alarms.filter(id.eq(alarm_id)).filter(acknowledged_at.is_null()))
+            .set((acknowledged_at.eq(diesel::dsl::now.nullable()), acknowledged_by.eq(user)))
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Uncleared or unacknowledged alarms, newest first.
+    pub fn list_active(conn: &mut PgConnection) -> QueryResult<Vec<Alarm>> {
+        use crate::schema::alarms::dsl::*;
+        alarms
+            .filter(cleared_at.is_null().or(acknowledged_at.is_null()))
+            .order((raised_at.desc(), id.desc()))
+            .load::<Alarm>(conn)
+    }
+
+    /// Most recent first.
+    pub fn list_recent(conn: &mut PgConnection, limit: i64) -> QueryResult<Vec<Alarm>> {
+        use crate::schema::alarms::dsl::*;
+        alarms
+            .order((raised_at.desc(), id.desc()))
+            .limit(limit)
+            .load::<Alarm>(conn)
+    }
+}
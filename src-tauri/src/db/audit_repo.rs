@@ -0,0 +1,106 @@
+use diesel::prelude::*;
+use diesel::{OptionalExtension, PgConnection, QueryResult};
+
+use crate::model::{
+    AuditDiscrepancyRow, AuditStatus, InventoryAudit, InventoryAuditScan, NewAuditDiscrepancy,
+    NewInventoryAudit, NewInventoryAuditScan,
+};
+use crate::schema::{inventory_audit_discrepancies, inventory_audits};
+
+pub struct AuditRepo;
+
+impl AuditRepo {
+    pub fn create(conn: &mut PgConnection, started_by: Option<i32>) -> QueryResult<InventoryAudit> {
+        diesel::insert_into(inventory_audits::table)
+            .values(NewInventoryAudit { started_by })
+            .get_result(conn)
+    }
+
+    pub fn get(conn: &mut PgConnection, audit_id: i32) -> QueryResult<Option<InventoryAudit>> {
+        use crate::schema::inventory_audits::dsl::*;
+        inventory_audits
+            .filter(id.eq(audit_id))
+            .first::<InventoryAudit>(conn)
+            .optional()
+    }
+
+    pub fn find_open(conn: &mut PgConnection) -> QueryResult<Option<InventoryAudit>> {
+        use crate::schema::inventory_audits::dsl::*;
+        inventory_audits
+            .filter(audit_status.eq(AuditStatus::Open.as_str()))
+            .order(id.desc())
+            .first::<InventoryAudit>(conn)
+            .optional()
+    }
+
+    /// Most recent first.
+    pub fn list_recent(conn: &mut PgConnection, limit: i64) -> QueryResult<Vec<InventoryAudit>> {
+        use crate::schema::inventory_audits::dsl::*;
+        inventory_audits
+            .order(id.desc())
+            .limit(limit)
+            .load::<InventoryAudit>(conn)
+    }
+
+    /// Records a scan; scanning the same garment again moves it to the new slot.
+    pub fn record_scan(conn: &mut PgConnection, scan: &NewInventoryAuditScan) -> QueryResult<InventoryAuditScan> {
+        use crate::schema::inventory_audit_scans::dsl::*;
+        diesel::insert_into(inventory_audit_scans)
+            .values(scan)
+            .on_conflict((audit_id, item_id))
+            .do_update()
+            .set((slot_number.eq(scan.slot_number), scanned_at.eq(diesel::dsl::now)))
+            .get_result(conn)
+    }
+
+    pub fn scans(conn: &mut PgConnection, audit: i32) -> QueryResult<Vec<InventoryAuditScan>> {
+        use crate::schema::inventory_audit_scans::dsl::*;
+        inventory_audit_scans
+            .filter(audit_id.eq(audit))
+            .order((slot_number.asc(), id.asc()))
+            .load::<InventoryAuditScan>(conn)
+    }
+
+    pub fn save_discrepancies(conn: &mut PgConnection, rows: &[NewAuditDiscrepancy]) -> QueryResult<usize> {
+        diesel::insert_into(inventory_audit_discrepancies::table)
+            .values(rows)
+            .execute(conn)
+    }
+
+    pub fn discrepancies(conn: &mut PgConnection, audit: i32) -> QueryResult<Vec<AuditDiscrepancyRow>> {
+        use crate::schema::inventory_audit_discrepancies::dsl::*;
+        inventory_audit_discrepancies
+            .filter(audit_id.eq(audit))
+            .order(id.asc())
+            .load::<AuditDiscrepancyRow>(conn)
+    }
+
+    pub fn mark_finished(conn: &mut PgConnection, audit: i32) -> QueryResult<()> {
+        use crate::schema::inventory_audits::dsl::*;
+        diesel::update(inventory_audits.filter(id.eq(audit)))
+            .set((
+                audit_status.eq(AuditStatus::Finished.as_str()),
+                finished_at.eq(diesel::dsl::now.nullable()),
+            ))
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    /// Marks a finished audit applied. Returns false if it was not finished,
+    /// e.g. because another apply got there first.
+    pub fn mark_applied(conn: &mut PgConnection, audit: i32, user: Option<i32>) -> QueryResult<bool> {
+        use crate::schema::inventory_audits::dsl::*;
+        diesel::update(
+            inventory_audits
+                .filter(id.eq(audit))
+                .filter(audit_status.eq(AuditStatus::Finished.as_str())),
+        )
+        .set((
+            audit_status.eq(AuditStatus::Applied.as_str()),
+            applied_by.eq(user),
+            applied_at.eq(diesel::dsl::now.nullable()),
+        ))
+        .execute(conn)
+        .map(|rows| rows == 1)
+    }
+}
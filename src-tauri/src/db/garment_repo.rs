@@ -1,6 +1,6 @@
 use diesel::prelude::*;
 
-use crate::model::{Garment, NewGarment};
+use crate::model::{Garment, GarmentState, NewGarment};
 use crate::schema::garments;
 use crate::schema::garments::dsl::*;
 
@@ -58,3 +58,108 @@ pub fn update_garment_slot(conn: &mut PgConnection, barcode: &str, slot_num: i32
         .map_err(|e| e.to_string())
 }
 
+pub fn count_garments_in_slot(conn: &mut PgConnection, slot_num: i32) -> QueryResult<i64> {
+    garments
+        .filter(slot_number.eq(slot_num))
+        .count()
+        .get_result(conn)
+}
+
+/// Moves a garment to `slot_num` (-1 for off the conveyor) and returns the slot it was on.
+pub fn move_garment(conn: &mut PgConnection, barcode: &str, slot_num: i32) -> QueryResult<i32> {
+    let previous = garments
+        .filter(item_id.eq(barcode))
+        .select(slot_number)
+        .first::<i32>(conn)?;
+
+    diesel::update(garments.filter(item_id.eq(barcode)))
+        .set(slot_number.eq(slot_num))
+        .execute(conn)?;
+
+    Ok(previous)
+}
+
+/// The most recently created garment still hanging on a slot.
+pub fn latest_garment_in_slot(conn: &mut PgConnection, slot_num: i32) -> QueryResult<Option<String>> {
+    garments
+        .filter(slot_number.eq(slot_num))
+        .order(id.desc())
+        .select(item_id)
+        .first::<String>(conn)
+        .optional()
+}
+
+/// Garments hanging on one slot, oldest first.
+pub fn list_garments_in_slot(conn: &mut PgConnection, slot_num: i32) -> QueryResult<Vec<Garment>> {
+    garments
+        .filter(slot_number.eq(slot_num))
+        .order(id.asc())
+        .load::<Garment>(conn)
+}
+
+/// Every garment whose slot number is not -1.
+pub fn list_garments_on_conveyor(conn: &mut PgConnection) -> QueryResult<Vec<Garment>> {
+    garments
+        .filter(slot_number.ne(-1))
+        .order(id.asc())
+        .load::<Garment>(conn)
+}
+
+/// Moves a garment to `to`, rejecting changes the garment state machine does not allow.
+pub fn set_garment_state(conn: &mut PgConnection, barcode: &str, to: GarmentState) -> QueryResult<()> {
+    let current: String = garments
+        .filter(item_id.eq(barcode))
+        .select(garment_state)
+        .first(conn)?;
+
+    if let Some(from) = GarmentState::from_db(&current) {
+        from.transition(to)
+            .map_err(|e| diesel::result::Error::QueryBuilderError(e.into()))?;
+    }
+
+    diesel::update(garments.filter(item_id.eq(barcode)))
+        .set(garment_state.eq(to.as_str()))
+        .execute(conn)
+        .map(|_| ())
+}
+
+/// Every garment on a reopened ticket back to Not Processed.
+pub fn reset_garment_states(conn: &mut PgConnection, invoice_number: &str) -> QueryResult<usize> {
+    diesel::update(garments.filter(full_invoice_number.eq(invoice_number)))
+        .set(garment_state.eq(GarmentState::NotProcessed.as_str()))
+        .execute(conn)
+}
+
+/// Status of the ticket a garment belongs to.
+pub fn get_garment_ticket_status(conn: &mut PgConnection, barcode: &str) -> QueryResult<String> {
+    use crate::schema::tickets;
+    garments
+        .inner_join(tickets::table.on(tickets::full_invoice_number.eq(full_invoice_number)))
+        .filter(item_id.eq(barcode))
+        .select(tickets::ticket_status)
+        .first(conn)
+}
+
+/// Loads and row-locks the given garments until the transaction ends.
+pub fn lock_garments(conn: &mut PgConnection, barcodes: &[String]) -> QueryResult<Vec<Garment>> {
+    garments
+        .filter(item_id.eq_any(barcodes))
+        .order(id.asc())
+        .for_update()
+        .load::<Garment>(conn)
+}
+
+/// Puts a garment back on `slot_num` in `state`, as captured before a conveyor clear.
+pub fn restore_garment(conn: &mut PgConnection, barcode: &str, slot_num: i32, state: &str) -> QueryResult<usize> {
+    diesel::update(garments.filter(item_id.eq(barcode)))
+        .set((slot_number.eq(slot_num), garment_state.eq(state)))
+        .execute(conn)
+}
+
+pub fn count_ticket_garments_on_conveyor(conn: &mut PgConnection, invoice_number: &str) -> QueryResult<i64> {
+    garments
+        .filter(full_invoice_number.eq(invoice_number))
+        .filter(slot_number.ne(-1))
+        .count()
+        .get_result(conn)
+}
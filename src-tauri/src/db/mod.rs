@@ -5,6 +5,13 @@ pub mod garment_repo;
 pub mod customer_repo;
 pub mod ticket_repo;
 pub mod slot_repo;
+pub mod state_error;
+pub mod slot_history_repo;
+pub mod snapshot_repo;
+pub mod audit_repo;
+pub mod run_request_repo;
+pub mod alarm_repo;
+pub mod motion_profile_repo;
 pub mod app_state_repo;
 pub mod sessions_repo;
 pub mod db_migrations;
@@ -0,0 +1,69 @@
+use diesel::prelude::*;
+use diesel::{PgConnection, QueryResult};
+
+use crate::model::{MotionProfile, MotionProfileApplication, NewMotionProfile, NewMotionProfileApplication};
+use crate::schema::motion_profile_applications;
+
+pub struct MotionProfileRepo;
+
+impl MotionProfileRepo {
+    pub fn list_all(conn: &mut PgConnection) -> QueryResult<Vec<MotionProfile>> {
+        use crate::schema::motion_profiles::dsl::*;
+        motion_profiles.order(name.asc()).load::<MotionProfile>(conn)
+    }
+
+    pub fn find_by_id(conn: &mut PgConnection, profile_id: i32) -> QueryResult<Option<MotionProfile>> {
+        use crate::schema::motion_profiles::dsl::*;
+        motion_profiles
+            .filter(id.eq(profile_id))
+            .first::<MotionProfile>(conn)
+            .optional()
+    }
+
+    /// Creates the profile, or updates the values of the one with the same name.
+    pub fn upsert(conn: &mut PgConnection, profile: &NewMotionProfile) -> QueryResult<MotionProfile> {
+        use crate::schema::motion_profiles::dsl::*;
+        diesel::insert_into(motion_profiles)
+            .values(profile)
+            .on_conflict(name)
+            .do_update()
+            .set((
+                speed.eq(profile.speed),
+                acceleration.eq(profile.acceleration),
+                updated_at.eq(diesel::dsl::now),
+            ))
+            .get_result(conn)
+    }
+
+    pub fn delete(conn: &mut PgConnection, profile_id: i32) -> QueryResult<usize> {
+        use crate::schema::motion_profiles::dsl::*;
+        diesel::delete(motion_profiles.filter(id.eq(profile_id))).execute(conn)
+    }
+
+    pub fn insert_application(
+        conn: &mut PgConnection,
+        application: &NewMotionProfileApplication,
+    ) -> QueryResult<MotionProfileApplication> {
+        diesel::insert_into(motion_profile_applications::table)
+            .values(application)
+            .get_result(conn)
+    }
+
+    /// The most recently applied profile.
+    pub fn latest_application(conn: &mut PgConnection) -> QueryResult<Option<MotionProfileApplication>> {
+        use crate::schema::motion_profile_applications::dsl::*;
+        motion_profile_applications
+            .order((applied_at.desc(), id.desc()))
+            .first::<MotionProfileApplication>(conn)
+            .optional()
+    }
+
+    /// Most recent first.
+    pub fn list_applications(conn: &mut PgConnection, limit: i64) -> QueryResult<Vec<MotionProfileApplication>> {
+        use crate::schema::motion_profile_applications::dsl::*;
+        motion_profile_applications
+            .order((applied_at.desc(), id.desc()))
+            .limit(limit)
+            .load::<MotionProfileApplication>(conn)
+    }
+}
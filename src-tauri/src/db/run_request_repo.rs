@@ -0,0 +1,24 @@
+use diesel::prelude::*;
+use diesel::{PgConnection, QueryResult};
+
+use crate::model::{NewRunRequest, RunRequest};
+use crate::schema::run_requests;
+
+pub struct RunRequestRepo;
+
+impl RunRequestRepo {
+    pub fn insert(conn: &mut PgConnection, request: &NewRunRequest) -> QueryResult<RunRequest> {
+        diesel::insert_into(run_requests::table)
+            .values(request)
+            .get_result(conn)
+    }
+
+    /// Most recent first.
+    pub fn list_recent(conn: &mut PgConnection, limit: i64) -> QueryResult<Vec<RunRequest>> {
+        use crate::schema::run_requests::dsl::*;
+        run_requests
+            .order((requested_at.desc(), id.desc()))
+            .limit(limit)
+            .load::<RunRequest>(conn)
+    }
+}
@@ -0,0 +1,55 @@
+use diesel::prelude::*;
+use diesel::{PgConnection, QueryResult};
+
+use crate::domain::session;
+use crate::model::{NewSlotHistory, SlotHistory};
+use crate::schema::slot_history;
+
+pub struct SlotHistoryRepo;
+
+impl SlotHistoryRepo {
+    /// Appends a history row, attributed to the operator currently logged in.
+    pub fn record(
+        conn: &mut PgConnection,
+        slot_number: i32,
+        from_state: Option<&str>,
+        to_state: &str,
+        ticket: Option<&str>,
+        item_id: Option<&str>,
+        reason: &str,
+    ) -> QueryResult<()> {
+        let active = session::active_session();
+
+        diesel::insert_into(slot_history::table)
+            .values(NewSlotHistory {
+                slot_number,
+                from_state: from_state.map(|s| s.to_string()),
+                to_state: to_state.to_string(),
+                ticket: ticket.map(|s| s.to_string()),
+                item_id: item_id.map(|s| s.to_string()),
+                user_id: active.map(|s| s.user_id),
+                session_id: active.map(|s| s.session_id),
+                reason: reason.to_string(),
+            })
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Every change to a slot, oldest first.
+    pub fn for_slot(conn: &mut PgConnection, num: i32) -> QueryResult<Vec<SlotHistory>> {
+        use crate::schema::slot_history::dsl::*;
+        slot_history
+            .filter(slot_number.eq(num))
+            .order((changed_at.asc(), id.asc()))
+            .load::<SlotHistory>(conn)
+    }
+
+    /// Every slot change made on behalf of a ticket, oldest first.
+    pub fn for_ticket(conn: &mut PgConnection, invoice: &str) -> QueryResult<Vec<SlotHistory>> {
+        use crate::schema::slot_history::dsl::*;
+        slot_history
+            .filter(ticket.eq(invoice))
+            .order((changed_at.asc(), id.asc()))
+            .load::<SlotHistory>(conn)
+    }
+}
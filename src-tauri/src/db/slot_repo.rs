@@ -2,16 +2,45 @@
 use diesel::prelude::*;
 use diesel::{OptionalExtension, PgConnection, QueryResult};
 
-use crate::model::Slot;
+use crate::db::{garment_repo, slot_history_repo::SlotHistoryRepo, state_error::{StateError, StateResult}};
+use crate::model::{NewSlot, Slot, SlotState};
+
+/// `to_state` of the history row written when a slot is deleted.
+pub const DELETED_STATE: &str = "deleted";
+
+/// Reason recorded when a slot is deleted because its position was removed.
+pub const RETIRED_DELETE_REASON: &str = "removed from frame configuration";
 
 pub struct SlotRepo;
 
 impl SlotRepo {
+    /// Rejects state changes the slot state machine does not allow.
+    fn check_transition(before: Option<&Slot>, to: SlotState) -> StateResult<()> {
+        let Some(slot) = before else { return Ok(()) };
+        match SlotState::from_db(&slot.slot_state) {
+            Some(from) => from
+                .transition(to)
+                .map(|_| ())
+                .map_err(|e| StateError::Rejected(format!("Slot {}: {}", slot.slot_number, e))),
+            None => Ok(()),
+        }
+    }
+
     pub fn get(conn: &mut PgConnection, num: i32) -> QueryResult<Option<Slot>> {
         use crate::schema::slots::dsl::*;
         slots.filter(slot_number.eq(num)).first::<Slot>(conn).optional()
     }
 
+    /// Loads and row-locks the given slots until the transaction ends.
+    pub fn lock(conn: &mut PgConnection, nums: &[i32]) -> QueryResult<Vec<Slot>> {
+        use crate::schema::slots::dsl::*;
+        slots
+            .filter(slot_number.eq_any(nums))
+            .order(slot_number.asc())
+            .for_update()
+            .load::<Slot>(conn)
+    }
+
     pub fn list_all(conn: &mut PgConnection) -> QueryResult<Vec<Slot>> {
         use crate::schema::slots::dsl::*;
         slots.order(slot_number.asc()).load::<Slot>(conn)
@@ -20,7 +49,8 @@ impl SlotRepo {
     pub fn find_first_empty(conn: &mut PgConnection) -> QueryResult<Option<Slot>> {
         use crate::schema::slots::dsl::*;
         slots
-            .filter(slot_state.eq("empty"))
+            .filter(slot_state.eq(SlotState::Empty.as_str()))
+            .filter(retired.eq(false))
             .order(slot_number.asc())
             .first::<Slot>(conn)
             .optional()
@@ -35,6 +65,14 @@ impl SlotRepo {
             .optional()
     }
 
+    pub fn find_ticket_slots(conn: &mut PgConnection, ticket: &str) -> QueryResult<Vec<Slot>> {
+        use crate::schema::slots::dsl::*;
+        slots
+            .filter(assigned_ticket.eq(ticket))
+            .order(slot_number.asc())
+            .load::<Slot>(conn)
+    }
+
     pub fn ticket_on_conveyor(conn: &mut PgConnection, ticket: &str) -> QueryResult<bool> {
         use crate::schema::slots::dsl::*;
         let count: i64 = slots
@@ -47,42 +85,77 @@ impl SlotRepo {
     pub fn free_slot(
         conn: &mut PgConnection,
         slot_number_val: i32,
-    ) -> QueryResult<()> {
+        reason: &str,
+    ) -> StateResult<()> {
         use crate::schema::slots::dsl::*;
 
+        let before = Self::get(conn, slot_number_val)?;
+        Self::check_transition(before.as_ref(), SlotState::Empty)?;
         diesel::update(slots.filter(slot_number.eq(slot_number_val)))
             .set((
-                slot_state.eq("empty"),
+                slot_state.eq(SlotState::Empty.as_str()),
                 assigned_ticket.eq::<Option<String>>(None),
                 item_id.eq::<Option<String>>(None),
+                garment_count.eq(0),
+                reserved_at.eq::<Option<chrono::NaiveDateTime>>(None),
+                state_reason.eq::<Option<String>>(None),
+                blocked_by_layout.eq(false),
                 updated_at.eq(diesel::dsl::now),
             ))
             .execute(conn)?;
 
-        Ok(())
+        Self::log_change(conn, before, None, reason)?;
+        Ok(Self::delete_if_retired(conn, slot_number_val)?)
+    }
+
+    /// Drops the ticket and garments recorded on a slot without changing its
+    /// state, so a blocked slot stays blocked. The state is left alone, so
+    /// there is no transition to check.
+    pub fn clear_assignment(conn: &mut PgConnection, num: i32, reason: &str) -> StateResult<()> {
+        use crate::schema::slots::dsl::*;
+        let before = Self::get(conn, num)?;
+        diesel::update(slots.filter(slot_number.eq(num)))
+            .set((
+                assigned_ticket.eq::<Option<String>>(None),
+                item_id.eq::<Option<String>>(None),
+                garment_count.eq(0),
+                reserved_at.eq::<Option<chrono::NaiveDateTime>>(None),
+                updated_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)?;
+        Ok(Self::log_change(conn, before, None, reason)?)
     }
 
-    /// Atomic reservation: only reserves if currently empty.
+    /// Atomic reservation: only reserves if currently empty and below `capacity`.
     /// Returns true if reservation succeeded, false if someone else got it.
     pub fn try_reserve(
         conn: &mut PgConnection,
         num: i32,
         ticket: Option<&str>,
+        capacity: i32,
+        reason: &str,
     ) -> QueryResult<bool> {
         use crate::schema::slots::dsl::*;
 
+        let before = Self::get(conn, num)?;
         let rows = diesel::update(
             slots
                 .filter(slot_number.eq(num))
-                .filter(slot_state.eq("empty")),
+                .filter(slot_state.eq(SlotState::Empty.as_str()))
+                .filter(retired.eq(false))
+                .filter(garment_count.lt(capacity)),
         )
         .set((
-            slot_state.eq("reserved"),
+            slot_state.eq(SlotState::Reserved.as_str()),
             assigned_ticket.eq(ticket.map(|s| s.to_string())),
+            reserved_at.eq(diesel::dsl::now.nullable()),
             updated_at.eq(diesel::dsl::now),
         ))
         .execute(conn)?;
 
+        if rows == 1 {
+            Self::log_change(conn, before, None, reason)?;
+        }
         Ok(rows == 1)
     }
 
@@ -91,40 +164,371 @@ impl SlotRepo {
         conn: &mut PgConnection,
         num: i32,
         ticket: Option<&str>,
-    ) -> QueryResult<()> {
+        reason: &str,
+    ) -> StateResult<()> {
         use crate::schema::slots::dsl::*;
 
+        let before = Self::get(conn, num)?;
+        Self::check_transition(before.as_ref(), SlotState::Occupied)?;
         diesel::update(slots.filter(slot_number.eq(num)))
             .set((
-                slot_state.eq("occupied"),
+                slot_state.eq(SlotState::Occupied.as_str()),
                 assigned_ticket.eq(ticket.map(|s| s.to_string())),
+                reserved_at.eq::<Option<chrono::NaiveDateTime>>(None),
                 updated_at.eq(diesel::dsl::now),
             ))
             .execute(conn)?;
 
-        Ok(())
+        Ok(Self::log_change(conn, before, None, reason)?)
     }
 
-    pub fn clear(conn: &mut PgConnection, num: i32) -> QueryResult<()> {
+    /// A garment was hung on the slot; `item` becomes the slot's latest item.
+    pub fn record_load(conn: &mut PgConnection, num: i32, item: &str) -> QueryResult<()> {
         use crate::schema::slots::dsl::*;
+        let before = Self::get(conn, num)?;
+        diesel::update(slots.filter(slot_number.eq(num)))
+            .set((
+                garment_count.eq(garment_count + 1),
+                item_id.eq(Some(item.to_string())),
+                updated_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)?;
+        Self::log_change(conn, before, Some(item), "garment loaded")
+    }
 
+    /// `item` was taken off the slot. `latest` is the item now on top, if any.
+    pub fn record_unload(conn: &mut PgConnection, num: i32, item: &str, latest: Option<&str>) -> QueryResult<()> {
+        use crate::schema::slots::dsl::*;
+        let before = Self::get(conn, num)?;
+        diesel::update(slots.filter(slot_number.eq(num)).filter(garment_count.gt(0)))
+            .set((
+                garment_count.eq(garment_count - 1),
+                item_id.eq(latest.map(|s| s.to_string())),
+                updated_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)?;
+        Self::log_change(conn, before, Some(item), "garment unloaded")
+    }
+
+    /// Overwrites the garment count and latest item, e.g. after recounting the garments table.
+    pub fn set_garment_count(
+        conn: &mut PgConnection,
+        num: i32,
+        count: i32,
+        latest: Option<&str>,
+        reason: &str,
+    ) -> QueryResult<()> {
+        use crate::schema::slots::dsl::*;
+        let before = Self::get(conn, num)?;
         diesel::update(slots.filter(slot_number.eq(num)))
             .set((
-                slot_state.eq("empty"),
+                garment_count.eq(count),
+                item_id.eq(latest.map(|s| s.to_string())),
+                updated_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)?;
+        Self::log_change(conn, before, None, reason)
+    }
+
+    /// Total garments hanging across all active slots.
+    pub fn total_garment_count(conn: &mut PgConnection) -> QueryResult<i64> {
+        use crate::schema::slots::dsl::*;
+        let total: Option<i64> = slots
+            .filter(retired.eq(false))
+            .select(diesel::dsl::sum(garment_count))
+            .first(conn)?;
+        Ok(total.unwrap_or(0))
+    }
+
+    /// Reserved slots with nothing loaded whose reservation is older than `ttl_secs`.
+    pub fn find_expired_reservations(conn: &mut PgConnection, ttl_secs: i32) -> QueryResult<Vec<Slot>> {
+        use crate::schema::slots::dsl::*;
+        use diesel::dsl::{now, IntervalDsl};
+        slots
+            .filter(slot_state.eq(SlotState::Reserved.as_str()))
+            .filter(garment_count.eq(0))
+            .filter(reserved_at.lt((now - ttl_secs.seconds()).nullable()))
+            .order(slot_number.asc())
+            .load::<Slot>(conn)
+    }
+
+    /// Releases a reservation back to empty, only if it is still an unloaded reservation.
+    /// Returns true if the slot was released.
+    pub fn release_reservation(conn: &mut PgConnection, num: i32, reason: &str) -> QueryResult<bool> {
+        use crate::schema::slots::dsl::*;
+        let before = Self::get(conn, num)?;
+        let rows = diesel::update(
+            slots
+                .filter(slot_number.eq(num))
+                .filter(slot_state.eq(SlotState::Reserved.as_str()))
+                .filter(garment_count.eq(0)),
+        )
+        .set((
+            slot_state.eq(SlotState::Empty.as_str()),
+            assigned_ticket.eq::<Option<String>>(None),
+            item_id.eq::<Option<String>>(None),
+            reserved_at.eq::<Option<chrono::NaiveDateTime>>(None),
+            updated_at.eq(diesel::dsl::now),
+        ))
+        .execute(conn)?;
+
+        if rows == 1 {
+            Self::log_change(conn, before, None, reason)?;
+            Self::delete_if_retired(conn, num)?;
+        }
+        Ok(rows == 1)
+    }
+
+    pub fn clear(conn: &mut PgConnection, num: i32, reason: &str) -> StateResult<()> {
+        use crate::schema::slots::dsl::*;
+
+        let before = Self::get(conn, num)?;
+        Self::check_transition(before.as_ref(), SlotState::Empty)?;
+        diesel::update(slots.filter(slot_number.eq(num)))
+            .set((
+                slot_state.eq(SlotState::Empty.as_str()),
                 assigned_ticket.eq::<Option<String>>(None),
                 item_id.eq::<Option<String>>(None),
+                garment_count.eq(0),
+                reserved_at.eq::<Option<chrono::NaiveDateTime>>(None),
+                state_reason.eq::<Option<String>>(None),
+                blocked_by_layout.eq(false),
+                updated_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)?;
+
+        Self::log_change(conn, before, None, reason)?;
+        Ok(Self::delete_if_retired(conn, num)?)
+    }
+
+    pub fn set_error(conn: &mut PgConnection, num: i32, reason: &str) -> StateResult<()> {
+        use crate::schema::slots::dsl::*;
+        let before = Self::get(conn, num)?;
+        Self::check_transition(before.as_ref(), SlotState::Error)?;
+        diesel::update(slots.filter(slot_number.eq(num)))
+            .set((
+                slot_state.eq(SlotState::Error.as_str()),
+                reserved_at.eq::<Option<chrono::NaiveDateTime>>(None),
+                state_reason.eq(Some(reason.to_string())),
+                updated_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)?;
+        Ok(Self::log_change(conn, before, None, reason)?)
+    }
+
+    /// Takes a slot out of service, only if it is empty or in error with no ticket
+    /// and nothing loaded. `by_layout` marks blocks made by the frame configuration,
+    /// which the topology sync may lift again. Returns true if the slot was blocked.
+    pub fn set_blocked(conn: &mut PgConnection, num: i32, reason: &str, by_layout: bool) -> StateResult<bool> {
+        use crate::schema::slots::dsl::*;
+        let before = Self::get(conn, num)?;
+        Self::check_transition(before.as_ref(), SlotState::Blocked)?;
+        let rows = diesel::update(
+            slots
+                .filter(slot_number.eq(num))
+                .filter(slot_state.eq_any([SlotState::Empty.as_str(), SlotState::Error.as_str()]))
+                .filter(assigned_ticket.is_null())
+                .filter(garment_count.eq(0)),
+        )
+        .set((
+            slot_state.eq(SlotState::Blocked.as_str()),
+            reserved_at.eq::<Option<chrono::NaiveDateTime>>(None),
+            state_reason.eq(Some(reason.to_string())),
+            blocked_by_layout.eq(by_layout),
+            updated_at.eq(diesel::dsl::now),
+        ))
+        .execute(conn)?;
+
+        if rows == 1 {
+            Self::log_change(conn, before, None, reason)?;
+        }
+        Ok(rows == 1)
+    }
+
+    /// The only way out of blocked: the slot goes back to empty.
+    pub fn set_unblocked(conn: &mut PgConnection, num: i32, reason: &str) -> StateResult<()> {
+        use crate::schema::slots::dsl::*;
+        let before = Self::get(conn, num)?;
+        if let Some(slot) = before.as_ref().filter(|s| s.slot_state != SlotState::Blocked.as_str()) {
+            return Err(StateError::Rejected(format!("Slot {} is {}, not blocked", num, slot.slot_state)));
+        }
+        diesel::update(slots.filter(slot_number.eq(num)).filter(slot_state.eq(SlotState::Blocked.as_str())))
+            .set((
+                slot_state.eq(SlotState::Empty.as_str()),
+                state_reason.eq::<Option<String>>(None),
+                blocked_by_layout.eq(false),
+                updated_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)?;
+        Ok(Self::log_change(conn, before, None, reason)?)
+    }
+
+    /// Writes a slot back as it was captured in a conveyor snapshot.
+    #[allow(clippy::too_many_arguments)]
+    pub fn restore(
+        conn: &mut PgConnection,
+        num: i32,
+        state: SlotState,
+        ticket: Option<&str>,
+        item: Option<&str>,
+        count: i32,
+        reserved: Option<chrono::NaiveDateTime>,
+        reason: &str,
+    ) -> StateResult<()> {
+        use crate::schema::slots::dsl::*;
+        let before = Self::get(conn, num)?;
+        Self::check_transition(before.as_ref(), state)?;
+        diesel::update(slots.filter(slot_number.eq(num)))
+            .set((
+                slot_state.eq(state.as_str()),
+                assigned_ticket.eq(ticket.map(|s| s.to_string())),
+                item_id.eq(item.map(|s| s.to_string())),
+                garment_count.eq(count),
+                reserved_at.eq(reserved),
                 updated_at.eq(diesel::dsl::now),
             ))
             .execute(conn)?;
+        Ok(Self::log_change(conn, before, None, reason)?)
+    }
+
+    /// Returns an error slot to service: occupied if garments are still on it,
+    /// otherwise empty. Returns false if the slot was not in error.
+    pub fn clear_error(conn: &mut PgConnection, num: i32, reason: &str) -> StateResult<bool> {
+        use crate::schema::slots::dsl::*;
+        let Some(before) = Self::get(conn, num)? else { return Ok(false) };
+        if before.slot_state != SlotState::Error.as_str() {
+            return Ok(false);
+        }
+        let to = if before.garment_count > 0 { SlotState::Occupied } else { SlotState::Empty };
+        Self::check_transition(Some(&before), to)?;
 
+        let target = diesel::update(slots.filter(slot_number.eq(num)).filter(slot_state.eq(SlotState::Error.as_str())));
+        let rows = if to == SlotState::Occupied {
+            target
+                .set((
+                    slot_state.eq(SlotState::Occupied.as_str()),
+                    state_reason.eq::<Option<String>>(None),
+                    updated_at.eq(diesel::dsl::now),
+                ))
+                .execute(conn)?
+        } else {
+            target
+                .set((
+                    slot_state.eq(SlotState::Empty.as_str()),
+                    assigned_ticket.eq::<Option<String>>(None),
+                    item_id.eq::<Option<String>>(None),
+                    state_reason.eq::<Option<String>>(None),
+                    updated_at.eq(diesel::dsl::now),
+                ))
+                .execute(conn)?
+        };
+
+        if rows == 1 {
+            Self::log_change(conn, Some(before), None, reason)?;
+            Self::delete_if_retired(conn, num)?;
+        }
+        Ok(rows == 1)
+    }
+
+    /// Blocked and error slots that still exist in the layout.
+    pub fn list_out_of_service(conn: &mut PgConnection) -> QueryResult<Vec<Slot>> {
+        use crate::schema::slots::dsl::*;
+        slots
+            .filter(slot_state.eq_any([SlotState::Blocked.as_str(), SlotState::Error.as_str()]))
+            .filter(retired.eq(false))
+            .order(slot_number.asc())
+            .load::<Slot>(conn)
+    }
+
+    pub fn insert(conn: &mut PgConnection, new_slot: &NewSlot, reason: &str) -> QueryResult<()> {
+        use crate::schema::slots;
+        diesel::insert_into(slots::table)
+            .values(new_slot)
+            .execute(conn)?;
+        SlotHistoryRepo::record(conn, new_slot.slot_number, None, &new_slot.slot_state, None, None, reason)
+    }
+
+    /// Deletes the slot and records the deletion in its history.
+    pub fn delete(conn: &mut PgConnection, num: i32, reason: &str) -> QueryResult<()> {
+        use crate::schema::slots::dsl::*;
+        let Some(before) = Self::get(conn, num)? else { return Ok(()) };
+        diesel::delete(slots.filter(slot_number.eq(num))).execute(conn)?;
+        SlotHistoryRepo::record(
+            conn,
+            num,
+            Some(&before.slot_state),
+            DELETED_STATE,
+            before.assigned_ticket.as_deref(),
+            before.item_id.as_deref(),
+            reason,
+        )
+    }
+
+    /// Deletes a retired slot as soon as nothing is left on it, instead of
+    /// waiting for the next frame configuration sync.
+    fn delete_if_retired(conn: &mut PgConnection, num: i32) -> QueryResult<()> {
+        let Some(slot) = Self::get(conn, num)? else { return Ok(()) };
+        let emptied = slot.retired
+            && slot.slot_state == SlotState::Empty.as_str()
+            && slot.assigned_ticket.is_none()
+            && slot.garment_count == 0
+            && garment_repo::count_garments_in_slot(conn, num)? == 0;
+        if emptied {
+            Self::delete(conn, num, RETIRED_DELETE_REASON)?;
+        }
         Ok(())
     }
 
-    pub fn set_blocked(conn: &mut PgConnection, num: i32) -> QueryResult<()> {
+    /// Updates the frame/position a slot belongs to and whether it is retired.
+    pub fn set_layout(
+        conn: &mut PgConnection,
+        num: i32,
+        frame: i32,
+        position: i32,
+        is_retired: bool,
+    ) -> QueryResult<()> {
         use crate::schema::slots::dsl::*;
         diesel::update(slots.filter(slot_number.eq(num)))
-            .set((slot_state.eq("blocked"), updated_at.eq(diesel::dsl::now)))
+            .set((
+                frame_number.eq(frame),
+                frame_position.eq(position),
+                retired.eq(is_retired),
+            ))
             .execute(conn)?;
         Ok(())
     }
+
+    /// Writes a history row if the slot changed since `before` was read.
+    /// The ticket and item fall back to the previous values so a freed slot
+    /// still shows which ticket left it.
+    fn log_change(
+        conn: &mut PgConnection,
+        before: Option<Slot>,
+        item: Option<&str>,
+        reason: &str,
+    ) -> QueryResult<()> {
+        let Some(before) = before else { return Ok(()) };
+        let Some(after) = Self::get(conn, before.slot_number)? else { return Ok(()) };
+
+        let unchanged = before.slot_state == after.slot_state
+            && before.assigned_ticket == after.assigned_ticket
+            && before.item_id == after.item_id
+            && before.garment_count == after.garment_count;
+        if unchanged {
+            return Ok(());
+        }
+
+        let ticket = after.assigned_ticket.as_deref().or(before.assigned_ticket.as_deref());
+        let item = item.or(after.item_id.as_deref()).or(before.item_id.as_deref());
+
+        SlotHistoryRepo::record(
+            conn,
+            after.slot_number,
+            Some(&before.slot_state),
+            &after.slot_state,
+            ticket,
+            item,
+            reason,
+        )
+    }
 }
@@ -0,0 +1,50 @@
+use diesel::prelude::*;
+use diesel::{OptionalExtension, PgConnection, QueryResult};
+
+use crate::model::{ConveyorSnapshot, NewConveyorSnapshot};
+use crate::schema::conveyor_snapshots;
+
+pub struct SnapshotRepo;
+
+impl SnapshotRepo {
+    pub fn insert(conn: &mut PgConnection, snapshot: &NewConveyorSnapshot) -> QueryResult<ConveyorSnapshot> {
+        diesel::insert_into(conveyor_snapshots::table)
+            .values(snapshot)
+            .get_result(conn)
+    }
+
+    pub fn get(conn: &mut PgConnection, snapshot_id: i32) -> QueryResult<Option<ConveyorSnapshot>> {
+        use crate::schema::conveyor_snapshots::dsl::*;
+        conveyor_snapshots
+            .filter(id.eq(snapshot_id))
+            .first::<ConveyorSnapshot>(conn)
+            .optional()
+    }
+
+    /// Like [`SnapshotRepo::get`], but holds a row lock until the transaction ends.
+    pub fn get_for_update(conn: &mut PgConnection, snapshot_id: i32) -> QueryResult<Option<ConveyorSnapshot>> {
+        use crate::schema::conveyor_snapshots::dsl::*;
+        conveyor_snapshots
+            .filter(id.eq(snapshot_id))
+            .for_update()
+            .first::<ConveyorSnapshot>(conn)
+            .optional()
+    }
+
+    /// Most recent first.
+    pub fn list_recent(conn: &mut PgConnection, limit: i64) -> QueryResult<Vec<ConveyorSnapshot>> {
+        use crate::schema::conveyor_snapshots::dsl::*;
+        conveyor_snapshots
+            .order((created_at.desc(), id.desc()))
+            .limit(limit)
+            .load::<ConveyorSnapshot>(conn)
+    }
+
+    pub fn mark_restored(conn: &mut PgConnection, snapshot_id: i32, user: Option<i32>) -> QueryResult<()> {
+        use crate::schema::conveyor_snapshots::dsl::*;
+        diesel::update(conveyor_snapshots.filter(id.eq(snapshot_id)))
+            .set((restored_at.eq(diesel::dsl::now.nullable()), restored_by.eq(user)))
+            .execute(conn)
+            .map(|_| ())
+    }
+}
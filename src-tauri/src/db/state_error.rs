@@ -0,0 +1,30 @@
+use std::fmt;
+
+/// Why a state-changing write failed: the state machine refused the change,
+/// or the database did. Refusals read as their own message, so callers can
+/// show them as they are.
+#[derive(Debug)]
+pub enum StateError {
+    /// The change is not allowed from the current state.
+    Rejected(String),
+    Db(diesel::result::Error),
+}
+
+pub type StateResult<T> = Result<T, StateError>;
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::Rejected(msg) => write!(f, "{msg}"),
+            StateError::Db(e) => write!(f, "DB Error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl From<diesel::result::Error> for StateError {
+    fn from(e: diesel::result::Error) -> Self {
+        StateError::Db(e)
+    }
+}
@@ -1,6 +1,7 @@
 use diesel::prelude::*;
 
-use crate::model::{NewTicket, Ticket, UpdateTicket};
+use crate::db::{garment_repo, state_error::{StateError, StateResult}};
+use crate::model::{NewTicket, Ticket, TicketStatus, UpdateTicket};
 use crate::schema::tickets;
 use crate::schema::tickets::dsl::*;
 
@@ -28,6 +29,14 @@ pub fn get_ticket_by_invoice_number(conn: &mut PgConnection, invoice_number: &st
         .map_err(|e| e.to_string())
 }
 
+pub fn get_pickup_date(conn: &mut PgConnection, invoice_number: &str) -> QueryResult<Option<chrono::NaiveDateTime>> {
+    tickets
+        .filter(full_invoice_number.eq(invoice_number))
+        .select(invoice_pickup_date)
+        .first::<chrono::NaiveDateTime>(conn)
+        .optional()
+}
+
 pub fn get_customer_from_ticket(conn: &mut PgConnection, invoice_number: &str) -> Result<Option<crate::model::Customer>, String> {
     use crate::schema::customers::dsl as customers_dsl;
     use crate::schema::tickets::dsl as tickets_dsl;
@@ -54,8 +63,26 @@ pub fn get_num_items_on_ticket(conn: &mut PgConnection, invoice_number: &str) ->
     Ok(ticket.number_of_items as i32)
 }
 
+/// Rejects status changes the ticket state machine does not allow.
+fn check_status_change(current: &str, new_status: &str) -> Result<(), String> {
+    let to = TicketStatus::from_db(new_status)
+        .ok_or_else(|| format!("Unknown ticket status: {}", new_status))?;
+    match TicketStatus::from_db(current) {
+        Some(from) => from.transition(to).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
 pub fn update_ticket(conn: &mut PgConnection, ticket_id: i32, updated_ticket: &UpdateTicket) -> Result<Ticket, String> {
     use crate::schema::tickets::dsl as tickets_dsl;
+    if let Some(new_status) = &updated_ticket.ticket_status {
+        let current: String = tickets_dsl::tickets
+            .filter(tickets_dsl::id.eq(ticket_id))
+            .select(tickets_dsl::ticket_status)
+            .first(conn)
+            .map_err(|e| e.to_string())?;
+        check_status_change(&current, new_status)?;
+    }
     diesel::update(tickets_dsl::tickets.filter(tickets_dsl::id.eq(ticket_id)))
         .set(updated_ticket)
         .get_result::<Ticket>(conn)
@@ -91,12 +118,90 @@ pub fn update_ticket_pickup_date(conn: &mut PgConnection, invoice_number: &str,
 
 pub fn update_ticket_status(conn: &mut PgConnection, invoice_number: &str, new_status: &str) -> Result<Ticket, String> {
     use crate::schema::tickets::dsl as tickets_dsl;
+    let current = get_ticket_by_invoice_number(conn, invoice_number)?.ticket_status;
+    check_status_change(&current, new_status)?;
     diesel::update(tickets_dsl::tickets.filter(tickets_dsl::full_invoice_number.eq(invoice_number)))
         .set(ticket_status.eq(new_status))
         .get_result::<Ticket>(conn)
         .map_err(|e| e.to_string())
 }
 
+/// Only the count changes, so there is no status change to check.
+pub fn set_garments_processed(conn: &mut PgConnection, invoice_number: &str, count: i32) -> QueryResult<usize> {
+    diesel::update(tickets.filter(full_invoice_number.eq(invoice_number)))
+        .set(garments_processed.eq(count))
+        .execute(conn)
+}
+
+/// A Processing ticket back to Not Processed with nothing scanned. Not run
+/// through `check_status_change`: the filter already limits it to Processing,
+/// which can always go back.
+pub fn reset_ticket_progress(conn: &mut PgConnection, invoice_number: &str) -> QueryResult<usize> {
+    diesel::update(
+        tickets
+            .filter(full_invoice_number.eq(invoice_number))
+            .filter(ticket_status.eq(TicketStatus::Processing.as_str())),
+    )
+    .set((garments_processed.eq(0), ticket_status.eq(TicketStatus::NotProcessed.as_str())))
+    .execute(conn)
+}
+
+/// The only way out of Complete/Processed: the ticket and its garments go
+/// back to Not Processed so they can be scanned again.
+pub fn reopen_ticket(conn: &mut PgConnection, invoice_number: &str) -> Result<Ticket, String> {
+    let ticket = get_ticket_by_invoice_number(conn, invoice_number)?;
+    if !TicketStatus::from_db(&ticket.ticket_status).is_some_and(TicketStatus::is_finished) {
+        return Err(format!("Ticket {} is {}, not complete", invoice_number, ticket.ticket_status));
+    }
+
+    conn.transaction::<Ticket, diesel::result::Error, _>(|conn| {
+        garment_repo::reset_garment_states(conn, invoice_number)?;
+        diesel::update(tickets.filter(id.eq(ticket.id)))
+            .set((garments_processed.eq(0), ticket_status.eq(TicketStatus::NotProcessed.as_str())))
+            .get_result::<Ticket>(conn)
+    })
+    .map_err(|e| e.to_string())
+}
+
+pub fn list_tickets_by_invoice(conn: &mut PgConnection, invoice_numbers: &[String]) -> QueryResult<Vec<Ticket>> {
+    tickets
+        .filter(full_invoice_number.eq_any(invoice_numbers))
+        .load::<Ticket>(conn)
+}
+
+/// Loads and row-locks the given tickets until the transaction ends.
+pub fn lock_tickets(conn: &mut PgConnection, invoice_numbers: &[String]) -> QueryResult<Vec<Ticket>> {
+    tickets
+        .filter(full_invoice_number.eq_any(invoice_numbers))
+        .order(id.asc())
+        .for_update()
+        .load::<Ticket>(conn)
+}
+
+/// Writes back the progress captured before a conveyor clear. Refused if the
+/// ticket has since finished and the saved status would reopen it.
+pub fn restore_ticket_progress(conn: &mut PgConnection, invoice_number: &str, processed: i32, status: &str) -> StateResult<usize> {
+    let current: Option<String> = tickets
+        .filter(full_invoice_number.eq(invoice_number))
+        .select(ticket_status)
+        .first(conn)
+        .optional()?;
+    if let Some(current) = current {
+        check_status_change(&current, status)
+            .map_err(|e| StateError::Rejected(format!("Ticket {}: {}", invoice_number, e)))?;
+    }
+    Ok(diesel::update(tickets.filter(full_invoice_number.eq(invoice_number)))
+        .set((garments_processed.eq(processed), ticket_status.eq(status)))
+        .execute(conn)?)
+}
+
+/// Tickets in `status` plus any ticket in `invoice_numbers`.
+pub fn list_tickets_with_status_or_in(conn: &mut PgConnection, status: &str, invoice_numbers: &[String]) -> QueryResult<Vec<Ticket>> {
+    tickets
+        .filter(ticket_status.eq(status).or(full_invoice_number.eq_any(invoice_numbers)))
+        .load::<Ticket>(conn)
+}
+
 // pub fn update_ticket_item_count(conn: &mut PgConnection, invoice_number: &str, new_item_count: i32) -> Result<Ticket, String> {
 //     use crate::schema::tickets::dsl as tickets_dsl;
 //     diesel::update(tickets_dsl::tickets.filter(tickets_dsl::full_invoice_number.eq(invoice_number)))
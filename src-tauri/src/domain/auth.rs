@@ -52,3 +52,16 @@ pub fn is_user_admin(user: &User) -> bool {
     user.is_admin != 0
 }
 
+/// The operator logged in at this station, provided they are an admin.
+pub fn require_admin(conn: &mut PgConnection) -> Result<User, String> {
+    let active = crate::domain::session::active_session()
+        .ok_or_else(|| "No operator is logged in".to_string())?;
+
+    let user = users_repo::get_user_by_id(conn, active.user_id)
+        .map_err(|e| format!("DB Error: {}", e))?;
+
+    if !is_user_admin(&user) {
+        return Err("Admin access required".into());
+    }
+    Ok(user)
+}
@@ -0,0 +1,42 @@
+use std::sync::RwLock;
+
+use crate::model::Session;
+
+/// The operator currently logged in at this station, used to attribute changes.
+#[derive(Debug, Clone, Copy)]
+pub struct ActiveSession {
+    pub session_id: i32,
+    pub user_id: i32,
+}
+
+static ACTIVE_SESSION: RwLock<Option<ActiveSession>> = RwLock::new(None);
+
+/// User whose PIN was verified last, until they log out. Only they can start
+/// a session.
+static AUTHENTICATED_USER: RwLock<Option<i32>> = RwLock::new(None);
+
+pub fn set_authenticated_user(user_id: i32) {
+    let mut w = AUTHENTICATED_USER.write().unwrap();
+    *w = Some(user_id);
+}
+
+pub fn authenticated_user() -> Option<i32> {
+    *AUTHENTICATED_USER.read().unwrap()
+}
+
+pub fn set_active_session(session: &Session) {
+    let mut w = ACTIVE_SESSION.write().unwrap();
+    *w = Some(ActiveSession { session_id: session.id, user_id: session.user_id });
+}
+
+pub fn clear_active_session(session_id: i32) {
+    let mut w = ACTIVE_SESSION.write().unwrap();
+    if w.is_some_and(|s| s.session_id == session_id) {
+        *w = None;
+        *AUTHENTICATED_USER.write().unwrap() = None;
+    }
+}
+
+pub fn active_session() -> Option<ActiveSession> {
+    *ACTIVE_SESSION.read().unwrap()
+}
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::db::{audit_repo::AuditRepo, garment_repo, slot_repo::SlotRepo, state_error::StateError};
+use crate::domain::session;
+use crate::model::{
+    AuditStatus, Garment, GarmentState, InventoryAudit, InventoryAuditScan, NewAuditDiscrepancy,
+    NewInventoryAuditScan, Slot, SlotState,
+};
+use crate::slot_consistency::run_consistency_check;
+use crate::slot_manager::SlotManager;
+
+const AUDITS_LISTED: i64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    /// On a slot according to the database but not scanned anywhere.
+    Missing,
+    /// Scanned on a slot but off the conveyor (or unknown) in the database.
+    Unexpected,
+    /// Scanned on a different slot than the database has.
+    WrongSlot,
+}
+
+impl DiscrepancyKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DiscrepancyKind::Missing => "missing",
+            DiscrepancyKind::Unexpected => "unexpected",
+            DiscrepancyKind::WrongSlot => "wrong_slot",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "missing" => Some(Self::Missing),
+            "unexpected" => Some(Self::Unexpected),
+            "wrong_slot" => Some(Self::WrongSlot),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Discrepancy {
+    pub kind: DiscrepancyKind,
+    pub item_id: String,
+    pub expected_slot: Option<i32>,
+    pub found_slot: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditReport {
+    pub audit: InventoryAudit,
+    pub garments_expected: usize,
+    pub garments_scanned: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AuditApplyReport {
+    pub corrected: Vec<String>,
+    /// Discrepancies left alone, with the reason.
+    pub skipped: Vec<String>,
+    /// Slot repairs made afterwards to match slot states to the moved garments.
+    pub slot_repairs: usize,
+}
+
+/// Compares the garments the database has on the conveyor with what was
+/// scanned. Missing and wrong-slot items come first in slot order, then
+/// unexpected items.
+pub fn find_discrepancies(on_conveyor: &[Garment], scans: &[InventoryAuditScan]) -> Vec<Discrepancy> {
+    let found: HashMap<&str, i32> = scans.iter().map(|s| (s.item_id.as_str(), s.slot_number)).collect();
+
+    let mut expected: Vec<&Garment> = on_conveyor.iter().filter(|g| g.slot_number != -1).collect();
+    expected.sort_by(|a, b| (a.slot_number, &a.item_id).cmp(&(b.slot_number, &b.item_id)));
+
+    let mut discrepancies = Vec::new();
+    for g in &expected {
+        match found.get(g.item_id.as_str()) {
+            None => discrepancies.push(Discrepancy {
+                kind: DiscrepancyKind::Missing,
+                item_id: g.item_id.clone(),
+                expected_slot: Some(g.slot_number),
+                found_slot: None,
+            }),
+            Some(&slot) if slot != g.slot_number => discrepancies.push(Discrepancy {
+                kind: DiscrepancyKind::WrongSlot,
+                item_id: g.item_id.clone(),
+                expected_slot: Some(g.slot_number),
+                found_slot: Some(slot),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    let expected_items: HashMap<&str, i32> = expected.iter().map(|g| (g.item_id.as_str(), g.slot_number)).collect();
+    let mut unexpected: Vec<&InventoryAuditScan> = scans
+        .iter()
+        .filter(|s| !expected_items.contains_key(s.item_id.as_str()))
+        .collect();
+    unexpected.sort_by(|a, b| (a.slot_number, &a.item_id).cmp(&(b.slot_number, &b.item_id)));
+
+    discrepancies.extend(unexpected.into_iter().map(|s| Discrepancy {
+        kind: DiscrepancyKind::Unexpected,
+        item_id: s.item_id.clone(),
+        expected_slot: None,
+        found_slot: Some(s.slot_number),
+    }));
+
+    discrepancies
+}
+
+fn get_audit(conn: &mut PgConnection, audit_id: i32) -> Result<InventoryAudit, String> {
+    AuditRepo::get(conn, audit_id)
+        .map_err(|e| format!("DB Error: {}", e))?
+        .ok_or_else(|| format!("Audit {} does not exist", audit_id))
+}
+
+fn require_status(audit: &InventoryAudit, status: AuditStatus) -> Result<(), String> {
+    if audit.audit_status != status.as_str() {
+        return Err(format!("Audit {} is {}, not {}", audit.id, audit.audit_status, status.as_str()));
+    }
+    Ok(())
+}
+
+/// Only one audit can be open at a time.
+pub fn start_audit(conn: &mut PgConnection) -> Result<InventoryAudit, String> {
+    if let Some(open) = AuditRepo::find_open(conn).map_err(|e| format!("DB Error: {}", e))? {
+        return Err(format!("Audit {} is still open", open.id));
+    }
+    AuditRepo::create(conn, session::active_session().map(|s| s.user_id))
+        .map_err(|e| format!("DB Error: {}", e))
+}
+
+pub fn record_scan(
+    conn: &mut PgConnection,
+    audit_id: i32,
+    slot_number: i32,
+    item_id: &str,
+) -> Result<InventoryAuditScan, String> {
+    let audit = get_audit(conn, audit_id)?;
+    require_status(&audit, AuditStatus::Open)?;
+
+    let item_id = item_id.trim();
+    if item_id.is_empty() {
+        return Err("Scan is empty".into());
+    }
+    if SlotRepo::get(conn, slot_number).map_err(|e| format!("DB Error: {}", e))?.is_none() {
+        return Err(format!("Slot {} does not exist", slot_number));
+    }
+
+    AuditRepo::record_scan(conn, &NewInventoryAuditScan {
+        audit_id,
+        slot_number,
+        item_id: item_id.to_string(),
+    })
+    .map_err(|e| format!("DB Error: {}", e))
+}
+
+/// Open audits are compared live; finished audits report what was stored.
+pub fn audit_report(conn: &mut PgConnection, audit_id: i32) -> Result<AuditReport, String> {
+    let audit = get_audit(conn, audit_id)?;
+    let scans = AuditRepo::scans(conn, audit_id).map_err(|e| format!("DB Error: {}", e))?;
+
+    if audit.audit_status == AuditStatus::Open.as_str() {
+        let on_conveyor = garment_repo::list_garments_on_conveyor(conn).map_err(|e| format!("DB Error: {}", e))?;
+        return Ok(AuditReport {
+            garments_expected: on_conveyor.len(),
+            garments_scanned: scans.len(),
+            discrepancies: find_discrepancies(&on_conveyor, &scans),
+            audit,
+        });
+    }
+
+    let discrepancies: Vec<Discrepancy> = AuditRepo::discrepancies(conn, audit_id)
+        .map_err(|e| format!("DB Error: {}", e))?
+        .into_iter()
+        .filter_map(|row| {
+            Some(Discrepancy {
+                kind: DiscrepancyKind::from_db(&row.kind)?,
+                item_id: row.item_id,
+                expected_slot: row.expected_slot,
+                found_slot: row.found_slot,
+            })
+        })
+        .collect();
+
+    // Scans that matched plus the expected items that were missing or moved
+    let matched = scans.len()
+        - discrepancies.iter().filter(|d| d.found_slot.is_some()).count();
+    let expected = matched
+        + discrepancies.iter().filter(|d| d.expected_slot.is_some()).count();
+
+    Ok(AuditReport {
+        garments_expected: expected,
+        garments_scanned: scans.len(),
+        discrepancies,
+        audit,
+    })
+}
+
+/// Closes the audit and stores its discrepancies.
+pub fn finish_audit(conn: &mut PgConnection, audit_id: i32) -> Result<AuditReport, String> {
+    let audit = get_audit(conn, audit_id)?;
+    require_status(&audit, AuditStatus::Open)?;
+
+    conn.transaction::<(), diesel::result::Error, _>(|conn| {
+        let on_conveyor = garment_repo::list_garments_on_conveyor(conn)?;
+        let scans = AuditRepo::scans(conn, audit_id)?;
+
+        let rows: Vec<NewAuditDiscrepancy> = find_discrepancies(&on_conveyor, &scans)
+            .into_iter()
+            .map(|d| NewAuditDiscrepancy {
+                audit_id,
+                kind: d.kind.as_str().to_string(),
+                item_id: d.item_id,
+                expected_slot: d.expected_slot,
+                found_slot: d.found_slot,
+            })
+            .collect();
+
+        AuditRepo::save_discrepancies(conn, &rows)?;
+        AuditRepo::mark_finished(conn, audit_id)
+    })
+    .map_err(|e| format!("DB Error: {}", e))?;
+
+    audit_report(conn, audit_id)
+}
+
+/// Why a garment cannot be moved to `slot`, if it cannot. `projected` is the
+/// slot's garment count including the moves already accepted.
+fn move_blocker(slot: Option<&Slot>, projected: i32, capacity: i32) -> Option<String> {
+    let Some(slot) = slot else { return Some("slot does not exist".to_string()) };
+    match SlotState::from_db(&slot.slot_state) {
+        Some(SlotState::Blocked) => Some(format!("slot {} is blocked", slot.slot_number)),
+        Some(SlotState::Error) => Some(format!("slot {} is in error", slot.slot_number)),
+        _ if projected >= capacity => Some(format!("slot {} is full", slot.slot_number)),
+        _ => None,
+    }
+}
+
+/// Moves garments to where the audit found them, takes missing ones off the
+/// conveyor, then repairs slot states to match. Garments that have moved
+/// since the audit was finished, or whose slot is blocked, in error or full,
+/// are skipped and listed with the reason.
+pub fn apply_audit(conn: &mut PgConnection, audit_id: i32) -> Result<AuditApplyReport, String> {
+    let audit = get_audit(conn, audit_id)?;
+    require_status(&audit, AuditStatus::Finished)?;
+
+    let report = audit_report(conn, audit_id)?;
+    let capacity = SlotManager::allocation_settings().slot_capacity;
+    let applied_by = session::active_session().map(|s| s.user_id);
+
+    // The outer error is a database failure, the inner one a refused apply
+    conn.transaction::<Result<AuditApplyReport, String>, StateError, _>(|conn| {
+        // Claimed first, so a second apply waits here and then finds it applied
+        if !AuditRepo::mark_applied(conn, audit_id, applied_by)? {
+            return Ok(Err(format!("Audit {} has already been applied", audit_id)));
+        }
+
+        let items: Vec<String> = report.discrepancies.iter().map(|d| d.item_id.clone()).collect();
+        let garments: HashMap<String, Garment> = garment_repo::lock_garments(conn, &items)?
+            .into_iter()
+            .map(|g| (g.item_id.clone(), g))
+            .collect();
+        let targets: Vec<i32> = report.discrepancies.iter().filter_map(|d| d.found_slot).collect();
+        let slots: HashMap<i32, Slot> = SlotRepo::lock(conn, &targets)?
+            .into_iter()
+            .map(|s| (s.slot_number, s))
+            .collect();
+
+        let mut result = AuditApplyReport::default();
+        let mut projected: HashMap<i32, i32> = slots.values().map(|s| (s.slot_number, s.garment_count)).collect();
+
+        for d in &report.discrepancies {
+            let Some(garment) = garments.get(&d.item_id) else {
+                result.skipped.push(format!("{}: not a known garment", d.item_id));
+                continue;
+            };
+            if garment.garment_state == GarmentState::Processed.as_str() {
+                result.skipped.push(format!("{}: belongs to a completed ticket", d.item_id));
+                continue;
+            }
+            if garment.slot_number != d.expected_slot.unwrap_or(-1) {
+                result.skipped.push(format!("{}: moved to slot {} since the audit", d.item_id, garment.slot_number));
+                continue;
+            }
+
+            let target = d.found_slot.unwrap_or(-1);
+            if target > 0 {
+                let count = projected.get(&target).copied().unwrap_or(0);
+                if let Some(reason) = move_blocker(slots.get(&target), count, capacity) {
+                    result.skipped.push(format!("{}: {}", d.item_id, reason));
+                    continue;
+                }
+                projected.insert(target, count + 1);
+            }
+            if let Some(count) = projected.get_mut(&garment.slot_number) {
+                *count -= 1;
+            }
+
+            SlotManager::load_garment(conn, &d.item_id, target)?;
+            result.corrected.push(d.item_id.clone());
+        }
+
+        result.slot_repairs = run_consistency_check(conn, true)?.issues.len();
+        Ok(Ok(result))
+    })
+    .map_err(|e| e.to_string())?
+}
+
+pub fn list_audits(conn: &mut PgConnection) -> QueryResult<Vec<InventoryAudit>> {
+    AuditRepo::list_recent(conn, AUDITS_LISTED)
+}
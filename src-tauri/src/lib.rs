@@ -1,6 +1,6 @@
 use std::sync::{Arc, atomic::AtomicBool};
 
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 use tokio::sync::Mutex;
 
 use crate::{db::{connection::{establish_connection, set_database_url}, data::{data_list_all_tickets, data_list_customers, data_list_garments_for_ticket, data_list_tickets_for_customer}, db_migrations::run_db_migrations}, io::fileutils::read_file, opc::opc_client::{AppState, OpcClient, OpcConfig}, pos::{spot::spot_file_utils::parse_spot_csv_core, wincleaners::parse_wincleaners_csv_core}, settings::{load_settings, appsettings::AppSettings}};
@@ -17,9 +17,19 @@ pub mod tauri_commands;
 pub mod settings;
 pub mod opc;
 pub mod slot_manager;
+pub mod slot_topology;
+pub mod slot_allocation;
+pub mod slot_zoning;
+pub mod slot_consistency;
+pub mod slot_consolidation;
+pub mod conveyor_clear;
+pub mod inventory_audit;
+pub mod reservation_sweeper;
+pub mod retrieval_queue;
 pub mod result;
 pub mod admin;
 pub mod configurator_config;
+pub mod controller;
 
 
 #[tauri::command]
@@ -64,12 +74,17 @@ pub fn run() {
             crate::pos::spot::output::conveyor_file_utils::set_conveyor_csv_output_dir(&output_dir);
             println!("Conveyor CSV output dir: {}", output_dir);
 
+            slot_manager::SlotManager::set_allocation_settings(&settings.slotAllocation);
+            println!("Slot allocation strategy: {}", settings.slotAllocation.strategy);
+
             let mut watch_settings = settings.clone();
             watch_settings.posCsvDir = pos_csv_path;
             watch_settings.conveyorCsvOutputDir = output_dir;
 
             match establish_connection() {
                 Ok(mut conn) => {
+                    let frames = settings.frames_configured().then(|| settings.frames.clone());
+                    let repair_on_startup = settings.slotAllocation.repair_on_startup;
 
                     std::thread::spawn(move || {
                         if let Err(e) = run_db_migrations(&mut conn) {
@@ -77,6 +92,27 @@ pub fn run() {
                             eprintln!("   Please configure database settings in the app");
                         } else {
                             println!("Database migrations completed successfully");
+
+                            // Removed positions wait for an admin to confirm the sync
+                            match frames.map(|frames| slot_topology::sync_slots_with_frames(&mut conn, &frames, false)) {
+                                Some(Ok(report)) => println!("Slot topology synced: {:?}", report),
+                                Some(Err(e)) => eprintln!("Failed to sync slot topology: {}", e),
+                                None => println!("Frames not configured; slot topology left as is"),
+                            }
+
+                            match slot_consistency::run_consistency_check(&mut conn, repair_on_startup) {
+                                Ok(report) => {
+                                    for issue in &report.issues {
+                                        println!("Consistency issue: {}", issue.description);
+                                    }
+                                    println!(
+                                        "Consistency check: {} issue(s), {}",
+                                        report.issues.len(),
+                                        if report.applied { "repaired" } else { "not repaired" }
+                                    );
+                                }
+                                Err(e) => eprintln!("Failed to run consistency check: {}", e),
+                            }
                         }
                     });
                 }
@@ -91,32 +127,96 @@ pub fn run() {
             // start file watch
             async_watch(watch_settings);
 
-            let opc = OpcClient::new(OpcConfig {
-                endpoint_url: settings.opcServerUrl.to_string(),
-                reconnect_backoff: std::time::Duration::from_secs(3),
-            });
+            reservation_sweeper::start_reservation_sweeper(app_handle.clone());
 
-            println!("OPC Client initialized");
+            opc::tag_map::set_tag_map(&settings.opcTags);
+            opc::telemetry::set_status_codes(&settings.conveyorStatusCodes);
+            opc::run_request::set_run_request_settings(&settings.runRequest);
+            opc::alarms::set_alarm_settings(&settings.alarms);
 
-            app.manage(AppState { opc: opc.clone(), hanger_detected: Arc::new(AtomicBool::new(false)), hanger_task: Arc::new(Mutex::new(None)) });
+            let mut opc_config = OpcConfig::new(&settings.opcServerUrl);
+            opc_config.security = settings.opcSecurity.clone();
+            if let Err(e) = opc::security::validate_security(&opc_config.security) {
+                eprintln!("OPC security settings are invalid: {e}");
+            }
+            let opc = OpcClient::new(opc_config);
+
+            println!("OPC Client initialized");
 
-            let opc_for_task = opc.clone();
-            tauri::async_runtime::spawn(async move {
-                if let Err(e) = opc_for_task.connect().await {
-                    eprintln!("OPC connect failed: {e}");
-                }
-                opc_for_task.start_reconnect_loop();
-            });
-            
             let num_frames = i16::try_from(settings.frames.len()).unwrap();
 
             println!("Num frames: {num_frames}");
             println!("Hello");
 
-            let opc_for_frames = opc.clone();
+            let kind = controller::ControllerKind::from_db(&settings.controller.kind).unwrap_or_else(|| {
+                eprintln!("Unknown controller {:?}; using OPC", settings.controller.kind);
+                controller::ControllerKind::Opc
+            });
+            let conveyor: Arc<dyn controller::ConveyorController> = match kind {
+                controller::ControllerKind::Opc => Arc::new(controller::opc_controller::OpcConveyor::new(opc.clone())),
+                controller::ControllerKind::Simulator => {
+                    let slots_per_frame = settings.frames.iter().map(|f| f.slots.len()).max().unwrap_or(0);
+                    let slots_per_frame = i16::try_from(slots_per_frame).unwrap_or_else(|_| {
+                        eprintln!("{slots_per_frame} slots per frame is more than the simulator supports; using {}", i16::MAX);
+                        i16::MAX
+                    });
+                    let sim = controller::simulator::SimulatedConveyor::new(
+                        settings.controller.clone(),
+                        num_frames,
+                        slots_per_frame,
+                    );
+
+                    // The simulator stands in for PLC telemetry
+                    let mut status_rx = sim.status_events();
+                    let status_handle = app_handle.clone();
+                    opc::telemetry::publish_status(&status_handle, sim.state().status());
+                    tauri::async_runtime::spawn(async move {
+                        loop {
+                            match status_rx.recv().await {
+                                Ok(status) => opc::telemetry::publish_status(&status_handle, status),
+                                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                            }
+                        }
+                    });
+                    Arc::new(sim)
+                }
+            };
+            println!("Conveyor controller: {}", kind.as_str());
+
+            app.manage(AppState { opc: opc.clone(), controller: conveyor.clone(), hanger_detected: Arc::new(AtomicBool::new(false)), hanger_task: Arc::new(Mutex::new(None)) });
+            retrieval_queue::start_retrieval_worker(app_handle.clone(), conveyor);
+            if kind == controller::ControllerKind::Simulator {
+                println!("Running against the conveyor simulator; the PLC is not contacted");
+                return Ok(());
+            }
+
+            // Forward connection health to the UI and push the frame count
+            // every time the PLC comes (back) online.
+            let mut health_rx = opc.health_events();
+            let opc_for_events = opc.clone();
+            let events_handle = app_handle.clone();
             tauri::async_runtime::spawn(async move {
-                let _ = opc::opc_commands::set_number_of_frames(&opc_for_frames, num_frames).await;
+                loop {
+                    let health = match health_rx.recv().await {
+                        Ok(h) => h,
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    };
+                    if health.state == opc::opc_client::ConnectionState::Connected {
+                        let _ = opc::opc_commands::set_number_of_frames(&opc_for_events, num_frames).await;
+                    }
+                    let _ = events_handle.emit(opc::opc_client::OPC_CONNECTION_EVENT, health);
+                }
             });
+
+            let opc_for_task = opc.clone();
+            tauri::async_runtime::spawn(async move {
+                opc_for_task.start_reconnect_loop();
+            });
+
+            tauri::async_runtime::spawn(opc::telemetry::start_telemetry(app_handle.clone(), opc.clone()));
+            tauri::async_runtime::spawn(opc::alarms::start_alarm_monitor(app_handle.clone(), opc.clone()));
  
             Ok(())
         })
@@ -134,6 +234,33 @@ pub fn run() {
             opc::opc_tauri_commands::subscribe_hanger_sensor,
             opc::opc_tauri_commands::set_number_of_frames,
             opc::opc_tauri_commands::set_slots_per_frame,
+            opc::opc_tauri_commands::get_opc_tag_map_tauri,
+            opc::opc_tauri_commands::save_opc_tag_map_tauri,
+            opc::opc_tauri_commands::import_opc_tag_map_tauri,
+            opc::opc_tauri_commands::verify_opc_tag_map_tauri,
+            opc::opc_tauri_commands::get_opc_health_tauri,
+            opc::opc_tauri_commands::list_opc_subscriptions_tauri,
+            opc::opc_tauri_commands::unsubscribe_opc_node_tauri,
+            opc::opc_tauri_commands::get_conveyor_status_tauri,
+            opc::opc_tauri_commands::get_run_request_settings_tauri,
+            opc::opc_tauri_commands::save_run_request_settings_tauri,
+            opc::opc_tauri_commands::list_run_requests_tauri,
+            opc::opc_tauri_commands::get_opc_security_tauri,
+            opc::opc_tauri_commands::save_opc_security_tauri,
+            opc::opc_tauri_commands::list_active_alarms_tauri,
+            opc::opc_tauri_commands::acknowledge_alarm_tauri,
+            opc::opc_tauri_commands::list_alarm_history_tauri,
+            opc::opc_tauri_commands::get_alarm_settings_tauri,
+            opc::opc_tauri_commands::save_alarm_settings_tauri,
+            opc::opc_tauri_commands::list_motion_profiles_tauri,
+            opc::opc_tauri_commands::save_motion_profile_tauri,
+            opc::opc_tauri_commands::delete_motion_profile_tauri,
+            opc::opc_tauri_commands::apply_motion_profile_tauri,
+            opc::opc_tauri_commands::get_active_motion_profile_tauri,
+            opc::opc_tauri_commands::list_motion_profile_history_tauri,
+            controller::controller_tauri_commands::get_controller_settings_tauri,
+            controller::controller_tauri_commands::save_controller_settings_tauri,
+            controller::controller_tauri_commands::get_active_controller_tauri,
             tauri_commands::handle_scan_tauri,
             tauri_commands::ticket_exists_tauri,
             tauri_commands::count_occupied_slots_tauri,
@@ -142,6 +269,7 @@ pub fn run() {
             tauri_commands::get_ticket_from_garment,
             tauri_commands::load_sensor_hanger_tauri,
             tauri_commands::wait_for_hanger_sensor,
+            tauri_commands::confirm_garment_load_tauri,
             tauri_commands::check_opc_connection_tauri,
             data_list_customers,
             data_list_all_tickets,
@@ -154,6 +282,25 @@ pub fn run() {
             tauri_commands::garment_ticket_on_conveyor_tauri,
             tauri_commands::get_slot_manager_stats,
             tauri_commands::clear_conveyor_tauri,
+            tauri_commands::clear_conveyor_scope_tauri,
+            tauri_commands::list_conveyor_snapshots_tauri,
+            tauri_commands::restore_conveyor_snapshot_tauri,
+            tauri_commands::start_inventory_audit_tauri,
+            tauri_commands::record_audit_scan_tauri,
+            tauri_commands::get_inventory_audit_report_tauri,
+            tauri_commands::finish_inventory_audit_tauri,
+            tauri_commands::apply_inventory_audit_tauri,
+            tauri_commands::list_inventory_audits_tauri,
+            tauri_commands::start_slot_consolidation_tauri,
+            tauri_commands::get_slot_consolidation_tauri,
+            tauri_commands::confirm_consolidation_move_tauri,
+            tauri_commands::skip_consolidation_move_tauri,
+            tauri_commands::cancel_slot_consolidation_tauri,
+            tauri_commands::enqueue_retrievals_tauri,
+            tauri_commands::get_retrieval_queue_tauri,
+            tauri_commands::cancel_retrieval_tauri,
+            tauri_commands::reprioritize_retrieval_tauri,
+            tauri_commands::set_retrieval_queue_paused_tauri,
             tauri_commands::start_user_session,
             tauri_commands::end_user_session,
             tauri_commands::increment_session_garments,
@@ -175,10 +322,20 @@ pub fn run() {
             tauri_commands::get_customer_report_tauri,
             tauri_commands::get_customer_report_by_id_tauri,
             tauri_commands::get_conveyor_activity_report_tauri,
+            tauri_commands::get_pickup_zone_report_tauri,
             tauri_commands::add_conveyor_activity_load_tauri,
             tauri_commands::add_conveyor_activity_unload_tauri,
             tauri_commands::get_sessions_in_range_tauri,
             tauri_commands::get_operator_stats_in_range_tauri,
+            tauri_commands::sync_slot_topology_tauri,
+            tauri_commands::get_slot_timeline_tauri,
+            tauri_commands::get_ticket_slot_history_tauri,
+            tauri_commands::block_slot_tauri,
+            tauri_commands::unblock_slot_tauri,
+            tauri_commands::clear_slot_error_tauri,
+            tauri_commands::get_out_of_service_slots_tauri,
+            tauri_commands::check_slot_consistency_tauri,
+            tauri_commands::reopen_ticket_tauri,
             greet
         ])
         .run(tauri::generate_context!())
@@ -3,7 +3,7 @@ use diesel::{prelude::*};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
-use crate::schema::{app_state, conveyoractivity, customers, garments, sessions, slots, tickets, users};
+use crate::schema::{alarms, app_state, conveyor_snapshots, conveyoractivity, customers, garments, inventory_audit_discrepancies, inventory_audit_scans, inventory_audits, motion_profile_applications, motion_profiles, run_requests, sessions, slot_history, slots, tickets, users};
 
 //
 // CUSTOMERS
@@ -59,6 +59,54 @@ pub struct Ticket {
     pub ticket_status: String,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TicketStatus {
+    NotProcessed,
+    Processing,
+    Complete,
+    Processed,
+}
+
+impl TicketStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TicketStatus::NotProcessed => "Not Processed",
+            TicketStatus::Processing => "Processing",
+            TicketStatus::Complete => "Complete",
+            TicketStatus::Processed => "Processed",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "Not Processed" => Some(Self::NotProcessed),
+            "Processing" => Some(Self::Processing),
+            "Complete" => Some(Self::Complete),
+            "Processed" => Some(Self::Processed),
+            _ => None,
+        }
+    }
+
+    /// Complete and Processed both mean the ticket has left the conveyor.
+    pub fn is_finished(self) -> bool {
+        matches!(self, TicketStatus::Complete | TicketStatus::Processed)
+    }
+
+    /// Checks a ticket status change. A finished ticket stays finished unless
+    /// it is explicitly reopened; a Complete ticket may still be marked Processed
+    /// once its last garment is scanned out.
+    pub fn transition(self, to: Self) -> Result<Self, String> {
+        if self == to || !self.is_finished() || (self, to) == (TicketStatus::Complete, TicketStatus::Processed) {
+            return Ok(to);
+        }
+        Err(format!(
+            "Ticket is {} and cannot go to {} without being reopened",
+            self.as_str(),
+            to.as_str()
+        ))
+    }
+}
+
 #[derive(Debug, Insertable, Deserialize)]
 #[diesel(table_name = tickets)]
 pub struct NewTicket {
@@ -148,6 +196,17 @@ impl GarmentState {
             _ => None,
         }
     }
+
+    /// Checks a garment state change. A processed garment only goes back
+    /// through a ticket reopen.
+    pub fn transition(self, to: Self) -> Result<Self, String> {
+        use GarmentState::*;
+        match (self, to) {
+            (a, b) if a == b => Ok(to),
+            (NotProcessed, Processing | Processed) | (Processing, NotProcessed | Processed) => Ok(to),
+            _ => Err(format!("Garment cannot go from {} to {}", self.as_str(), to.as_str())),
+        }
+    }
 }
 
 #[derive(Debug, Queryable, Identifiable, Serialize)]
@@ -189,6 +248,25 @@ pub struct Slot {
     pub item_id: Option<String>,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    pub frame_number: i32,
+    pub frame_position: i32,
+    pub retired: bool,
+    pub garment_count: i32,
+    pub reserved_at: Option<chrono::NaiveDateTime>,
+    pub state_reason: Option<String>,
+    pub blocked_by_layout: bool,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = slots)]
+pub struct NewSlot {
+    pub slot_number: i32,
+    pub slot_state: String,
+    pub frame_number: i32,
+    pub frame_position: i32,
+    pub state_reason: Option<String>,
+    pub blocked_by_layout: bool,
+    // created_at / updated_at from DB default
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -221,6 +299,21 @@ impl SlotState {
             _ => None,
         }
     }
+
+    /// Checks a slot state change. Slots holding or expecting garments must be
+    /// emptied before they are blocked, and error slots go back to empty
+    /// before they are handed out again. Blocked slots only leave through an
+    /// unblock, which `SlotRepo::set_unblocked` does without asking here.
+    pub fn transition(self, to: Self) -> Result<Self, String> {
+        use SlotState::*;
+        match (self, to) {
+            (Reserved | Occupied, Blocked)
+            | (Occupied, Reserved)
+            | (Blocked, Empty | Reserved | Occupied)
+            | (Error, Reserved) => Err(format!("Slot cannot go from {} to {}", self.as_str(), to.as_str())),
+            _ => Ok(to),
+        }
+    }
 }
 
 #[derive(Debug, Queryable, Identifiable, Serialize)]
@@ -298,8 +391,282 @@ pub struct NewConveyorActivity {
     pub action_type: String,
 }
 
+//
+// SLOT HISTORY
+//
+
+#[derive(Debug, Queryable, Identifiable, Serialize)]
+#[diesel(table_name = slot_history)]
+pub struct SlotHistory {
+    pub id: i32,
+    pub slot_number: i32,
+    pub from_state: Option<String>,
+    pub to_state: String,
+    pub ticket: Option<String>,
+    pub item_id: Option<String>,
+    pub user_id: Option<i32>,
+    pub session_id: Option<i32>,
+    pub reason: String,
+    pub changed_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = slot_history)]
+pub struct NewSlotHistory {
+    pub slot_number: i32,
+    pub from_state: Option<String>,
+    pub to_state: String,
+    pub ticket: Option<String>,
+    pub item_id: Option<String>,
+    pub user_id: Option<i32>,
+    pub session_id: Option<i32>,
+    pub reason: String,
+}
+
+//
+// CONVEYOR SNAPSHOTS
+//
+
+#[derive(Debug, Queryable, Identifiable, Serialize)]
+#[diesel(table_name = conveyor_snapshots)]
+pub struct ConveyorSnapshot {
+    pub id: i32,
+    pub scope: String,
+    #[serde(skip)]
+    pub payload: String,
+    pub slot_count: i32,
+    pub garment_count: i32,
+    pub created_by: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub restored_at: Option<NaiveDateTime>,
+    pub restored_by: Option<i32>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = conveyor_snapshots)]
+pub struct NewConveyorSnapshot {
+    pub scope: String,
+    pub payload: String,
+    pub slot_count: i32,
+    pub garment_count: i32,
+    pub created_by: Option<i32>,
+}
+
+//
+// INVENTORY AUDITS
+//
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AuditStatus {
+    Open,
+    Finished,
+    Applied,
+}
+
+impl AuditStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditStatus::Open => "open",
+            AuditStatus::Finished => "finished",
+            AuditStatus::Applied => "applied",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "open" => Some(Self::Open),
+            "finished" => Some(Self::Finished),
+            "applied" => Some(Self::Applied),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Queryable, Identifiable, Serialize)]
+#[diesel(table_name = inventory_audits)]
+pub struct InventoryAudit {
+    pub id: i32,
+    pub audit_status: String,
+    pub started_by: Option<i32>,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub applied_by: Option<i32>,
+    pub applied_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = inventory_audits)]
+pub struct NewInventoryAudit {
+    pub started_by: Option<i32>,
+}
+
+#[derive(Debug, Queryable, Identifiable, Serialize)]
+#[diesel(table_name = inventory_audit_scans)]
+pub struct InventoryAuditScan {
+    pub id: i32,
+    pub audit_id: i32,
+    pub slot_number: i32,
+    pub item_id: String,
+    pub scanned_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = inventory_audit_scans)]
+pub struct NewInventoryAuditScan {
+    pub audit_id: i32,
+    pub slot_number: i32,
+    pub item_id: String,
+}
+
+#[derive(Debug, Queryable, Identifiable, Serialize)]
+#[diesel(table_name = inventory_audit_discrepancies)]
+pub struct AuditDiscrepancyRow {
+    pub id: i32,
+    pub audit_id: i32,
+    pub kind: String,
+    pub item_id: String,
+    pub expected_slot: Option<i32>,
+    pub found_slot: Option<i32>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = inventory_audit_discrepancies)]
+pub struct NewAuditDiscrepancy {
+    pub audit_id: i32,
+    pub kind: String,
+    pub item_id: String,
+    pub expected_slot: Option<i32>,
+    pub found_slot: Option<i32>,
+}
+
 // #[derive(Debug, Queryable, Identifiable, Serialize, AsChangeset)]
 // #[diesel(table_name = conveyorinventory)]
 // pub struct ConveyorInventory {
 //     pub 
-// }
\ No newline at end of file
+// }
+//
+// RUN REQUESTS
+//
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunRequestOutcome {
+    /// The conveyor moved and stopped at the requested slot.
+    Success,
+    Timeout,
+    /// The PLC reported a fault, or stopped somewhere else.
+    Fault,
+    /// The request could not be written to the PLC.
+    Error,
+}
+
+impl RunRequestOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RunRequestOutcome::Success => "success",
+            RunRequestOutcome::Timeout => "timeout",
+            RunRequestOutcome::Fault => "fault",
+            RunRequestOutcome::Error => "error",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "success" => Some(Self::Success),
+            "timeout" => Some(Self::Timeout),
+            "fault" => Some(Self::Fault),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Queryable, Identifiable, Serialize)]
+#[diesel(table_name = run_requests)]
+pub struct RunRequest {
+    pub id: i32,
+    pub target_slot: i32,
+    pub outcome: String,
+    pub detail: Option<String>,
+    pub duration_ms: i64,
+    pub requested_by: Option<i32>,
+    pub requested_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = run_requests)]
+pub struct NewRunRequest {
+    pub target_slot: i32,
+    pub outcome: String,
+    pub detail: Option<String>,
+    pub duration_ms: i64,
+    pub requested_by: Option<i32>,
+}
+
+/// A PLC alarm. It stays active until it has cleared on the PLC and been acknowledged.
+#[derive(Debug, Clone, Queryable, Identifiable, Serialize)]
+#[diesel(table_name = alarms)]
+pub struct Alarm {
+    pub id: i32,
+    /// Name of the alarm tag that raised it.
+    pub source: String,
+    /// The fault code, or the bit number for bit alarms.
+    pub code: i64,
+    pub message: String,
+    pub raised_at: NaiveDateTime,
+    pub cleared_at: Option<NaiveDateTime>,
+    pub acknowledged_at: Option<NaiveDateTime>,
+    pub acknowledged_by: Option<i32>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = alarms)]
+pub struct NewAlarm {
+    pub source: String,
+    pub code: i64,
+    pub message: String,
+}
+
+/// A named speed and acceleration for the conveyor, in PLC units.
+#[derive(Debug, Clone, Queryable, Identifiable, Serialize)]
+#[diesel(table_name = motion_profiles)]
+pub struct MotionProfile {
+    pub id: i32,
+    pub name: String,
+    pub speed: i32,
+    pub acceleration: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = motion_profiles)]
+pub struct NewMotionProfile {
+    pub name: String,
+    pub speed: i32,
+    pub acceleration: i32,
+}
+
+/// A motion profile applied to the PLC. The values are copied so the record
+/// survives later edits to the profile.
+#[derive(Debug, Clone, Queryable, Identifiable, Serialize)]
+#[diesel(table_name = motion_profile_applications)]
+pub struct MotionProfileApplication {
+    pub id: i32,
+    pub profile_id: Option<i32>,
+    pub profile_name: String,
+    pub speed: i32,
+    pub acceleration: i32,
+    pub applied_by: Option<i32>,
+    pub applied_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = motion_profile_applications)]
+pub struct NewMotionProfileApplication {
+    pub profile_id: Option<i32>,
+    pub profile_name: String,
+    pub speed: i32,
+    pub acceleration: i32,
+    pub applied_by: Option<i32>,
+}
@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+use diesel::prelude::*;
+use diesel::{PgConnection, QueryResult};
+use open62541::{ua, ScalarValue, VariantValue};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::db::{alarm_repo::AlarmRepo, connection::establish_connection};
+use crate::domain::session;
+use crate::model::{Alarm, NewAlarm};
+use crate::opc::opc_client::OpcClient;
+use crate::opc::tag_map::{self, OpcSignal};
+use crate::settings::appsettings::{AlarmSettings, AlarmTag, OpcTag, OpcTagMap};
+
+/// Event emitted with an [`Alarm`] whenever it is raised, cleared or acknowledged.
+pub const ALARM_EVENT: &str = "alarm";
+
+/// How many alarms the history view shows.
+const ALARMS_LISTED: i64 = 100;
+
+static ALARM_SETTINGS: RwLock<Option<AlarmSettings>> = RwLock::new(None);
+
+pub fn set_alarm_settings(settings: &AlarmSettings) {
+    let mut w = ALARM_SETTINGS.write().unwrap();
+    *w = Some(settings.clone());
+}
+
+pub fn alarm_settings() -> AlarmSettings {
+    ALARM_SETTINGS.read().unwrap().clone().unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlarmKind {
+    /// The whole value is a fault code.
+    Code,
+    /// Every bit is its own fault.
+    Bits,
+}
+
+impl AlarmKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlarmKind::Code => "code",
+            AlarmKind::Bits => "bits",
+        }
+    }
+
+    pub fn from_db(s: &str) -> Option<Self> {
+        match s {
+            "code" => Some(AlarmKind::Code),
+            "bits" => Some(AlarmKind::Bits),
+            _ => None,
+        }
+    }
+}
+
+/// A fault currently reported by an alarm tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlarmCondition {
+    pub code: i64,
+    pub message: String,
+}
+
+pub fn validate_alarm_settings(settings: &AlarmSettings) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for tag in &settings.tags {
+        if tag.name.trim().is_empty() {
+            return Err("Every alarm tag needs a name".into());
+        }
+        if !names.insert(tag.name.as_str()) {
+            return Err(format!("Alarm tag {} is listed twice", tag.name));
+        }
+        let kind = AlarmKind::from_db(&tag.kind)
+            .ok_or_else(|| format!("{}: unknown alarm kind {:?}", tag.name, tag.kind))?;
+        let (source, _) = resolve_tag(tag, &tag_map::tag_map())?;
+        let width = bit_width(tag, &source)?;
+        if kind == AlarmKind::Bits {
+            if let Some(m) = tag.meanings.iter().find(|m| !(0..i64::from(width)).contains(&m.value)) {
+                return Err(format!("{}: bit {} is out of range for {}", tag.name, m.value, source.data_type));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The node an alarm tag is read from: its tag map signal, or its own node id.
+fn resolve_tag(tag: &AlarmTag, map: &OpcTagMap) -> Result<(OpcTag, ua::NodeId), String> {
+    let source = match &tag.signal {
+        Some(name) => {
+            let signal = OpcSignal::from_db(name).ok_or_else(|| format!("{}: unknown signal {:?}", tag.name, name))?;
+            signal.tag(map).clone()
+        }
+        None => OpcTag {
+            browse_name: tag.name.clone(),
+            node_id: tag.node_id.clone(),
+            data_type: tag.data_type.clone(),
+        },
+    };
+    let id = tag_map::node_id(&source).map_err(|e| format!("{}: {}", tag.name, e))?;
+    Ok((source, id))
+}
+
+fn bit_width(tag: &AlarmTag, source: &OpcTag) -> Result<u32, String> {
+    tag_map::bit_width(&source.data_type)
+        .ok_or_else(|| format!("{}: {} is not a flag or integer type", tag.name, source.data_type))
+}
+
+/// The faults a tag value stands for. Code tags report nothing for their
+/// normal values; bit tags report each set bit of the `width` the tag's data
+/// type has, so a negative Int16 does not light up the upper 48 bits.
+pub fn decode_conditions(tag: &AlarmTag, value: i64, width: u32) -> Vec<AlarmCondition> {
+    let condition = |code: i64, fallback: String| AlarmCondition {
+        code,
+        message: tag
+            .meanings
+            .iter()
+            .find(|m| m.value == code)
+            .map(|m| m.message.clone())
+            .unwrap_or(fallback),
+    };
+
+    match AlarmKind::from_db(&tag.kind) {
+        Some(AlarmKind::Code) if !tag.normal_values.contains(&value) => {
+            vec![condition(value, format!("{} fault code {}", tag.name, value))]
+        }
+        Some(AlarmKind::Bits) => (0..i64::from(width.min(64)))
+            .filter(|bit| value & (1i64 << bit) != 0)
+            .map(|bit| condition(bit, format!("{} bit {}", tag.name, bit)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// What has to change so the open alarms of a source match its active conditions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AlarmChanges {
+    pub raise: Vec<AlarmCondition>,
+    /// Ids of open alarms whose condition is gone.
+    pub clear: Vec<i32>,
+}
+
+pub fn reconcile(open: &[Alarm], active: &[AlarmCondition]) -> AlarmChanges {
+    AlarmChanges {
+        raise: active
+            .iter()
+            .filter(|c| !open.iter().any(|a| a.code == c.code))
+            .cloned()
+            .collect(),
+        clear: open
+            .iter()
+            .filter(|a| !active.iter().any(|c| c.code == a.code))
+            .map(|a| a.id)
+            .collect(),
+    }
+}
+
+/// Raises and clears alarms of `source` and returns the rows that changed.
+/// Either every change is written or none is.
+pub fn apply_conditions(conn: &mut PgConnection, source: &str, active: &[AlarmCondition]) -> QueryResult<Vec<Alarm>> {
+    conn.transaction(|conn| {
+        let open = AlarmRepo::list_uncleared(conn, source)?;
+        let changes = reconcile(&open, active);
+
+        let mut changed = Vec::new();
+        for id in changes.clear {
+            changed.push(AlarmRepo::clear(conn, id)?);
+        }
+        for condition in changes.raise {
+            let alarm = NewAlarm { source: source.to_string(), code: condition.code, message: condition.message };
+            changed.push(AlarmRepo::insert(conn, &alarm)?);
+        }
+        Ok(changed)
+    })
+}
+
+fn alarm_value(value: &ua::Variant) -> Option<i64> {
+    match value.to_value() {
+        VariantValue::Scalar(ScalarValue::Boolean(b)) => Some(b.value().into()),
+        VariantValue::Scalar(v) => tag_map::decode_integer(&v),
+        _ => None,
+    }
+}
+
+fn record(tag: &AlarmTag, value: i64, width: u32) -> Result<Vec<Alarm>, String> {
+    let active = decode_conditions(tag, value, width);
+    let mut conn = establish_connection()?;
+    apply_conditions(&mut conn, &tag.name, &active).map_err(|e| e.to_string())
+}
+
+/// Subscribes to the configured alarm tags and emits [`ALARM_EVENT`] for
+/// every alarm raised or cleared. Signals are resolved through the tag map
+/// at startup.
+pub async fn start_alarm_monitor(app: AppHandle, opc: OpcClient) {
+    let map = tag_map::tag_map();
+    for tag in alarm_settings().tags {
+        let resolved = resolve_tag(&tag, &map).and_then(|(source, node)| Ok((bit_width(&tag, &source)?, node)));
+        let (width, node) = match resolved {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                eprintln!("[Alarms] {} not monitored: {e}", tag.name);
+                continue;
+            }
+        };
+        let mut rx = match opc.subscribe_value(node).await {
+            Ok(rx) => rx,
+            Err(e) => {
+                eprintln!("[Alarms] {} not monitored: {e}", tag.name);
+                continue;
+            }
+        };
+
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let value = match rx.recv().await {
+                    Ok(v) => v,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                let Some(value) = alarm_value(&value) else {
+                    eprintln!("[Alarms] {}: expected a flag or an integer", tag.name);
+                    continue;
+                };
+                match record(&tag, value, width) {
+                    Ok(changed) => {
+                        for alarm in changed {
+                            println!(
+                                "[Alarms] {} {} ({})",
+                                alarm.source,
+                                if alarm.cleared_at.is_some() { "cleared" } else { "raised" },
+                                alarm.message
+                            );
+                            let _ = app.emit(ALARM_EVENT, alarm);
+                        }
+                    }
+                    Err(e) => eprintln!("[Alarms] {}: {e}", tag.name),
+                }
+            }
+        });
+    }
+}
+
+/// Acknowledges an alarm on behalf of the logged-in operator.
+pub fn acknowledge_alarm(conn: &mut PgConnection, alarm_id: i32) -> Result<Alarm, String> {
+    let user = session::active_session().ok_or_else(|| "No operator is logged in".to_string())?;
+    AlarmRepo::acknowledge(conn, alarm_id, Some(user.user_id))
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Alarm {alarm_id} does not exist or is already acknowledged"))
+}
+
+pub fn list_active_alarms(conn: &mut PgConnection) -> QueryResult<Vec<Alarm>> {
+    AlarmRepo::list_active(conn)
+}
+
+pub fn list_alarm_history(conn: &mut PgConnection) -> QueryResult<Vec<Alarm>> {
+    AlarmRepo::list_recent(conn, ALARMS_LISTED)
+}
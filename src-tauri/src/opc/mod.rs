@@ -1,4 +1,10 @@
+pub mod alarms;
+pub mod motion_profile;
 pub mod opc_client;
 pub mod opc_commands;
 pub mod opc_tauri_commands;
-pub mod sensor;
\ No newline at end of file
+pub mod run_request;
+pub mod security;
+pub mod sensor;
+pub mod tag_map;
+pub mod telemetry;
\ No newline at end of file
@@ -0,0 +1,162 @@
+use diesel::{PgConnection, QueryResult};
+use tauri::{AppHandle, Emitter};
+
+use crate::db::motion_profile_repo::MotionProfileRepo;
+use crate::domain::session;
+use crate::model::{MotionProfile, MotionProfileApplication, NewMotionProfile, NewMotionProfileApplication};
+use crate::opc::alarms::{self, AlarmCondition, ALARM_EVENT};
+use crate::opc::opc_client::OpcClient;
+use crate::opc::opc_commands;
+use crate::opc::tag_map::{self, OpcSignal};
+use crate::opc::telemetry::{self, ConveyorState, ConveyorStatus};
+use crate::settings::appsettings::OpcTagMap;
+
+/// Only one profile may be written to the PLC at a time.
+static APPLY_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// How many applications the history view shows.
+const APPLICATIONS_LISTED: i64 = 100;
+
+/// Alarm source raised while the PLC's speed and acceleration are unknown.
+pub const MOTION_ALARM_SOURCE: &str = "MotionProfile";
+
+/// Why a motion write failed.
+#[derive(Debug)]
+pub struct MotionWriteError {
+    pub message: String,
+    /// The previous values could not be put back, so the PLC may hold half a
+    /// profile.
+    pub restore_failed: bool,
+}
+
+/// Checks a profile can be stored and written with the mapped tag types.
+pub fn validate_motion_profile(profile: &NewMotionProfile, map: &OpcTagMap) -> Result<(), String> {
+    if profile.name.trim().is_empty() {
+        return Err("A motion profile needs a name".into());
+    }
+    if profile.speed <= 0 {
+        return Err("Speed must be greater than 0".into());
+    }
+    if profile.acceleration <= 0 {
+        return Err("Acceleration must be greater than 0".into());
+    }
+    tag_map::encode_integer(OpcSignal::Speed.tag(map), profile.speed.into())?;
+    tag_map::encode_integer(OpcSignal::Acceleration.tag(map), profile.acceleration.into())?;
+    Ok(())
+}
+
+/// Writes speed and acceleration and reads both back. If any step fails,
+/// the values found before the write are restored.
+pub async fn write_motion(opc: &OpcClient, speed: i64, acceleration: i64) -> Result<(), MotionWriteError> {
+    let unread = |message| MotionWriteError { message, restore_failed: false };
+    let previous = (
+        opc_commands::get_speed(opc).await.map_err(unread)?,
+        opc_commands::get_acceleration(opc).await.map_err(unread)?,
+    );
+
+    let written = async {
+        opc_commands::set_speed(opc, speed).await?;
+        opc_commands::set_acceleration(opc, acceleration).await?;
+        let readback = (opc_commands::get_speed(opc).await?, opc_commands::get_acceleration(opc).await?);
+        if readback != (speed, acceleration) {
+            return Err(format!(
+                "PLC reports speed {} and acceleration {} after writing {} and {}",
+                readback.0, readback.1, speed, acceleration
+            ));
+        }
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = written {
+        let restored = async {
+            opc_commands::set_speed(opc, previous.0).await?;
+            opc_commands::set_acceleration(opc, previous.1).await
+        }
+        .await;
+        return Err(match restored {
+            Ok(()) => MotionWriteError { message: format!("{e}; the previous values were restored"), restore_failed: false },
+            Err(r) => MotionWriteError {
+                message: format!("{e}; restoring the previous values failed: {r}"),
+                restore_failed: true,
+            },
+        });
+    }
+    Ok(())
+}
+
+/// Refuses unless the PLC is online and the conveyor is known to be stopped.
+pub fn check_can_apply(status: &ConveyorStatus) -> Result<(), String> {
+    if !status.plc_online {
+        return Err("The PLC is offline".into());
+    }
+    match status.state {
+        ConveyorState::Idle | ConveyorState::Faulted => Ok(()),
+        ConveyorState::Running => Err("Wait for the conveyor to stop before changing its motion".into()),
+        ConveyorState::Unknown => Err("The conveyor state is not known yet".into()),
+    }
+}
+
+/// Writes a stored profile to the PLC and records it as the active one.
+/// The application is only recorded once the PLC reports the new
+/// values. If the old values cannot be restored after a failed write, a
+/// [`MOTION_ALARM_SOURCE`] alarm is raised until a profile applies cleanly.
+pub async fn apply_motion_profile(
+    app: &AppHandle,
+    conn: &mut PgConnection,
+    opc: &OpcClient,
+    profile: &MotionProfile,
+) -> Result<MotionProfileApplication, String> {
+    let _guard = APPLY_LOCK.lock().await;
+    check_can_apply(&telemetry::last_status())?;
+
+    let application = NewMotionProfileApplication {
+        profile_id: Some(profile.id),
+        profile_name: profile.name.clone(),
+        speed: profile.speed,
+        acceleration: profile.acceleration,
+        applied_by: session::active_session().map(|s| s.user_id),
+    };
+    if let Err(e) = write_motion(opc, profile.speed.into(), profile.acceleration.into()).await {
+        if e.restore_failed {
+            let condition = AlarmCondition {
+                code: 1,
+                message: format!("Speed and acceleration are unknown after applying {}: {}", profile.name, e.message),
+            };
+            raise_motion_alarm(app, conn, &[condition]);
+        }
+        return Err(e.message);
+    }
+    // The PLC holds known values again, whether or not the record below lands
+    raise_motion_alarm(app, conn, &[]);
+    let recorded = MotionProfileRepo::insert_application(conn, &application)
+        .map_err(|e| format!("{} was written to the PLC but could not be recorded: {}", profile.name, e))?;
+
+    println!(
+        "[Motion] applied {} (speed {}, acceleration {})",
+        profile.name, profile.speed, profile.acceleration
+    );
+    Ok(recorded)
+}
+
+/// Raises the given motion alarm, or clears it when `active` is empty.
+fn raise_motion_alarm(app: &AppHandle, conn: &mut PgConnection, active: &[AlarmCondition]) {
+    match alarms::apply_conditions(conn, MOTION_ALARM_SOURCE, active) {
+        Ok(changed) => {
+            for alarm in changed {
+                let _ = app.emit(ALARM_EVENT, alarm);
+            }
+        }
+        Err(e) => eprintln!("[Motion] could not record alarm: {e}"),
+    }
+}
+
+pub fn find_motion_profile(conn: &mut PgConnection, profile_id: i32) -> Result<MotionProfile, String> {
+    MotionProfileRepo::find_by_id(conn, profile_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Motion profile {profile_id} does not exist"))
+}
+
+pub fn list_motion_profile_history(conn: &mut PgConnection) -> QueryResult<Vec<MotionProfileApplication>> {
+    MotionProfileRepo::list_applications(conn, APPLICATIONS_LISTED)
+}
@@ -1,9 +1,19 @@
-use std::{collections::HashMap, sync::{Arc, atomic::{AtomicBool, Ordering}}, time::Duration};
+use std::{collections::HashMap, sync::{Arc, RwLock, atomic::{AtomicBool, Ordering}}, time::{Duration, Instant}};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use tokio::{sync::{Mutex, broadcast}, task::JoinHandle};
 use thiserror::Error;
 
-use open62541::{ua, AsyncClient};
+use crate::opc::security;
+use crate::settings::appsettings::OpcSecuritySettings;
 
+use open62541::{ua, AsyncClient, AsyncMonitoredItem, AsyncSubscription, MonitoredItemBuilder, SubscriptionBuilder};
+
+/// Event emitted whenever the connection state changes or a connect attempt fails.
+pub const OPC_CONNECTION_EVENT: &str = "opc_connection";
+
+/// Server_ServerStatus_CurrentTime, present on every OPC UA server.
+const HEARTBEAT_NODE: u32 = 2258;
 
 #[derive(Debug, Error)]
 pub enum OpcError {
@@ -11,11 +21,14 @@ pub enum OpcError {
     Ua(String),
     #[error("Not connected")]
     NotConnected,
+    #[error("Heartbeat timed out after {0:?}")]
+    Timeout(Duration),
 }
 
 #[derive(Clone)]
 pub struct AppState {
     pub opc: crate::opc::opc_client::OpcClient,
+    pub controller: Arc<dyn crate::controller::ConveyorController>,
     pub hanger_detected: Arc<AtomicBool>,
     pub hanger_task: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
@@ -23,7 +36,83 @@ pub struct AppState {
 #[derive(Clone, Debug)]
 pub struct OpcConfig {
     pub endpoint_url: String,
+    /// Wait before the first reconnect attempt; doubles on each failure.
     pub reconnect_backoff: Duration,
+    pub max_reconnect_backoff: Duration,
+    pub heartbeat_interval: Duration,
+    pub heartbeat_timeout: Duration,
+    /// Intervals used by [`OpcClient::subscribe_value`].
+    pub subscription: SubscriptionOptions,
+    pub security: OpcSecuritySettings,
+}
+
+impl OpcConfig {
+    pub fn new(endpoint_url: &str) -> Self {
+        Self {
+            endpoint_url: endpoint_url.to_string(),
+            reconnect_backoff: Duration::from_secs(1),
+            max_reconnect_backoff: Duration::from_secs(60),
+            heartbeat_interval: Duration::from_secs(2),
+            heartbeat_timeout: Duration::from_secs(2),
+            subscription: SubscriptionOptions {
+                sampling_interval: Duration::from_millis(100),
+                publishing_interval: Duration::from_millis(200),
+            },
+            security: OpcSecuritySettings::default(),
+        }
+    }
+}
+
+/// How often the server samples a monitored node and how often it publishes changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubscriptionOptions {
+    pub sampling_interval: Duration,
+    pub publishing_interval: Duration,
+}
+
+/// Doubles the reconnect wait, capped at `max`.
+pub fn next_backoff(current: Duration, max: Duration) -> Duration {
+    current.saturating_mul(2).min(max)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+/// Connection diagnostics, published with every [`OPC_CONNECTION_EVENT`].
+#[derive(Debug, Clone, Serialize)]
+pub struct OpcHealth {
+    pub state: ConnectionState,
+    pub endpoint_url: String,
+    pub connected_since: Option<DateTime<Utc>>,
+    pub last_heartbeat_at: Option<DateTime<Utc>>,
+    /// Round trip of the last successful heartbeat read.
+    pub latency_ms: Option<u64>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    /// Failed connect attempts since the last successful connect.
+    pub reconnect_attempts: u32,
+    pub next_retry_ms: Option<u64>,
+}
+
+impl OpcHealth {
+    fn new(endpoint_url: &str) -> Self {
+        Self {
+            state: ConnectionState::Disconnected,
+            endpoint_url: endpoint_url.to_string(),
+            connected_since: None,
+            last_heartbeat_at: None,
+            latency_ms: None,
+            last_error: None,
+            last_error_at: None,
+            reconnect_attempts: 0,
+            next_retry_ms: None,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -31,16 +120,141 @@ pub struct OpcClient {
     cfg: OpcConfig,
     inner: Arc<Mutex<Inner>>,
     connected_flag: Arc<AtomicBool>,
+    health: Arc<RwLock<OpcHealth>>,
+    health_tx: broadcast::Sender<OpcHealth>,
 }
 
 struct Inner {
     client: Option<Arc<AsyncClient>>,
-    subs: HashMap<String, broadcast::Sender<ua::Variant>>,
+    subs: HashMap<String, ManagedSubscription>,
+}
+
+/// A monitored node. The sender outlives the server-side subscription, so
+/// receivers keep working across reconnects.
+struct ManagedSubscription {
+    node_id: ua::NodeId,
+    options: SubscriptionOptions,
+    tx: broadcast::Sender<ua::Variant>,
+    task: Option<JoinHandle<()>>,
+    runtime: Arc<RwLock<SubscriptionRuntime>>,
+}
+
+#[derive(Default)]
+struct SubscriptionRuntime {
+    active: bool,
+    last_value_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+/// One row of [`OpcClient::subscriptions`].
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionStatus {
+    pub node_id: String,
+    pub sampling_interval_ms: u64,
+    pub publishing_interval_ms: u64,
+    /// The monitored item exists on the server and is delivering values.
+    pub active: bool,
+    pub receivers: usize,
+    pub last_value_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl ManagedSubscription {
+    /// (Re)creates the server-side subscription on `client`. The task owns the
+    /// subscription, so aborting it deletes the subscription on the server.
+    fn start(&mut self, client: &Arc<AsyncClient>) {
+        self.stop();
+
+        let client = client.clone();
+        let node_id = self.node_id.clone();
+        let options = self.options;
+        let tx = self.tx.clone();
+        let runtime = self.runtime.clone();
+
+        self.task = Some(tokio::spawn(async move {
+            let (_subscription, mut item) = match monitor(&client, &node_id, options).await {
+                Ok(created) => created,
+                Err(e) => {
+                    eprintln!("OPC subscribe to {node_id} failed: {e}");
+                    runtime.write().unwrap().last_error = Some(e.to_string());
+                    return;
+                }
+            };
+            // The subscription only keeps a weak reference to the client.
+            drop(client);
+            {
+                let mut rt = runtime.write().unwrap();
+                rt.active = true;
+                rt.last_error = None;
+            }
+
+            while let Some(dv) = item.next().await {
+                let Some(value) = dv.value() else { continue };
+                runtime.write().unwrap().last_value_at = Some(Utc::now());
+                let _ = tx.send(value.clone());
+            }
+
+            let mut rt = runtime.write().unwrap();
+            rt.active = false;
+            rt.last_error = Some("monitored item closed".into());
+        }));
+    }
+
+    fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        self.runtime.write().unwrap().active = false;
+    }
+
+    fn is_running(&self) -> bool {
+        self.task.as_ref().is_some_and(|t| !t.is_finished())
+    }
+
+    fn status(&self) -> SubscriptionStatus {
+        let rt = self.runtime.read().unwrap();
+        SubscriptionStatus {
+            node_id: self.node_id.to_string(),
+            sampling_interval_ms: self.options.sampling_interval.as_millis() as u64,
+            publishing_interval_ms: self.options.publishing_interval.as_millis() as u64,
+            active: rt.active && self.is_running(),
+            receivers: self.tx.receiver_count(),
+            last_value_at: rt.last_value_at,
+            last_error: rt.last_error.clone(),
+        }
+    }
+}
+
+async fn monitor(
+    client: &AsyncClient,
+    node_id: &ua::NodeId,
+    options: SubscriptionOptions,
+) -> Result<(AsyncSubscription, AsyncMonitoredItem), OpcError> {
+    let ua_err = |e: open62541::Error| OpcError::Ua(format!("{e:?}"));
+
+    let (_, subscription) = SubscriptionBuilder::default()
+        .requested_publishing_interval(Some(options.publishing_interval))
+        .create(client)
+        .await
+        .map_err(ua_err)?;
+    let (_, item) = MonitoredItemBuilder::new([node_id.clone()])
+        .sampling_interval(Some(options.sampling_interval))
+        .create(&subscription)
+        .await
+        .map_err(ua_err)?
+        .pop()
+        .ok_or(OpcError::Ua("no monitored item created".into()))?
+        .map_err(ua_err)?;
+
+    Ok((subscription, item))
 }
 
 impl OpcClient {
     pub fn new(cfg: OpcConfig) -> Self {
+        let (health_tx, _) = broadcast::channel(32);
         Self {
+            health: Arc::new(RwLock::new(OpcHealth::new(&cfg.endpoint_url))),
+            health_tx,
             cfg,
             inner: Arc::new(Mutex::new(Inner {
                 client: None,
@@ -50,28 +264,163 @@ impl OpcClient {
         }
     }
 
-    
+    /// Connects and checks the server answers before reporting connected.
     pub async fn connect(&self) -> Result<(), OpcError> {
-        let client = AsyncClient::new(&self.cfg.endpoint_url)
-            .map_err(|e| OpcError::Ua(format!("{e:?}")))?;
-        let mut inner = self.inner.lock().await;
-        inner.client = Some(Arc::new(client));
+        self.update_health(true, |h| h.state = ConnectionState::Connecting);
+
+        let url = self.cfg.endpoint_url.clone();
+        let security = self.cfg.security.clone();
+        let client = match tokio::task::spawn_blocking(move || security::connect_client(&url, &security)).await {
+            Ok(Ok(c)) => Arc::new(c),
+            Ok(Err(e)) => return Err(self.connect_failed(OpcError::Ua(e))),
+            Err(e) => return Err(self.connect_failed(OpcError::Ua(e.to_string()))),
+        };
+        let latency = match self.heartbeat(&client).await {
+            Ok(latency) => latency,
+            Err(e) => {
+                Self::close(client).await;
+                return Err(self.connect_failed(e));
+            }
+        };
+
+        {
+            let mut inner = self.inner.lock().await;
+            // Subscriptions nobody listens to any more are dropped; the rest
+            // are re-created on the new session.
+            inner.subs.retain(|_, sub| sub.tx.receiver_count() > 0);
+            for sub in inner.subs.values_mut() {
+                sub.start(&client);
+            }
+            inner.client = Some(client);
+        }
         self.connected_flag.store(true, Ordering::Relaxed);
+
+        let now = Utc::now();
+        self.update_health(true, |h| {
+            h.state = ConnectionState::Connected;
+            h.connected_since = Some(now);
+            h.last_heartbeat_at = Some(now);
+            h.latency_ms = Some(latency.as_millis() as u64);
+            h.reconnect_attempts = 0;
+            h.next_retry_ms = None;
+        });
+        println!(
+            "OPC connected to {} ({}, {}) in {:?}",
+            self.cfg.endpoint_url, self.cfg.security.security_policy, self.cfg.security.security_mode, latency
+        );
         Ok(())
     }
 
-    pub async fn read_value(&self, node_id: ua::NodeId) -> Result<ua::Variant, OpcError> {
+    fn connect_failed(&self, err: OpcError) -> OpcError {
+        let message = err.to_string();
+        self.update_health(true, |h| {
+            h.state = ConnectionState::Disconnected;
+            h.last_error = Some(message);
+            h.last_error_at = Some(Utc::now());
+            h.reconnect_attempts += 1;
+        });
+        err
+    }
+
+    /// Reads the server clock. Fails if the client has given up on the
+    /// session or the server does not answer within the heartbeat timeout.
+    async fn heartbeat(&self, client: &AsyncClient) -> Result<Duration, OpcError> {
+        let status = client.state().connect_status;
+        if !status.is_good() {
+            return Err(OpcError::Ua(format!("session lost ({})", status.name())));
+        }
+
+        let started = Instant::now();
+        let node = ua::NodeId::numeric(0, HEARTBEAT_NODE);
+        let dv = tokio::time::timeout(self.cfg.heartbeat_timeout, client.read_value(&node))
+            .await
+            .map_err(|_| OpcError::Timeout(self.cfg.heartbeat_timeout))?
+            .map_err(|e| OpcError::Ua(format!("{e:?}")))?;
+
+        if dv.value().is_none() {
+            return Err(OpcError::Ua("heartbeat returned no value".into()));
+        }
+        Ok(started.elapsed())
+    }
+
+    /// Drops the current client so the supervisor reconnects.
+    async fn teardown(&self, reason: &str) {
         let client = {
-            let inner = self.inner.lock().await;
-            inner.client.clone().ok_or(OpcError::NotConnected)?
+            let mut inner = self.inner.lock().await;
+            for sub in inner.subs.values_mut() {
+                sub.stop();
+            }
+            inner.client.take()
         };
+        self.connected_flag.store(false, Ordering::Relaxed);
+
+        let reason = reason.to_string();
+        self.update_health(true, |h| {
+            h.state = ConnectionState::Disconnected;
+            h.connected_since = None;
+            h.latency_ms = None;
+            h.last_error = Some(reason);
+            h.last_error_at = Some(Utc::now());
+        });
+
+        if let Some(client) = client {
+            Self::close(client).await;
+        }
+    }
+
+    /// Disconnects gracefully unless a read or write still holds the client.
+    async fn close(client: Arc<AsyncClient>) {
+        if let Ok(client) = Arc::try_unwrap(client) {
+            client.disconnect().await;
+        }
+    }
 
-        let dv = client
+    fn update_health(&self, publish: bool, f: impl FnOnce(&mut OpcHealth)) {
+        let snapshot = {
+            let mut health = self.health.write().unwrap();
+            f(&mut health);
+            health.clone()
+        };
+        if publish {
+            let _ = self.health_tx.send(snapshot);
+        }
+    }
+
+    fn note_error(&self, err: &OpcError) {
+        let message = err.to_string();
+        self.update_health(false, |h| {
+            h.last_error = Some(message);
+            h.last_error_at = Some(Utc::now());
+        });
+    }
+
+    pub fn health(&self) -> OpcHealth {
+        self.health.read().unwrap().clone()
+    }
+
+    /// Receives a copy of [`OpcHealth`] on every state change or failed attempt.
+    pub fn health_events(&self) -> broadcast::Receiver<OpcHealth> {
+        self.health_tx.subscribe()
+    }
+
+    async fn current_client(&self) -> Result<Arc<AsyncClient>, OpcError> {
+        let inner = self.inner.lock().await;
+        inner.client.clone().ok_or(OpcError::NotConnected)
+    }
+
+    pub async fn read_value(&self, node_id: ua::NodeId) -> Result<ua::Variant, OpcError> {
+        let client = self.current_client().await?;
+
+        let result = client
             .read_value(&node_id)
             .await
-            .map_err(|e| OpcError::Ua(format!("{e:?}")))?;
+            .map_err(|e| OpcError::Ua(format!("{e:?}")))
+            .and_then(|dv| dv.value().cloned().ok_or(OpcError::Ua("DataValue has no value".into())));
 
-        Ok(dv.value().ok_or(OpcError::Ua("DataValue has no value".into()))?.clone())
+        if let Err(e) = &result {
+            self.note_error(e);
+        }
+        result
     }
 
     pub async fn write_value(
@@ -79,67 +428,113 @@ impl OpcClient {
         node_id: ua::NodeId,
         value: ua::DataValue,
     ) -> Result<(), OpcError> {
-        let client = {
-            let inner = self.inner.lock().await;
-            inner.client.clone().ok_or(OpcError::NotConnected)?
-        };
+        let client = self.current_client().await?;
 
         client
             .write_value(&node_id, &value).await
-            .map_err(|e| OpcError::Ua(format!("{e:?}")))?;
-
-        Ok(())
+            .map_err(|e| {
+                let err = OpcError::Ua(format!("{e:?}"));
+                self.note_error(&err);
+                err
+            })
     }
 
 
+    /// Monitors `node_id` with the configured intervals. See [`Self::subscribe_value_with`].
     pub async fn subscribe_value(
         &self,
         node_id: ua::NodeId,
+    ) -> Result<broadcast::Receiver<ua::Variant>, OpcError> {
+        self.subscribe_value_with(node_id, self.cfg.subscription).await
+    }
+
+    /// Monitors `node_id`, sharing one server-side subscription per node.
+    /// While disconnected the subscription is registered and created once
+    /// the connection comes up; it is re-created after every reconnect.
+    /// Subscribing again with different options replaces the intervals.
+    pub async fn subscribe_value_with(
+        &self,
+        node_id: ua::NodeId,
+        options: SubscriptionOptions,
     ) -> Result<broadcast::Receiver<ua::Variant>, OpcError> {
         let key = node_id.to_string();
+        let mut inner = self.inner.lock().await;
+        let client = inner.client.clone();
 
-        let (tx, rx) = {
-            let mut inner = self.inner.lock().await;
-            if let Some(existing) = inner.subs.get(&key) {
-                (existing.clone(), existing.subscribe())
-            } else {
-                let (tx, rx) = broadcast::channel(256);
-                inner.subs.insert(key.clone(), tx.clone());
-                (tx, rx)
+        let sub = inner.subs.entry(key).or_insert_with(|| ManagedSubscription {
+            node_id,
+            options,
+            tx: broadcast::channel(256).0,
+            task: None,
+            runtime: Arc::new(RwLock::new(SubscriptionRuntime::default())),
+        });
+        let rx = sub.tx.subscribe();
+
+        let changed = sub.options != options;
+        sub.options = options;
+        if let Some(client) = client {
+            if changed || !sub.is_running() {
+                sub.start(&client);
             }
-        };
+        }
 
-        let client = {
-            let inner = self.inner.lock().await;
-            inner.client.clone().ok_or(OpcError::NotConnected)?
-        };
+        Ok(rx)
+    }
 
-        tokio::spawn(async move {
-            if let Ok(subscription) = client.create_subscription().await {
-                if let Ok(mut item) = subscription.create_monitored_item(&node_id).await {
-                    while let Some(v) = item.next().await {
-                        let _ = tx.send(v.value().unwrap().clone());
-                    }
-                }
+    /// Deletes the subscription for `node_id`. Existing receivers see the
+    /// channel close. Returns false if the node was not subscribed.
+    pub async fn unsubscribe(&self, node_id: &ua::NodeId) -> bool {
+        let mut inner = self.inner.lock().await;
+        match inner.subs.remove(&node_id.to_string()) {
+            Some(mut sub) => {
+                sub.stop();
+                true
             }
-        });
+            None => false,
+        }
+    }
 
-        Ok(rx)
+    pub async fn subscriptions(&self) -> Vec<SubscriptionStatus> {
+        let inner = self.inner.lock().await;
+        let mut list: Vec<_> = inner.subs.values().map(ManagedSubscription::status).collect();
+        list.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+        list
     }
 
+    /// Supervises the connection: heartbeats while connected, tears the
+    /// client down when a heartbeat fails, and reconnects with exponential backoff.
     pub fn start_reconnect_loop(&self) {
         let this = self.clone();
         tokio::spawn(async move {
+            let mut backoff = this.cfg.reconnect_backoff;
             loop {
-                let connected = {
-                    let inner = this.inner.lock().await;
-                    inner.client.is_some()
-                };
-
-                if !connected {
-                    let _ = this.connect().await;
+                match this.current_client().await {
+                    Ok(client) => {
+                        match this.heartbeat(&client).await {
+                            Ok(latency) => this.update_health(false, |h| {
+                                h.last_heartbeat_at = Some(Utc::now());
+                                h.latency_ms = Some(latency.as_millis() as u64);
+                            }),
+                            Err(e) => {
+                                eprintln!("OPC heartbeat failed: {e}");
+                                drop(client);
+                                this.teardown(&e.to_string()).await;
+                                continue;
+                            }
+                        }
+                        tokio::time::sleep(this.cfg.heartbeat_interval).await;
+                    }
+                    Err(_) => {
+                        if this.connect().await.is_ok() {
+                            backoff = this.cfg.reconnect_backoff;
+                            continue;
+                        }
+                        eprintln!("OPC connect to {} failed; retrying in {:?}", this.cfg.endpoint_url, backoff);
+                        this.update_health(false, |h| h.next_retry_ms = Some(backoff.as_millis() as u64));
+                        tokio::time::sleep(backoff).await;
+                        backoff = next_backoff(backoff, this.cfg.max_reconnect_backoff);
+                    }
                 }
-                tokio::time::sleep(this.cfg.reconnect_backoff).await;
             }
         });
     }
@@ -1,99 +1,45 @@
-use crate::opc::{ opc_client::OpcClient};
-use open62541::{ScalarValue, VariantValue, ua::{self, DataValue}};
+use crate::opc::{opc_client::OpcClient, run_request::{self, RunRequestResult}, tag_map::{self, OpcSignal}};
+use open62541::ua;
 use tauri::State;
 
 pub fn get_opc_client(opc_client: State<OpcClient>) -> OpcClient {
     opc_client.inner().clone()
 }
 
-pub async fn set_speed(opc_client: &mut OpcClient, node_id: ua::NodeId, speed: DataValue) {
-    let _ = opc_client.write_value(node_id, speed).await;
+pub async fn set_speed(opc_client: &OpcClient, speed: i64) -> Result<(), String> {
+    tag_map::write_integer(opc_client, OpcSignal::Speed, speed).await
 }
 
-pub async fn get_speed(opc_client: &mut OpcClient, node_id: ua::NodeId) -> Option<DataValue> {
-    match opc_client.read_value(node_id).await {
-        Ok(value) => Some(DataValue::new(value)),
-        Err(_) => None,
-    }
+pub async fn get_speed(opc_client: &OpcClient) -> Result<i64, String> {
+    tag_map::read_integer(opc_client, OpcSignal::Speed).await
 }
 
-pub async fn jog_forward(opc_client: &OpcClient) -> Result<(), String> {
-    println!("Jogging station 1 forward");
-    match opc_client
-        .write_value(
-            ua::NodeId::numeric(1, 81),
-            DataValue::new(ua::Variant::scalar(ua::Boolean::new(true))),
-        )
-        .await
-    {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e.to_string()),
-    }
+pub async fn set_acceleration(opc_client: &OpcClient, acceleration: i64) -> Result<(), String> {
+    tag_map::write_integer(opc_client, OpcSignal::Acceleration, acceleration).await
 }
 
-pub async fn slot_run_request(opc_client: &OpcClient, target_slot: i16) -> Result<(), String> {
-    println!("Slot Run Request");
-    set_target_slot(opc_client, target_slot).await?;
-    opc_client
-        .write_value(
-            ua::NodeId::numeric(1, 83),
-            DataValue::new(ua::Variant::scalar(ua::Boolean::new(false))),
-        )
-        .await
-        .map_err(|e| e.to_string())?;
+pub async fn get_acceleration(opc_client: &OpcClient) -> Result<i64, String> {
+    tag_map::read_integer(opc_client, OpcSignal::Acceleration).await
+}
 
-    let _ = match opc_client
-        .write_value(
-            ua::NodeId::numeric(1, 83),
-            DataValue::new(ua::Variant::scalar(ua::Boolean::new(true))),
-        )
-        .await
-    {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e.to_string()),
-    };   
+pub async fn jog_forward(opc_client: &OpcClient) -> Result<(), String> {
+    tag_map::write_bool(opc_client, OpcSignal::JogForward, true).await
+}
 
-    opc_client
-        .write_value(
-            ua::NodeId::numeric(1, 83),
-            DataValue::new(ua::Variant::scalar(ua::Boolean::new(false))),
-        )
-        .await
-        .map_err(|e| e.to_string())
+pub async fn slot_run_request(opc_client: &OpcClient, target_slot: i16) -> Result<RunRequestResult, String> {
+    run_request::request_slot_run(opc_client, target_slot).await
 }
 
 pub async fn set_target_slot(opc_client: &OpcClient, target_slot: i16) -> Result<(), String> {
-    println!("Slot Run Request");
-    match opc_client
-        .write_value(
-            ua::NodeId::numeric(1, 267),
-            DataValue::new(ua::Variant::scalar(ua::Int16::new(target_slot))),
-        )
-        .await
-    {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e.to_string()),
-    }   
+    tag_map::write_integer(opc_client, OpcSignal::TargetSlot, target_slot.into()).await
 }
 
 pub async fn get_target_slot(opc_client: &OpcClient) -> Result<ua::Variant, String> {
-    println!("Get Target Slot");
-    opc_client
-        .read_value(ua::NodeId::numeric(1, 267))
-        .await
-        .map_err(|e| e.to_string())
+    tag_map::read_raw(opc_client, OpcSignal::TargetSlot).await
 }
 
 pub async fn get_load_hanger_sensor(opc_client: &OpcClient) -> Result<bool, String> {
-    let v: ua::Variant = opc_client
-        .read_value(ua::NodeId::numeric(1, 102))
-        .await
-        .map_err(|e| e.to_string())?;
-
-    match v.to_value() {
-        VariantValue::Scalar(ScalarValue::Boolean(b)) => Ok(b.value()),
-        other => Err(format!("Expected Boolean at ns=1;i=99, got: {other:?}")),
-    }
+    tag_map::read_bool(opc_client, OpcSignal::HangerSensor).await
 }
 
 pub fn check_opc_connection(opc_client: &OpcClient) -> bool {
@@ -101,21 +47,9 @@ pub fn check_opc_connection(opc_client: &OpcClient) -> bool {
 }
 
 pub async fn set_number_of_frames(opc_client: &OpcClient, num_frames: i16) -> Result<(), String> {
-    opc_client
-        .write_value(
-            ua::NodeId::numeric(1, 259),
-            DataValue::new(ua::Variant::scalar(ua::Int16::new(num_frames))),
-        )
-        .await
-        .map_err(|e| e.to_string())
+    tag_map::write_integer(opc_client, OpcSignal::NumberOfFrames, num_frames.into()).await
 }
 
 pub async fn set_slots_per_frame(opc_client: &OpcClient, slots_per_frame: i16) -> Result<(), String> {
-    opc_client
-        .write_value(
-            ua::NodeId::numeric(1, 258),
-            DataValue::new(ua::Variant::scalar(ua::Int16::new(slots_per_frame))),
-        )
-        .await
-        .map_err(|e| e.to_string())
+    tag_map::write_integer(opc_client, OpcSignal::SlotsPerFrame, slots_per_frame.into()).await
 }
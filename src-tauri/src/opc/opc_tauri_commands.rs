@@ -1,18 +1,23 @@
-use open62541::{ScalarValue, VariantValue, ua};
+use open62541::ua;
 use tauri::Emitter;
 
-use crate::opc::{opc_client::AppState, opc_commands};
+use crate::opc::{alarms, motion_profile, opc_client::{AppState, OpcHealth, SubscriptionStatus}, run_request::{self, RunRequestResult}, security, tag_map::{self, OpcSignal, TagCheck, TagImportReport}, telemetry::{self, ConveyorStatus}};
+use crate::db::{connection::establish_connection, motion_profile_repo::MotionProfileRepo};
+use crate::model::{Alarm, MotionProfile, MotionProfileApplication, NewMotionProfile, RunRequest, RunRequestOutcome};
+use crate::controller::ControllerKind;
+use crate::domain::auth;
+use crate::settings::appsettings::{AlarmSettings, OpcSecuritySettings, OpcTagMap, RunRequestSettings};
 
 
 
 #[tauri::command]
 pub async fn station1_jog_fwd(state: tauri::State<'_, AppState>) -> Result<(), String> {
-    opc_commands::jog_forward(&state.opc).await
+    state.controller.jog_forward().await
 }
 
 #[tauri::command]
-pub async fn get_target_slot_tauri(state: tauri::State<'_, AppState>) -> Result<ua::Variant, String> {
-    opc_commands::get_target_slot(&state.opc).await
+pub async fn get_target_slot_tauri(state: tauri::State<'_, AppState>) -> Result<i64, String> {
+    state.controller.target_slot().await
 }
 
 #[tauri::command]
@@ -20,16 +25,16 @@ pub async fn subscribe_hanger_sensor(
     state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
-    let mut rx = state.opc
-        .subscribe_value(ua::NodeId::numeric(1, 102))
-        .await
-        .map_err(|e| e.to_string())?;
+    let mut rx = state.controller.watch_hanger_sensor().await?;
 
     let handle = tokio::spawn(async move {
-        while let Ok(variant) = rx.recv().await {
-            if let VariantValue::Scalar(ScalarValue::Boolean(b)) = variant.to_value() {
-                let _ = app_handle.emit("hanger_sensor", b.value());
-            }
+        loop {
+            let sensed = match rx.recv().await {
+                Ok(v) => v,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            };
+            let _ = app_handle.emit("hanger_sensor", sensed);
         }
     });
 
@@ -42,12 +47,23 @@ pub async fn subscribe_hanger_sensor(
     Ok(())
 }
 
+/// Fails unless the conveyor reached the slot, so callers never carry on
+/// with a load or unload after a timeout or fault.
 #[tauri::command]
 pub async fn slot_run_request_tauri(
     state: tauri::State<'_, AppState>,
     target_slot: i16,
-) -> Result<(), String> {
-    opc_commands::slot_run_request(&state.opc, target_slot).await
+) -> Result<RunRequestResult, String> {
+    let result = state.controller.run_to_slot(target_slot).await?;
+    match result.outcome {
+        RunRequestOutcome::Success => Ok(result),
+        outcome => Err(format!(
+            "Slot {} run {}: {}",
+            target_slot,
+            outcome.as_str(),
+            result.detail.unwrap_or_default()
+        )),
+    }
 }
 
 #[tauri::command]
@@ -55,7 +71,7 @@ pub async fn set_number_of_frames(
     state: tauri::State<'_, AppState>,
     num_frames: i16,
 ) -> Result<(), String> {
-    opc_commands::set_number_of_frames(&state.opc, num_frames).await
+    state.controller.set_number_of_frames(num_frames).await
 }
 
 #[tauri::command]
@@ -63,5 +79,240 @@ pub async fn set_slots_per_frame(
     state: tauri::State<'_, AppState>,
     slots_per_fame: i16,
 ) -> Result<(), String> {
-    opc_commands::set_slots_per_frame(&state.opc, slots_per_fame).await
+    state.controller.set_slots_per_frame(slots_per_fame).await
+}
+
+#[tauri::command]
+pub fn get_opc_tag_map_tauri() -> Result<OpcTagMap, String> {
+    Ok(tag_map::tag_map())
+}
+
+/// Admin only. Saves the tag map and uses it from the next OPC read or write.
+#[tauri::command]
+pub fn save_opc_tag_map_tauri(app: tauri::AppHandle, tags: OpcTagMap) -> Result<(), String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+
+    for signal in OpcSignal::ALL {
+        let tag = signal.tag(&tags);
+        tag_map::check_tag_type(signal, tag)?;
+        tag_map::node_id(tag)?;
+    }
+
+    let mut settings = crate::settings::load_settings(&app);
+    settings.opcTags = tags;
+    crate::settings::save_settings(&app, &settings)?;
+    tag_map::set_tag_map(&settings.opcTags);
+    Ok(())
+}
+
+/// Admin only. Imports node ids and types from a UaExpert export (e.g.
+/// conveyor.csv), matching each signal by browse name, and saves the result.
+#[tauri::command]
+pub fn import_opc_tag_map_tauri(app: tauri::AppHandle, path: String) -> Result<TagImportReport, String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let exported = tag_map::parse_uaexpert_export(&contents)?;
+    let report = tag_map::import_tags(&tag_map::tag_map(), &exported)?;
+
+    save_opc_tag_map_tauri(app, report.tags.clone())?;
+    Ok(report)
+}
+
+/// Reads every mapped tag and checks the server's type matches the map.
+#[tauri::command]
+pub async fn verify_opc_tag_map_tauri(state: tauri::State<'_, AppState>) -> Result<Vec<TagCheck>, String> {
+    Ok(tag_map::verify_tag_map(&state.opc, &tag_map::tag_map()).await)
+}
+
+#[tauri::command]
+pub fn get_opc_health_tauri(state: tauri::State<'_, AppState>) -> OpcHealth {
+    state.opc.health()
+}
+
+#[tauri::command]
+pub async fn list_opc_subscriptions_tauri(state: tauri::State<'_, AppState>) -> Result<Vec<SubscriptionStatus>, String> {
+    Ok(state.opc.subscriptions().await)
+}
+
+/// Deletes the subscription for a node, e.g. `ns=1;i=102`.
+#[tauri::command]
+pub async fn unsubscribe_opc_node_tauri(
+    state: tauri::State<'_, AppState>,
+    node_id: String,
+) -> Result<bool, String> {
+    let node = node_id
+        .trim()
+        .parse::<ua::NodeId>()
+        .map_err(|e| format!("Invalid node id {:?}: {}", node_id, e))?;
+    Ok(state.opc.unsubscribe(&node).await)
+}
+
+/// Last-known conveyor telemetry, also pushed as `conveyor_status` events.
+#[tauri::command]
+pub fn get_conveyor_status_tauri() -> ConveyorStatus {
+    telemetry::last_status()
+}
+
+#[tauri::command]
+pub fn get_run_request_settings_tauri() -> RunRequestSettings {
+    run_request::run_request_settings()
+}
+
+/// Admin only. The new pulse and timeouts apply from the next run request.
+#[tauri::command]
+pub fn save_run_request_settings_tauri(app: tauri::AppHandle, run_request: RunRequestSettings) -> Result<(), String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+    if run_request.arrival_timeout_ms < run_request.pulse_ms.saturating_add(run_request.start_timeout_ms) {
+        return Err("Arrival timeout must cover the pulse and the start timeout".into());
+    }
+
+    let mut settings = crate::settings::load_settings(&app);
+    settings.runRequest = run_request;
+    crate::settings::save_settings(&app, &settings)?;
+    run_request::set_run_request_settings(&settings.runRequest);
+    Ok(())
+}
+
+/// Most recent run requests first.
+#[tauri::command]
+pub fn list_run_requests_tauri() -> Result<Vec<RunRequest>, String> {
+    let mut conn = establish_connection()?;
+    run_request::list_run_requests(&mut conn).map_err(|e| e.to_string())
+}
+
+/// The stored password is never sent back; it comes back empty.
+#[tauri::command]
+pub fn get_opc_security_tauri(app: tauri::AppHandle) -> OpcSecuritySettings {
+    OpcSecuritySettings { password: String::new(), ..crate::settings::load_settings(&app).opcSecurity }
+}
+
+/// Admin only. Stores the policy, mode, login, certificates and trust lists
+/// the OPC client connects with. The client reads them when the app starts.
+#[tauri::command]
+pub fn save_opc_security_tauri(app: tauri::AppHandle, security: OpcSecuritySettings) -> Result<(), String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+
+    let mut settings = crate::settings::load_settings(&app);
+    let security = OpcSecuritySettings {
+        // Keep the stored password when the form sends it back empty.
+        password: if security.password.is_empty() && security.username == settings.opcSecurity.username {
+            settings.opcSecurity.password.clone()
+        } else {
+            security.password.clone()
+        },
+        ..security
+    };
+    security::validate_security(&security)?;
+
+    settings.opcSecurity = security;
+    crate::settings::save_settings(&app, &settings)
+}
+
+/// Alarms that are still on or not yet acknowledged, newest first.
+/// Changes are pushed as `alarm` events.
+#[tauri::command]
+pub fn list_active_alarms_tauri() -> Result<Vec<Alarm>, String> {
+    let mut conn = establish_connection()?;
+    alarms::list_active_alarms(&mut conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn acknowledge_alarm_tauri(app: tauri::AppHandle, alarm_id: i32) -> Result<Alarm, String> {
+    let mut conn = establish_connection()?;
+    let alarm = alarms::acknowledge_alarm(&mut conn, alarm_id)?;
+    let _ = app.emit(alarms::ALARM_EVENT, &alarm);
+    Ok(alarm)
+}
+
+/// Most recent alarms first, cleared and acknowledged ones included.
+#[tauri::command]
+pub fn list_alarm_history_tauri() -> Result<Vec<Alarm>, String> {
+    let mut conn = establish_connection()?;
+    alarms::list_alarm_history(&mut conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_alarm_settings_tauri(app: tauri::AppHandle) -> AlarmSettings {
+    crate::settings::load_settings(&app).alarms
+}
+
+/// Admin only. Replaces the PLC tags watched for alarms and what their values
+/// mean. The alarm monitor subscribes to them when the app starts.
+#[tauri::command]
+pub fn save_alarm_settings_tauri(app: tauri::AppHandle, alarms: AlarmSettings) -> Result<(), String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+    alarms::validate_alarm_settings(&alarms)?;
+
+    let mut settings = crate::settings::load_settings(&app);
+    settings.alarms = alarms;
+    crate::settings::save_settings(&app, &settings)
+}
+
+#[tauri::command]
+pub fn list_motion_profiles_tauri() -> Result<Vec<MotionProfile>, String> {
+    let mut conn = establish_connection()?;
+    MotionProfileRepo::list_all(&mut conn).map_err(|e| e.to_string())
+}
+
+/// Admin only. Creates the profile, or updates the one with the same name.
+#[tauri::command]
+pub fn save_motion_profile_tauri(name: String, speed: i32, acceleration: i32) -> Result<MotionProfile, String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+
+    let profile = NewMotionProfile { name: name.trim().to_string(), speed, acceleration };
+    motion_profile::validate_motion_profile(&profile, &tag_map::tag_map())?;
+    MotionProfileRepo::upsert(&mut conn, &profile).map_err(|e| e.to_string())
+}
+
+/// Admin only. Past applications of the profile are kept.
+#[tauri::command]
+pub fn delete_motion_profile_tauri(profile_id: i32) -> Result<(), String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+
+    match MotionProfileRepo::delete(&mut conn, profile_id).map_err(|e| e.to_string())? {
+        0 => Err(format!("Motion profile {profile_id} does not exist")),
+        _ => Ok(()),
+    }
+}
+
+/// Admin only. Writes the profile's speed and acceleration to the PLC,
+/// verifies them and records the profile as active. Refused while the PLC is
+/// offline or the conveyor is not known to be stopped.
+#[tauri::command]
+pub async fn apply_motion_profile_tauri(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    profile_id: i32,
+) -> Result<MotionProfileApplication, String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+    if state.controller.kind() != ControllerKind::Opc {
+        return Err("Motion profiles can only be applied to the PLC".into());
+    }
+
+    let profile = motion_profile::find_motion_profile(&mut conn, profile_id)?;
+    motion_profile::apply_motion_profile(&app, &mut conn, &state.opc, &profile).await
+}
+
+/// The last profile applied, or none if no profile has been applied yet.
+#[tauri::command]
+pub fn get_active_motion_profile_tauri() -> Result<Option<MotionProfileApplication>, String> {
+    let mut conn = establish_connection()?;
+    MotionProfileRepo::latest_application(&mut conn).map_err(|e| e.to_string())
+}
+
+/// Most recent applications first.
+#[tauri::command]
+pub fn list_motion_profile_history_tauri() -> Result<Vec<MotionProfileApplication>, String> {
+    let mut conn = establish_connection()?;
+    motion_profile::list_motion_profile_history(&mut conn).map_err(|e| e.to_string())
 }
@@ -0,0 +1,183 @@
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use diesel::{PgConnection, QueryResult};
+use serde::Serialize;
+
+use crate::db::{connection::establish_connection, run_request_repo::RunRequestRepo};
+use crate::domain::session;
+use crate::model::{NewRunRequest, RunRequest, RunRequestOutcome};
+use crate::opc::opc_client::OpcClient;
+use crate::opc::tag_map::{self, OpcSignal};
+use crate::opc::telemetry::{self, ConveyorState, ConveyorStatus};
+use crate::settings::appsettings::RunRequestSettings;
+
+static RUN_SETTINGS: RwLock<Option<RunRequestSettings>> = RwLock::new(None);
+
+/// Only one run request may drive the conveyor at a time.
+static RUN_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// How often telemetry is checked while waiting for the conveyor.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How many requests the log view shows.
+const RUN_REQUESTS_LISTED: i64 = 100;
+
+pub fn set_run_request_settings(settings: &RunRequestSettings) {
+    let mut w = RUN_SETTINGS.write().unwrap();
+    *w = Some(settings.clone());
+}
+
+pub fn run_request_settings() -> RunRequestSettings {
+    RUN_SETTINGS.read().unwrap().clone().unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunRequestResult {
+    pub target_slot: i16,
+    pub outcome: RunRequestOutcome,
+    pub detail: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// What the latest telemetry means for a pending request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunProgress {
+    /// The conveyor has not started yet.
+    Waiting,
+    Moving,
+    Finished(RunRequestOutcome, Option<String>),
+}
+
+/// Decides where a run request stands. `moving_seen` is whether the
+/// conveyor has been seen running since the pulse, and `start_timed_out`
+/// whether the start timeout has passed without that. Arrival is judged by
+/// the slot the PLC measures at the load station, not the one it was sent
+/// to. A conveyor that never ran but stands idle on the requested slot was
+/// already there.
+pub fn evaluate_progress(
+    status: &ConveyorStatus,
+    target_slot: i16,
+    moving_seen: bool,
+    start_timed_out: bool,
+) -> RunProgress {
+    match status.state {
+        ConveyorState::Faulted => RunProgress::Finished(
+            RunRequestOutcome::Fault,
+            Some(status.fault.clone().unwrap_or_else(|| "conveyor faulted".into())),
+        ),
+        ConveyorState::Running => RunProgress::Moving,
+        ConveyorState::Idle if moving_seen => match status.actual_slot {
+            Some(actual) if actual == i64::from(target_slot) => RunProgress::Finished(RunRequestOutcome::Success, None),
+            Some(actual) => RunProgress::Finished(
+                RunRequestOutcome::Fault,
+                Some(format!("conveyor stopped at slot {actual}, expected {target_slot}")),
+            ),
+            None => RunProgress::Finished(
+                RunRequestOutcome::Fault,
+                Some("conveyor stopped but the PLC did not report its position".into()),
+            ),
+        },
+        ConveyorState::Idle if start_timed_out && status.actual_slot == Some(i64::from(target_slot)) => {
+            RunProgress::Finished(RunRequestOutcome::Success, Some("conveyor was already at the slot".into()))
+        }
+        _ if moving_seen => RunProgress::Moving,
+        _ => RunProgress::Waiting,
+    }
+}
+
+/// Sends a run request for `target_slot` and waits for the conveyor to
+/// move there. Every request is logged with its outcome and duration.
+pub async fn request_slot_run(opc: &OpcClient, target_slot: i16) -> Result<RunRequestResult, String> {
+    let _guard = RUN_LOCK.lock().await;
+    let settings = run_request_settings();
+    let started = Instant::now();
+
+    let (outcome, detail) = match send_pulse(opc, target_slot, &settings).await {
+        Ok(()) => wait_for_arrival(target_slot, &settings, started).await,
+        Err(e) => (RunRequestOutcome::Error, Some(e)),
+    };
+
+    let result = RunRequestResult {
+        target_slot,
+        outcome,
+        detail,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    println!(
+        "[RunRequest] slot {} -> {} in {} ms{}",
+        result.target_slot,
+        result.outcome.as_str(),
+        result.duration_ms,
+        result.detail.as_deref().map(|d| format!(" ({d})")).unwrap_or_default()
+    );
+    log_request(&result);
+
+    match result.outcome {
+        RunRequestOutcome::Error => Err(result.detail.clone().unwrap_or_default()),
+        _ => Ok(result),
+    }
+}
+
+/// Writes the target slot, then holds the run request flag on for the
+/// configured pulse. The flag is always turned off again.
+async fn send_pulse(opc: &OpcClient, target_slot: i16, settings: &RunRequestSettings) -> Result<(), String> {
+    tag_map::write_integer(opc, OpcSignal::TargetSlot, target_slot.into()).await?;
+    tag_map::write_bool(opc, OpcSignal::RunRequest, false).await?;
+
+    let raised = tag_map::write_bool(opc, OpcSignal::RunRequest, true).await;
+    if raised.is_ok() {
+        tokio::time::sleep(Duration::from_millis(settings.pulse_ms)).await;
+    }
+    let lowered = tag_map::write_bool(opc, OpcSignal::RunRequest, false).await;
+
+    raised.and(lowered)
+}
+
+async fn wait_for_arrival(
+    target_slot: i16,
+    settings: &RunRequestSettings,
+    started: Instant,
+) -> (RunRequestOutcome, Option<String>) {
+    let pulse_done = Instant::now();
+    let start_timeout = Duration::from_millis(settings.start_timeout_ms);
+    let arrival_timeout = Duration::from_millis(settings.arrival_timeout_ms);
+    let mut moving_seen = false;
+
+    loop {
+        let start_timed_out = pulse_done.elapsed() >= start_timeout;
+        match evaluate_progress(&telemetry::last_status(), target_slot, moving_seen, start_timed_out) {
+            RunProgress::Finished(outcome, detail) => return (outcome, detail),
+            RunProgress::Moving => moving_seen = true,
+            RunProgress::Waiting => {
+                if start_timed_out {
+                    return (RunRequestOutcome::Timeout, Some("conveyor did not start".into()));
+                }
+            }
+        }
+        if started.elapsed() >= arrival_timeout {
+            return (RunRequestOutcome::Timeout, Some("conveyor did not reach the slot".into()));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn log_request(result: &RunRequestResult) {
+    let entry = NewRunRequest {
+        target_slot: result.target_slot.into(),
+        outcome: result.outcome.as_str().to_string(),
+        detail: result.detail.clone(),
+        duration_ms: result.duration_ms as i64,
+        requested_by: session::active_session().map(|s| s.user_id),
+    };
+    let logged = establish_connection().and_then(|mut conn| {
+        RunRequestRepo::insert(&mut conn, &entry).map_err(|e| e.to_string())
+    });
+    if let Err(e) = logged {
+        eprintln!("[RunRequest] Failed to log request: {e}");
+    }
+}
+
+pub fn list_run_requests(conn: &mut PgConnection) -> QueryResult<Vec<RunRequest>> {
+    RunRequestRepo::list_recent(conn, RUN_REQUESTS_LISTED)
+}
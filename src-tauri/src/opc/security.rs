@@ -0,0 +1,187 @@
+use open62541::{ua, AsyncClient, Certificate, ClientBuilder, CustomCertificateVerification, DataType, PrivateKey};
+use open62541_sys::{UA_ByteString, UA_CertificateVerification, UA_CertificateVerification_Trustlist};
+
+use crate::settings::appsettings::OpcSecuritySettings;
+
+const POLICY_URI_PREFIX: &str = "http://opcfoundation.org/UA/SecurityPolicy#";
+
+/// Security policies the client supports. The deprecated Basic128Rsa15 and
+/// Basic256 policies are left out on purpose.
+pub const SECURITY_POLICIES: [&str; 4] = ["None", "Basic256Sha256", "Aes128_Sha256_RsaOaep", "Aes256_Sha256_RsaPss"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityMode {
+    None,
+    Sign,
+    SignAndEncrypt,
+}
+
+impl SecurityMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SecurityMode::None => "none",
+            SecurityMode::Sign => "sign",
+            SecurityMode::SignAndEncrypt => "signAndEncrypt",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(Self::None),
+            "sign" => Some(Self::Sign),
+            "signAndEncrypt" => Some(Self::SignAndEncrypt),
+            _ => None,
+        }
+    }
+
+    fn to_ua(self) -> ua::MessageSecurityMode {
+        match self {
+            SecurityMode::None => ua::MessageSecurityMode::NONE,
+            SecurityMode::Sign => ua::MessageSecurityMode::SIGN,
+            SecurityMode::SignAndEncrypt => ua::MessageSecurityMode::SIGNANDENCRYPT,
+        }
+    }
+}
+
+pub fn policy_uri(policy: &str) -> String {
+    format!("{POLICY_URI_PREFIX}{policy}")
+}
+
+/// Checks the policy, mode and credentials fit together.
+pub fn validate_security(security: &OpcSecuritySettings) -> Result<SecurityMode, String> {
+    let mode = SecurityMode::from_db(&security.security_mode)
+        .ok_or_else(|| format!("Unknown security mode: {}", security.security_mode))?;
+    if !SECURITY_POLICIES.contains(&security.security_policy.as_str()) {
+        return Err(format!("Unknown security policy: {}", security.security_policy));
+    }
+
+    let secured = mode != SecurityMode::None;
+    if secured != (security.security_policy != "None") {
+        return Err(format!(
+            "Security mode {} cannot be used with policy {}",
+            mode.as_str(),
+            security.security_policy
+        ));
+    }
+    if secured && (security.certificate_path.trim().is_empty() || security.private_key_path.trim().is_empty()) {
+        return Err("A client certificate and private key are required for sign and signAndEncrypt".into());
+    }
+    if secured && !security.accept_any_server_certificate && security.trusted_certificate_paths.is_empty() {
+        return Err("Trust the server certificate or its CA, or accept any server certificate".into());
+    }
+    if security.username.trim().is_empty() && !security.password.is_empty() {
+        return Err("A password was given without a username".into());
+    }
+    Ok(mode)
+}
+
+fn read_file(path: &str, what: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path.trim()).map_err(|e| format!("Cannot read {} {}: {}", what, path, e))
+}
+
+fn read_certificates(paths: &[String], what: &str) -> Result<Vec<ua::ByteString>, String> {
+    paths.iter().map(|path| read_file(path, what).map(|bytes| ua::ByteString::new(&bytes))).collect()
+}
+
+/// Verifies the server certificate against the configured trust and issuer
+/// lists, using open62541's own mbedTLS checks. The Rust bindings only offer
+/// accept-all or custom verification, so this wraps the C trust list.
+struct TrustList(UA_CertificateVerification);
+
+impl TrustList {
+    fn load(security: &OpcSecuritySettings) -> Result<Self, String> {
+        let trusted = read_certificates(&security.trusted_certificate_paths, "trusted certificate")?;
+        let issuers = read_certificates(&security.issuer_certificate_paths, "issuer certificate")?;
+
+        // SAFETY: ua::ByteString is a transparent wrapper of UA_ByteString,
+        // and the trust list copies the certificates it parses.
+        let mut inner: UA_CertificateVerification = unsafe { std::mem::zeroed() };
+        let status = unsafe {
+            UA_CertificateVerification_Trustlist(
+                &mut inner,
+                trusted.as_ptr().cast::<UA_ByteString>(),
+                trusted.len(),
+                issuers.as_ptr().cast::<UA_ByteString>(),
+                issuers.len(),
+                std::ptr::null(),
+                0,
+            )
+        };
+        let list = TrustList(inner);
+        let status = ua::StatusCode::clone_raw(&status);
+        if !status.is_good() {
+            return Err(format!("Cannot load the trusted certificates: {}", status.name()));
+        }
+        Ok(list)
+    }
+}
+
+impl CustomCertificateVerification for TrustList {
+    fn verify_certificate(&self, certificate: &ua::ByteString) -> ua::StatusCode {
+        let status = match self.0.verifyCertificate {
+            // SAFETY: The callback was set up by the trust list for `self.0`.
+            Some(verify) => unsafe { verify(&self.0, certificate.as_ptr()) },
+            None => return ua::StatusCode::BADCERTIFICATEUNTRUSTED,
+        };
+        ua::StatusCode::clone_raw(&status)
+    }
+
+    fn verify_application_uri(&self, certificate: &ua::ByteString, application_uri: &ua::String) -> ua::StatusCode {
+        let status = match self.0.verifyApplicationURI {
+            // SAFETY: As above.
+            Some(verify) => unsafe { verify(&self.0, certificate.as_ptr(), application_uri.as_ptr()) },
+            None => return ua::StatusCode::BADCERTIFICATEURIINVALID,
+        };
+        ua::StatusCode::clone_raw(&status)
+    }
+}
+
+impl Drop for TrustList {
+    fn drop(&mut self) {
+        if let Some(clear) = self.0.clear {
+            // SAFETY: Frees what the trust list allocated, exactly once.
+            unsafe { clear(&mut self.0) };
+        }
+    }
+}
+
+/// Builds and connects a client with the configured security. This blocks
+/// until the session is open, so call it from a blocking task.
+pub fn connect_client(endpoint_url: &str, security: &OpcSecuritySettings) -> Result<AsyncClient, String> {
+    let mode = validate_security(security)?;
+
+    let mut builder = if mode == SecurityMode::None {
+        ClientBuilder::default()
+    } else {
+        let certificate = Certificate::from_bytes(&read_file(&security.certificate_path, "client certificate")?);
+        let private_key = PrivateKey::from_bytes(&read_file(&security.private_key_path, "private key")?);
+        ClientBuilder::default_encryption(&certificate, &private_key)
+            .map_err(|e| format!("Invalid client certificate or key: {e:?}"))?
+    };
+
+    let policy = ua::String::new(&policy_uri(&security.security_policy)).map_err(|e| format!("{e:?}"))?;
+    builder = builder.security_mode(mode.to_ua()).security_policy_uri(policy);
+
+    if !security.username.trim().is_empty() {
+        let token = ua::UserNameIdentityToken::new(security.username.trim(), &security.password);
+        builder = builder.user_identity_token(&ua::UserIdentityToken::UserName(token));
+    }
+    if !security.application_uri.trim().is_empty() {
+        let description = ua::ApplicationDescription::init()
+            .with_application_uri(security.application_uri.trim())
+            .with_application_name("", "White Conveyors OAS")
+            .with_application_type(ua::ApplicationType::CLIENT);
+        builder = builder.client_description(description);
+    }
+    if security.accept_any_server_certificate {
+        builder = builder.accept_all();
+    } else if mode != SecurityMode::None {
+        let trust_list = TrustList::load(security)?;
+        builder = builder.certificate_verification(ua::CertificateVerification::custom(trust_list));
+    }
+
+    builder
+        .connect(endpoint_url)
+        .map(|client| client.into_async())
+        .map_err(|e| format!("{e:?}"))
+}
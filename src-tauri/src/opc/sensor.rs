@@ -2,17 +2,17 @@ use std::sync::{
     Arc,
     atomic::{AtomicBool, Ordering},
 };
-use tokio::time::{sleep, Duration};
+use tokio::time::{sleep, timeout, Duration};
 
-use crate::opc::{opc_client::OpcClient, opc_commands::get_load_hanger_sensor};
+use crate::controller::ConveyorController;
 
 
 pub async fn hanger_poll_loop(
-    opc_client: OpcClient,
+    controller: Arc<dyn ConveyorController>,
     detected: Arc<AtomicBool>,
 ) {
     loop {
-        match get_load_hanger_sensor(&opc_client).await {
+        match controller.hanger_sensor().await {
             Ok(value) => {
                 let sensed = value;
 
@@ -27,3 +27,26 @@ pub async fn hanger_poll_loop(
         sleep(Duration::from_millis(10)).await;
     }
 }
+
+/// Watches the load station hanger sensor for `window` and returns true once it
+/// goes from clear to detected. A hanger already in front of the sensor when the
+/// wait starts does not count; the sensor has to clear first.
+pub async fn wait_for_hanger(controller: &dyn ConveyorController, window: Duration) -> bool {
+    timeout(window, async {
+        let mut last = None;
+        loop {
+            match controller.hanger_sensor().await {
+                Ok(sensed) => {
+                    if sensed && last == Some(false) {
+                        return;
+                    }
+                    last = Some(sensed);
+                }
+                Err(e) => eprintln!("Sensor poll error (retrying): {e}"),
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .is_ok()
+}
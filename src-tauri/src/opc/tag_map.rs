@@ -0,0 +1,425 @@
+use std::sync::RwLock;
+
+use open62541::{ua, ScalarValue, VariantValue};
+use serde::Serialize;
+
+use crate::opc::opc_client::OpcClient;
+use crate::settings::appsettings::{OpcTag, OpcTagMap};
+
+static TAG_MAP: RwLock<Option<OpcTagMap>> = RwLock::new(None);
+
+pub fn set_tag_map(map: &OpcTagMap) {
+    let mut w = TAG_MAP.write().unwrap();
+    *w = Some(map.clone());
+}
+
+pub fn tag_map() -> OpcTagMap {
+    TAG_MAP.read().unwrap().clone().unwrap_or_default()
+}
+
+/// The logical signals the app reads or writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpcSignal {
+    JogForward,
+    RunRequest,
+    TargetSlot,
+    HangerSensor,
+    NumberOfFrames,
+    SlotsPerFrame,
+    ConveyorGo,
+    ConveyorStatus,
+    InverterStatus,
+    ConveyorTarget,
+    OffsetInches,
+    ActualSlot,
+    Speed,
+    Acceleration,
+}
+
+impl OpcSignal {
+    pub const ALL: [OpcSignal; 14] = [
+        OpcSignal::JogForward,
+        OpcSignal::RunRequest,
+        OpcSignal::TargetSlot,
+        OpcSignal::HangerSensor,
+        OpcSignal::NumberOfFrames,
+        OpcSignal::SlotsPerFrame,
+        OpcSignal::ConveyorGo,
+        OpcSignal::ConveyorStatus,
+        OpcSignal::InverterStatus,
+        OpcSignal::ConveyorTarget,
+        OpcSignal::OffsetInches,
+        OpcSignal::ActualSlot,
+        OpcSignal::Speed,
+        OpcSignal::Acceleration,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OpcSignal::JogForward => "jogForward",
+            OpcSignal::RunRequest => "runRequest",
+            OpcSignal::TargetSlot => "targetSlot",
+            OpcSignal::HangerSensor => "hangerSensor",
+            OpcSignal::NumberOfFrames => "numberOfFrames",
+            OpcSignal::SlotsPerFrame => "slotsPerFrame",
+            OpcSignal::ConveyorGo => "conveyorGo",
+            OpcSignal::ConveyorStatus => "conveyorStatus",
+            OpcSignal::InverterStatus => "inverterStatus",
+            OpcSignal::ConveyorTarget => "conveyorTarget",
+            OpcSignal::OffsetInches => "offsetInches",
+            OpcSignal::ActualSlot => "actualSlot",
+            OpcSignal::Speed => "speed",
+            OpcSignal::Acceleration => "acceleration",
+        }
+    }
+
+    pub fn from_db(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|signal| signal.as_str() == s)
+    }
+
+    /// Whether the signal carries a flag rather than a number.
+    pub fn is_boolean(self) -> bool {
+        matches!(
+            self,
+            OpcSignal::JogForward | OpcSignal::RunRequest | OpcSignal::HangerSensor | OpcSignal::ConveyorGo
+        )
+    }
+
+    pub fn tag(self, map: &OpcTagMap) -> &OpcTag {
+        match self {
+            OpcSignal::JogForward => &map.jog_forward,
+            OpcSignal::RunRequest => &map.run_request,
+            OpcSignal::TargetSlot => &map.target_slot,
+            OpcSignal::HangerSensor => &map.hanger_sensor,
+            OpcSignal::NumberOfFrames => &map.number_of_frames,
+            OpcSignal::SlotsPerFrame => &map.slots_per_frame,
+            OpcSignal::ConveyorGo => &map.conveyor_go,
+            OpcSignal::ConveyorStatus => &map.conveyor_status,
+            OpcSignal::InverterStatus => &map.inverter_status,
+            OpcSignal::ConveyorTarget => &map.conveyor_target,
+            OpcSignal::OffsetInches => &map.offset_inches,
+            OpcSignal::ActualSlot => &map.actual_slot,
+            OpcSignal::Speed => &map.speed,
+            OpcSignal::Acceleration => &map.acceleration,
+        }
+    }
+
+    fn tag_mut(self, map: &mut OpcTagMap) -> &mut OpcTag {
+        match self {
+            OpcSignal::JogForward => &mut map.jog_forward,
+            OpcSignal::RunRequest => &mut map.run_request,
+            OpcSignal::TargetSlot => &mut map.target_slot,
+            OpcSignal::HangerSensor => &mut map.hanger_sensor,
+            OpcSignal::NumberOfFrames => &mut map.number_of_frames,
+            OpcSignal::SlotsPerFrame => &mut map.slots_per_frame,
+            OpcSignal::ConveyorGo => &mut map.conveyor_go,
+            OpcSignal::ConveyorStatus => &mut map.conveyor_status,
+            OpcSignal::InverterStatus => &mut map.inverter_status,
+            OpcSignal::ConveyorTarget => &mut map.conveyor_target,
+            OpcSignal::OffsetInches => &mut map.offset_inches,
+            OpcSignal::ActualSlot => &mut map.actual_slot,
+            OpcSignal::Speed => &mut map.speed,
+            OpcSignal::Acceleration => &mut map.acceleration,
+        }
+    }
+}
+
+const INTEGER_TYPES: [&str; 6] = ["SByte", "Byte", "Int16", "UInt16", "Int32", "UInt32"];
+
+/// Checks a tag's configured type can carry the signal.
+pub fn check_tag_type(signal: OpcSignal, tag: &OpcTag) -> Result<(), String> {
+    let ok = if signal.is_boolean() {
+        tag.data_type == "Boolean"
+    } else {
+        INTEGER_TYPES.contains(&tag.data_type.as_str())
+    };
+    if !ok {
+        return Err(format!(
+            "{} ({}) is configured as {}, which cannot carry a {}",
+            signal.as_str(),
+            tag.node_id,
+            tag.data_type,
+            if signal.is_boolean() { "flag" } else { "number" }
+        ));
+    }
+    Ok(())
+}
+
+pub fn node_id(tag: &OpcTag) -> Result<ua::NodeId, String> {
+    tag.node_id
+        .trim()
+        .parse::<ua::NodeId>()
+        .map_err(|e| format!("Invalid node id {:?}: {}", tag.node_id, e))
+}
+
+/// How many bits a value of an integer or flag type has.
+pub fn bit_width(data_type: &str) -> Option<u32> {
+    Some(match data_type {
+        "Boolean" => 1,
+        "SByte" | "Byte" => 8,
+        "Int16" | "UInt16" => 16,
+        "Int32" | "UInt32" => 32,
+        "Int64" | "UInt64" => 64,
+        _ => return None,
+    })
+}
+
+/// Builds the value to write for an integer signal in the tag's configured type.
+pub fn encode_integer(tag: &OpcTag, value: i64) -> Result<ua::Variant, String> {
+    let out_of_range = || format!("{} does not fit {} at {}", value, tag.data_type, tag.node_id);
+    Ok(match tag.data_type.as_str() {
+        "SByte" => ua::Variant::scalar(ua::SByte::new(i8::try_from(value).map_err(|_| out_of_range())?)),
+        "Byte" => ua::Variant::scalar(ua::Byte::new(u8::try_from(value).map_err(|_| out_of_range())?)),
+        "Int16" => ua::Variant::scalar(ua::Int16::new(i16::try_from(value).map_err(|_| out_of_range())?)),
+        "UInt16" => ua::Variant::scalar(ua::UInt16::new(u16::try_from(value).map_err(|_| out_of_range())?)),
+        "Int32" => ua::Variant::scalar(ua::Int32::new(i32::try_from(value).map_err(|_| out_of_range())?)),
+        "UInt32" => ua::Variant::scalar(ua::UInt32::new(u32::try_from(value).map_err(|_| out_of_range())?)),
+        other => return Err(format!("{} at {} is not an integer type", other, tag.node_id)),
+    })
+}
+
+/// The built-in type name of a value read from the server.
+pub fn scalar_type_name(value: &ScalarValue) -> &'static str {
+    match value {
+        ScalarValue::Boolean(_) => "Boolean",
+        ScalarValue::SByte(_) => "SByte",
+        ScalarValue::Byte(_) => "Byte",
+        ScalarValue::Int16(_) => "Int16",
+        ScalarValue::UInt16(_) => "UInt16",
+        ScalarValue::Int32(_) => "Int32",
+        ScalarValue::UInt32(_) => "UInt32",
+        ScalarValue::Int64(_) => "Int64",
+        ScalarValue::UInt64(_) => "UInt64",
+        ScalarValue::Float(_) => "Float",
+        ScalarValue::Double(_) => "Double",
+        ScalarValue::String(_) => "String",
+        _ => "Unsupported",
+    }
+}
+
+pub(crate) fn decode_integer(value: &ScalarValue) -> Option<i64> {
+    Some(match value {
+        ScalarValue::SByte(v) => v.value().into(),
+        ScalarValue::Byte(v) => v.value().into(),
+        ScalarValue::Int16(v) => v.value().into(),
+        ScalarValue::UInt16(v) => v.value().into(),
+        ScalarValue::Int32(v) => v.value().into(),
+        ScalarValue::UInt32(v) => v.value().into(),
+        ScalarValue::Int64(v) => v.value(),
+        _ => return None,
+    })
+}
+
+fn configured(signal: OpcSignal) -> Result<(OpcTag, ua::NodeId), String> {
+    let tag = signal.tag(&tag_map()).clone();
+    check_tag_type(signal, &tag)?;
+    let id = node_id(&tag)?;
+    Ok((tag, id))
+}
+
+pub async fn write_bool(opc: &OpcClient, signal: OpcSignal, value: bool) -> Result<(), String> {
+    let (_, id) = configured(signal)?;
+    opc.write_value(id, ua::DataValue::new(ua::Variant::scalar(ua::Boolean::new(value))))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn write_integer(opc: &OpcClient, signal: OpcSignal, value: i64) -> Result<(), String> {
+    let (tag, id) = configured(signal)?;
+    let variant = encode_integer(&tag, value)?;
+    opc.write_value(id, ua::DataValue::new(variant))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn read_raw(opc: &OpcClient, signal: OpcSignal) -> Result<ua::Variant, String> {
+    let (_, id) = configured(signal)?;
+    opc.read_value(id).await.map_err(|e| e.to_string())
+}
+
+pub async fn read_bool(opc: &OpcClient, signal: OpcSignal) -> Result<bool, String> {
+    let (tag, id) = configured(signal)?;
+    match opc.read_value(id).await.map_err(|e| e.to_string())?.to_value() {
+        VariantValue::Scalar(ScalarValue::Boolean(b)) => Ok(b.value()),
+        other => Err(format!("Expected Boolean at {}, got: {other:?}", tag.node_id)),
+    }
+}
+
+pub async fn read_integer(opc: &OpcClient, signal: OpcSignal) -> Result<i64, String> {
+    let (tag, id) = configured(signal)?;
+    match opc.read_value(id).await.map_err(|e| e.to_string())?.to_value() {
+        VariantValue::Scalar(v) => decode_integer(&v)
+            .ok_or_else(|| format!("Expected {} at {}, got {}", tag.data_type, tag.node_id, scalar_type_name(&v))),
+        other => Err(format!("Expected {} at {}, got: {other:?}", tag.data_type, tag.node_id)),
+    }
+}
+
+/// A variable from a UaExpert data access view export.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportedTag {
+    pub node_id: String,
+    pub browse_name: String,
+    pub data_type: String,
+}
+
+/// Splits an export into records of fields. Fields are separated by
+/// whitespace and may be quoted; quoted fields can span lines and use `""`
+/// for a literal quote.
+fn export_records(contents: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_field = false;
+    let mut in_quotes = false;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_quotes = true;
+                in_field = true;
+            }
+            '\n' | '\r' | ' ' | '\t' => {
+                if in_field {
+                    fields.push(std::mem::take(&mut field));
+                    in_field = false;
+                }
+                if c == '\n' && !fields.is_empty() {
+                    records.push(std::mem::take(&mut fields));
+                }
+            }
+            _ => {
+                field.push(c);
+                in_field = true;
+            }
+        }
+    }
+    if in_field {
+        fields.push(field);
+    }
+    if !fields.is_empty() {
+        records.push(fields);
+    }
+    records
+}
+
+/// Reads the tags from a UaExpert export such as conveyor.csv. Each record is
+/// server, index, node id, browse name, value, data type, timestamps, status.
+/// The node id cell may hold a second `nsu=` line, which is dropped.
+pub fn parse_uaexpert_export(contents: &str) -> Result<Vec<ExportedTag>, String> {
+    let tags: Vec<ExportedTag> = export_records(contents)
+        .into_iter()
+        .filter(|f| f.len() >= 6)
+        .filter_map(|f| {
+            let node_id = f[2].lines().next()?.trim().to_string();
+            if !(node_id.starts_with("ns=") || node_id.starts_with("i=")) {
+                return None;
+            }
+            Some(ExportedTag {
+                node_id,
+                browse_name: f[3].trim().to_string(),
+                data_type: f[5].trim().to_string(),
+            })
+        })
+        .collect();
+
+    if tags.is_empty() {
+        return Err("No tags found; expected a UaExpert data access view export".into());
+    }
+    Ok(tags)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagImportReport {
+    pub tags: OpcTagMap,
+    /// Signals whose browse name was found in the export.
+    pub matched: Vec<String>,
+    /// Signals left unchanged because their browse name was not in the export.
+    pub missing: Vec<String>,
+}
+
+/// Updates the node id and data type of every signal whose browse name is in
+/// the export. Fails if an exported tag has a type that cannot carry its signal.
+pub fn import_tags(map: &OpcTagMap, exported: &[ExportedTag]) -> Result<TagImportReport, String> {
+    let mut tags = map.clone();
+    let mut matched = Vec::new();
+    let mut missing = Vec::new();
+
+    for signal in OpcSignal::ALL {
+        let tag = signal.tag_mut(&mut tags);
+        match exported.iter().find(|e| e.browse_name == tag.browse_name) {
+            Some(e) => {
+                tag.node_id = e.node_id.clone();
+                tag.data_type = e.data_type.clone();
+                check_tag_type(signal, tag)?;
+                matched.push(signal.as_str().to_string());
+            }
+            None => missing.push(signal.as_str().to_string()),
+        }
+    }
+
+    Ok(TagImportReport { tags, matched, missing })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagCheck {
+    pub signal: String,
+    pub node_id: String,
+    pub data_type: String,
+    /// Type of the value the server returned, if it could be read.
+    pub server_type: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Reads every configured tag and compares its value type with the map.
+pub async fn verify_tag_map(opc: &OpcClient, map: &OpcTagMap) -> Vec<TagCheck> {
+    let mut checks = Vec::new();
+    for signal in OpcSignal::ALL {
+        let tag = signal.tag(map);
+        let mut check = TagCheck {
+            signal: signal.as_str().to_string(),
+            node_id: tag.node_id.clone(),
+            data_type: tag.data_type.clone(),
+            server_type: None,
+            error: None,
+        };
+
+        let id = match check_tag_type(signal, tag).and_then(|_| node_id(tag)) {
+            Ok(id) => id,
+            Err(e) => {
+                check.error = Some(e);
+                checks.push(check);
+                continue;
+            }
+        };
+
+        match opc.read_value(id).await {
+            Ok(v) => match v.to_value() {
+                VariantValue::Scalar(s) => {
+                    let found = scalar_type_name(&s);
+                    if found != tag.data_type {
+                        check.error = Some(format!("server has {}, map says {}", found, tag.data_type));
+                    }
+                    check.server_type = Some(found.to_string());
+                }
+                _ => check.error = Some("server returned no value".into()),
+            },
+            Err(e) => check.error = Some(e.to_string()),
+        }
+        checks.push(check);
+    }
+    checks
+}
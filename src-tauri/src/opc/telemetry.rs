@@ -0,0 +1,235 @@
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+use open62541::{ua, ScalarValue, VariantValue};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::opc::opc_client::{ConnectionState, OpcClient};
+use crate::opc::tag_map::{self, OpcSignal};
+use crate::settings::appsettings::ConveyorStatusCodes;
+
+/// Event emitted with the full [`ConveyorStatus`] whenever a telemetry tag changes.
+pub const CONVEYOR_STATUS_EVENT: &str = "conveyor_status";
+
+/// Signals the telemetry service subscribes to.
+pub const TELEMETRY_SIGNALS: [OpcSignal; 6] = [
+    OpcSignal::ConveyorGo,
+    OpcSignal::ConveyorStatus,
+    OpcSignal::InverterStatus,
+    OpcSignal::ConveyorTarget,
+    OpcSignal::OffsetInches,
+    OpcSignal::ActualSlot,
+];
+
+static STATUS_CODES: RwLock<Option<ConveyorStatusCodes>> = RwLock::new(None);
+
+pub fn set_status_codes(codes: &ConveyorStatusCodes) {
+    *STATUS_CODES.write().unwrap() = Some(codes.clone());
+}
+
+pub fn status_codes() -> ConveyorStatusCodes {
+    STATUS_CODES.read().unwrap().clone().unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConveyorState {
+    Running,
+    Idle,
+    Faulted,
+    /// No telemetry has been received yet.
+    Unknown,
+}
+
+/// Last-known conveyor telemetry. Values are kept while the PLC is offline.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConveyorStatus {
+    pub state: ConveyorState,
+    /// Why the conveyor is faulted, if it is.
+    pub fault: Option<String>,
+    pub conveyor_go: Option<bool>,
+    pub conveyor_status: Option<i64>,
+    pub inverter_status: Option<i64>,
+    /// Slot the PLC has been told to go to.
+    pub target_slot: Option<i64>,
+    pub offset_inches: Option<i64>,
+    /// Slot at the load station, as the PLC measures it.
+    pub actual_slot: Option<i64>,
+    pub plc_online: bool,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for ConveyorStatus {
+    fn default() -> Self {
+        Self {
+            state: ConveyorState::Unknown,
+            fault: None,
+            conveyor_go: None,
+            conveyor_status: None,
+            inverter_status: None,
+            target_slot: None,
+            offset_inches: None,
+            actual_slot: None,
+            plc_online: false,
+            updated_at: None,
+        }
+    }
+}
+
+static LAST_STATUS: RwLock<Option<ConveyorStatus>> = RwLock::new(None);
+
+pub fn last_status() -> ConveyorStatus {
+    LAST_STATUS.read().unwrap().clone().unwrap_or_default()
+}
+
+/// Works out the conveyor state from the raw PLC values. An inverter fault
+/// wins over everything else; a non-zero InverterStatus is its fault code.
+pub fn decode_state(
+    conveyor_go: Option<bool>,
+    conveyor_status: Option<i64>,
+    inverter_status: Option<i64>,
+    codes: &ConveyorStatusCodes,
+) -> (ConveyorState, Option<String>) {
+    if let Some(code) = inverter_status.filter(|c| *c != 0) {
+        return (ConveyorState::Faulted, Some(format!("Inverter fault code {code}")));
+    }
+    match conveyor_status {
+        Some(code) if code == codes.running => (ConveyorState::Running, None),
+        Some(code) if code == codes.stopped && conveyor_go == Some(true) => (ConveyorState::Running, None),
+        Some(code) if code == codes.stopped => (ConveyorState::Idle, None),
+        Some(code) => (ConveyorState::Faulted, Some(format!("Conveyor status code {code}"))),
+        None => match conveyor_go {
+            Some(true) => (ConveyorState::Running, None),
+            Some(false) => (ConveyorState::Idle, None),
+            None => (ConveyorState::Unknown, None),
+        },
+    }
+}
+
+/// Stores one telemetry value and re-derives the state.
+pub fn apply_value(status: &mut ConveyorStatus, signal: OpcSignal, value: &ua::Variant) -> Result<(), String> {
+    let scalar = match value.to_value() {
+        VariantValue::Scalar(v) => v,
+        other => return Err(format!("{}: expected a scalar, got {other:?}", signal.as_str())),
+    };
+    let integer = || {
+        tag_map::decode_integer(&scalar).ok_or_else(|| {
+            format!("{}: expected an integer, got {}", signal.as_str(), tag_map::scalar_type_name(&scalar))
+        })
+    };
+
+    match signal {
+        OpcSignal::ConveyorGo => match &scalar {
+            ScalarValue::Boolean(b) => status.conveyor_go = Some(b.value()),
+            other => return Err(format!("conveyorGo: expected Boolean, got {}", tag_map::scalar_type_name(other))),
+        },
+        OpcSignal::ConveyorStatus => status.conveyor_status = Some(integer()?),
+        OpcSignal::InverterStatus => status.inverter_status = Some(integer()?),
+        OpcSignal::ConveyorTarget => status.target_slot = Some(integer()?),
+        OpcSignal::OffsetInches => status.offset_inches = Some(integer()?),
+        OpcSignal::ActualSlot => status.actual_slot = Some(integer()?),
+        other => return Err(format!("{} is not a telemetry signal", other.as_str())),
+    }
+
+    (status.state, status.fault) = decode_state(status.conveyor_go, status.conveyor_status, status.inverter_status, &status_codes());
+    status.updated_at = Some(Utc::now());
+    Ok(())
+}
+
+fn update_and_notify(
+    on_change: &impl Fn(ConveyorStatus),
+    f: impl FnOnce(&mut ConveyorStatus) -> Result<(), String>,
+) {
+    let snapshot = {
+        let mut guard = LAST_STATUS.write().unwrap();
+        let status = guard.get_or_insert_with(ConveyorStatus::default);
+        if let Err(e) = f(status) {
+            eprintln!("[Telemetry] {e}");
+            return;
+        }
+        status.clone()
+    };
+    on_change(snapshot);
+}
+
+fn emitter(app: AppHandle) -> impl Fn(ConveyorStatus) + Clone + Send + 'static {
+    move |status| {
+        let _ = app.emit(CONVEYOR_STATUS_EVENT, status);
+    }
+}
+
+/// Replaces the status with one that did not come from the PLC, such as
+/// the simulator's, and emits it.
+pub fn publish_status(app: &AppHandle, status: ConveyorStatus) {
+    update_and_notify(&emitter(app.clone()), |s| {
+        *s = status;
+        Ok(())
+    });
+}
+
+/// Subscribes to the telemetry tags and emits [`CONVEYOR_STATUS_EVENT`] on
+/// every change. Tags are taken from the tag map at startup.
+pub async fn start_telemetry(app: AppHandle, opc: OpcClient) {
+    monitor_telemetry(opc, emitter(app)).await;
+}
+
+/// Keeps [`last_status`] up to date from the telemetry tags and calls
+/// `on_change` with every new status.
+pub async fn monitor_telemetry(opc: OpcClient, on_change: impl Fn(ConveyorStatus) + Clone + Send + 'static) {
+    let map = tag_map::tag_map();
+
+    for signal in TELEMETRY_SIGNALS {
+        let tag = signal.tag(&map).clone();
+        let node = match tag_map::check_tag_type(signal, &tag).and_then(|_| tag_map::node_id(&tag)) {
+            Ok(node) => node,
+            Err(e) => {
+                eprintln!("[Telemetry] {} not monitored: {e}", signal.as_str());
+                continue;
+            }
+        };
+        let mut rx = match opc.subscribe_value(node).await {
+            Ok(rx) => rx,
+            Err(e) => {
+                eprintln!("[Telemetry] {} not monitored: {e}", signal.as_str());
+                continue;
+            }
+        };
+
+        let on_change = on_change.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(value) => update_and_notify(&on_change, |s| apply_value(s, signal, &value)),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    // Flag the values as last-known while the PLC is unreachable
+    let mut health_rx = opc.health_events();
+    let online = opc.is_connected();
+    update_and_notify(&on_change, |s| {
+        s.plc_online = online;
+        Ok(())
+    });
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let health = match health_rx.recv().await {
+                Ok(h) => h,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let online = health.state == ConnectionState::Connected;
+            if online != last_status().plc_online {
+                update_and_notify(&on_change, |s| {
+                    s.plc_online = online;
+                    Ok(())
+                });
+            }
+        }
+    });
+}
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 
 use crate::{
     db::{connection::establish_connection, customer_repo, garment_repo, ticket_repo},
-    model::{NewCustomer, UpdateTicket},
+    model::{GarmentState, NewCustomer, TicketStatus, UpdateTicket},
     pos::spot::{
         output::{
             conveyor_file_utils::{write_split_invoice_batch},
@@ -87,7 +87,7 @@ pub fn handle_delete_item_op(fields: &[String], conn: &mut PgConnection, fm: &Fi
     }
 
     let ticket_info = ticket_repo::get_ticket_by_invoice_number(conn, &full_invoice_number)?;
-    if ticket_info.ticket_status == "Processing" {
+    if ticket_info.ticket_status == TicketStatus::Processing.as_str() {
         return Ok(false);
     }
 
@@ -197,7 +197,7 @@ pub fn create_garment_from_add_op(conn: &mut PgConnection, add_op: &spotops_type
         invoice_dropoff_date: add_op.invoice_dropoff_date.naive_local(),
         invoice_pickup_date: add_op.invoice_promised_date.naive_local(),
         slot_number: add_op.slot_occupancy as i32,
-        garment_state: "Not Processed".to_string(),
+        garment_state: GarmentState::NotProcessed.as_str().to_string(),
     }).map(|_| ()).map_err(|e| format!("CREATE_GARMENT_FAILED: {}", e))
 }
 
@@ -212,7 +212,7 @@ pub fn create_ticket_from_add_op(conn: &mut PgConnection, add_op: &spotops_types
         number_of_items: add_op.num_items as i32,
         invoice_dropoff_date: add_op.invoice_dropoff_date.naive_local(),
         invoice_pickup_date: add_op.invoice_promised_date.naive_local(),
-        ticket_status: "Not Processed".to_string(),
+        ticket_status: TicketStatus::NotProcessed.as_str().to_string(),
     }).map(|_| ()).map_err(|e| format!("CREATE_TICKET_FAILED: {}", e))
 }
 
@@ -4,7 +4,7 @@ use diesel::PgConnection;
 
 use crate::{
     db::{connection::establish_connection, customer_repo, garment_repo, ticket_repo},
-    model::{NewCustomer, NewGarment, NewTicket},
+    model::{GarmentState, NewCustomer, NewGarment, NewTicket, TicketStatus},
     settings::appsettings::FieldMappings,
 };
 
@@ -84,7 +84,7 @@ fn handle_garment_create(
             invoice_dropoff_date: dropoff_date,
             invoice_pickup_date: pickup_date,
             slot_number: slot_occupancy as i32,
-            garment_state: "Not Processed".to_string(),
+            garment_state: GarmentState::NotProcessed.as_str().to_string(),
         }).map_err(|e| format!("CREATE_GARMENT_FAILED: {e}"))?;
     }
 
@@ -99,7 +99,7 @@ fn handle_garment_create(
             number_of_items: num_items as i32,
             invoice_dropoff_date: dropoff_date,
             invoice_pickup_date: pickup_date,
-            ticket_status: "Not Processed".to_string(),
+            ticket_status: TicketStatus::NotProcessed.as_str().to_string(),
         }).map_err(|e| format!("CREATE_TICKET_FAILED: {e}"))?;
     }
 
@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use diesel::prelude::*;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::db::{connection::establish_connection, slot_repo::SlotRepo};
+use crate::slot_manager::SlotManager;
+
+/// How often the sweeper looks for stale reservations.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Event emitted after the sweeper releases one or more reservations.
+pub const RESERVATIONS_EXPIRED_EVENT: &str = "slot_reservations_expired";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpiredReservation {
+    pub slot_number: i32,
+    pub ticket: Option<String>,
+    pub reserved_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Releases every reservation older than `ttl_secs` that has no garments loaded.
+pub fn release_expired_reservations(
+    conn: &mut PgConnection,
+    ttl_secs: i32,
+) -> QueryResult<Vec<ExpiredReservation>> {
+    conn.transaction::<Vec<ExpiredReservation>, diesel::result::Error, _>(|conn| {
+        let mut released = Vec::new();
+
+        for slot in SlotRepo::find_expired_reservations(conn, ttl_secs)? {
+            // Skip slots that were loaded or freed since the query ran
+            if SlotRepo::release_reservation(conn, slot.slot_number, "reservation expired")? {
+                released.push(ExpiredReservation {
+                    slot_number: slot.slot_number,
+                    ticket: slot.assigned_ticket,
+                    reserved_at: slot.reserved_at,
+                });
+            }
+        }
+
+        Ok(released)
+    })
+}
+
+/// Spawns the background task that periodically releases expired reservations.
+pub fn start_reservation_sweeper(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(SWEEP_INTERVAL).await;
+
+            let ttl_secs = SlotManager::allocation_settings().reservation_ttl_secs;
+            if ttl_secs <= 0 {
+                continue;
+            }
+
+            let mut conn = match establish_connection() {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("[ReservationSweeper] DB unavailable: {}", e);
+                    continue;
+                }
+            };
+
+            match release_expired_reservations(&mut conn, ttl_secs) {
+                Ok(released) if released.is_empty() => {}
+                Ok(released) => {
+                    for r in &released {
+                        println!(
+                            "[ReservationSweeper] Released slot {} (ticket {:?}, reserved at {:?})",
+                            r.slot_number, r.ticket, r.reserved_at
+                        );
+                    }
+                    let _ = app.emit(RESERVATIONS_EXPIRED_EVENT, released);
+                }
+                Err(e) => eprintln!("[ReservationSweeper] Sweep failed: {}", e),
+            }
+        }
+    });
+}
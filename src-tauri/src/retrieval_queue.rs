@@ -0,0 +1,287 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+use crate::controller::ConveyorController;
+use crate::db::slot_repo::SlotRepo;
+use crate::model::{RunRequestOutcome, SlotState};
+use crate::opc::telemetry;
+
+/// Event emitted with the whole [`RetrievalQueue`] after every change.
+pub const RETRIEVAL_QUEUE_EVENT: &str = "retrieval_queue";
+
+/// How many finished items are kept for the operator to see.
+const FINISHED_KEPT: usize = 20;
+
+static QUEUE: Mutex<RetrievalQueue> = Mutex::new(RetrievalQueue::new());
+
+/// Wakes the worker when items are added or the queue is resumed.
+static WAKE: Notify = Notify::const_new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetrievalState {
+    Pending,
+    Running,
+    Arrived,
+    Failed,
+    Cancelled,
+}
+
+/// One slot to bring to the unload station.
+#[derive(Debug, Clone, Serialize)]
+pub struct RetrievalItem {
+    pub id: u64,
+    pub ticket: String,
+    pub slot_number: i32,
+    pub state: RetrievalState,
+    pub enqueued_at: DateTime<Utc>,
+    pub detail: Option<String>,
+    pub duration_ms: Option<u64>,
+}
+
+/// Slots waiting to be run to, in the order they will be run. The queue
+/// pauses after a failed run so the operator can check the conveyor.
+#[derive(Debug, Clone, Serialize)]
+pub struct RetrievalQueue {
+    pub paused: bool,
+    pub current: Option<RetrievalItem>,
+    pub pending: Vec<RetrievalItem>,
+    /// Most recent first.
+    pub finished: Vec<RetrievalItem>,
+    #[serde(skip)]
+    next_id: u64,
+}
+
+/// Slots the conveyor passes going forward from `from` to `to`.
+pub fn forward_distance(from: i32, to: i32, total_slots: i32) -> i32 {
+    if total_slots <= 0 {
+        return 0;
+    }
+    (to - from).rem_euclid(total_slots)
+}
+
+impl RetrievalQueue {
+    pub const fn new() -> Self {
+        Self { paused: false, current: None, pending: Vec::new(), finished: Vec::new(), next_id: 1 }
+    }
+
+    /// Queues each slot of a ticket. Slots already queued or running are
+    /// skipped. Returns the new item ids.
+    pub fn enqueue(&mut self, ticket: &str, slots: &[i32]) -> Vec<u64> {
+        let mut added = Vec::new();
+        for &slot_number in slots {
+            let queued = self.current.iter().chain(self.pending.iter()).any(|i| i.slot_number == slot_number);
+            if queued {
+                continue;
+            }
+            let id = self.next_id;
+            self.next_id += 1;
+            self.pending.push(RetrievalItem {
+                id,
+                ticket: ticket.to_string(),
+                slot_number,
+                state: RetrievalState::Pending,
+                enqueued_at: Utc::now(),
+                detail: None,
+                duration_ms: None,
+            });
+            added.push(id);
+        }
+        added
+    }
+
+    /// Removes a pending item. The running item cannot be cancelled.
+    pub fn cancel(&mut self, id: u64) -> bool {
+        let Some(idx) = self.pending.iter().position(|i| i.id == id) else {
+            return false;
+        };
+        let mut item = self.pending.remove(idx);
+        item.state = RetrievalState::Cancelled;
+        self.push_finished(item);
+        true
+    }
+
+    /// Moves a pending item to `position` (0 = next), clamped to the end.
+    pub fn move_to(&mut self, id: u64, position: usize) -> bool {
+        let Some(idx) = self.pending.iter().position(|i| i.id == id) else {
+            return false;
+        };
+        let item = self.pending.remove(idx);
+        let position = position.min(self.pending.len());
+        self.pending.insert(position, item);
+        true
+    }
+
+    /// Orders pending items so the conveyor visits them in one forward pass
+    /// from `from_slot`.
+    pub fn optimize(&mut self, from_slot: i32, total_slots: i32) {
+        self.pending
+            .sort_by_key(|i| forward_distance(from_slot, i.slot_number, total_slots));
+    }
+
+    /// Takes the next pending item unless paused or one is already running.
+    pub fn start_next(&mut self) -> Option<RetrievalItem> {
+        if self.paused || self.current.is_some() || self.pending.is_empty() {
+            return None;
+        }
+        let mut item = self.pending.remove(0);
+        item.state = RetrievalState::Running;
+        self.current = Some(item.clone());
+        Some(item)
+    }
+
+    /// Records how the running item ended; anything but arrival pauses the queue.
+    pub fn finish_current(&mut self, state: RetrievalState, detail: Option<String>, duration_ms: Option<u64>) {
+        let Some(mut item) = self.current.take() else { return };
+        item.state = state;
+        item.detail = detail;
+        item.duration_ms = duration_ms;
+        if state != RetrievalState::Arrived {
+            self.paused = true;
+        }
+        self.push_finished(item);
+    }
+
+    fn push_finished(&mut self, item: RetrievalItem) {
+        self.finished.insert(0, item);
+        self.finished.truncate(FINISHED_KEPT);
+    }
+
+    /// Where the conveyor last stopped, if known.
+    fn last_arrival(&self) -> Option<i32> {
+        self.finished
+            .iter()
+            .find(|i| i.state == RetrievalState::Arrived)
+            .map(|i| i.slot_number)
+    }
+}
+
+impl Default for RetrievalQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn snapshot() -> RetrievalQueue {
+    QUEUE.lock().unwrap().clone()
+}
+
+fn update(app: &AppHandle, f: impl FnOnce(&mut RetrievalQueue)) -> RetrievalQueue {
+    let queue = {
+        let mut q = QUEUE.lock().unwrap();
+        f(&mut q);
+        q.clone()
+    };
+    let _ = app.emit(RETRIEVAL_QUEUE_EVENT, &queue);
+    queue
+}
+
+/// Queues every occupied slot of each ticket. With `optimize` the whole
+/// pending list is reordered to minimise conveyor travel.
+pub fn enqueue_tickets(
+    conn: &mut PgConnection,
+    app: &AppHandle,
+    tickets: &[String],
+    optimize: bool,
+) -> Result<RetrievalQueue, String> {
+    let mut found = Vec::new();
+    for ticket in tickets {
+        let slots: Vec<i32> = SlotRepo::find_ticket_slots(conn, ticket)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|s| SlotState::from_db(&s.slot_state) == Some(SlotState::Occupied))
+            .map(|s| s.slot_number)
+            .collect();
+        if slots.is_empty() {
+            return Err(format!("Ticket {} is not on the conveyor", ticket));
+        }
+        found.push((ticket, slots));
+    }
+    let total_slots = SlotRepo::list_all(conn)
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|s| s.slot_number)
+        .max()
+        .unwrap_or(0);
+
+    let queue = update(app, |q| {
+        for (ticket, slots) in &found {
+            q.enqueue(ticket, slots);
+        }
+        if optimize {
+            let from = telemetry::last_status()
+                .actual_slot
+                .map(|s| s as i32)
+                .or_else(|| q.last_arrival())
+                .unwrap_or(1);
+            q.optimize(from, total_slots);
+        }
+    });
+    WAKE.notify_one();
+    Ok(queue)
+}
+
+pub fn cancel_item(app: &AppHandle, id: u64) -> Result<RetrievalQueue, String> {
+    let mut cancelled = false;
+    let queue = update(app, |q| cancelled = q.cancel(id));
+    if !cancelled {
+        return Err(format!("Retrieval {} is not pending", id));
+    }
+    Ok(queue)
+}
+
+pub fn move_item(app: &AppHandle, id: u64, position: usize) -> Result<RetrievalQueue, String> {
+    let mut moved = false;
+    let queue = update(app, |q| moved = q.move_to(id, position));
+    if !moved {
+        return Err(format!("Retrieval {} is not pending", id));
+    }
+    Ok(queue)
+}
+
+pub fn set_paused(app: &AppHandle, paused: bool) -> RetrievalQueue {
+    let queue = update(app, |q| q.paused = paused);
+    if !paused {
+        WAKE.notify_one();
+    }
+    queue
+}
+
+/// Spawns the worker that runs the conveyor to each queued slot in turn,
+/// starting the next only after the previous one has arrived.
+pub fn start_retrieval_worker(app: AppHandle, controller: Arc<dyn ConveyorController>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let next = QUEUE.lock().unwrap().start_next();
+            let Some(item) = next else {
+                WAKE.notified().await;
+                continue;
+            };
+            let _ = app.emit(RETRIEVAL_QUEUE_EVENT, snapshot());
+
+            let slot = match i16::try_from(item.slot_number) {
+                Ok(s) => s,
+                Err(_) => {
+                    update(&app, |q| q.finish_current(RetrievalState::Failed, Some("slot number out of range".into()), None));
+                    continue;
+                }
+            };
+
+            let (state, detail, duration) = match controller.run_to_slot(slot).await {
+                Ok(r) if r.outcome == RunRequestOutcome::Success => (RetrievalState::Arrived, None, Some(r.duration_ms)),
+                Ok(r) => (
+                    RetrievalState::Failed,
+                    Some(format!("{}: {}", r.outcome.as_str(), r.detail.unwrap_or_default())),
+                    Some(r.duration_ms),
+                ),
+                Err(e) => (RetrievalState::Failed, Some(e), None),
+            };
+            update(&app, |q| q.finish_current(state, detail, duration));
+        }
+    });
+}
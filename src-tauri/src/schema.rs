@@ -1,5 +1,18 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    alarms (id) {
+        id -> Int4,
+        source -> Varchar,
+        code -> Int8,
+        message -> Text,
+        raised_at -> Timestamp,
+        cleared_at -> Nullable<Timestamp>,
+        acknowledged_at -> Nullable<Timestamp>,
+        acknowledged_by -> Nullable<Int4>,
+    }
+}
+
 diesel::table! {
     app_state (id) {
         id -> Int4,
@@ -21,6 +34,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    conveyor_snapshots (id) {
+        id -> Int4,
+        scope -> Varchar,
+        payload -> Text,
+        slot_count -> Int4,
+        garment_count -> Int4,
+        created_by -> Nullable<Int4>,
+        created_at -> Timestamp,
+        restored_at -> Nullable<Timestamp>,
+        restored_by -> Nullable<Int4>,
+    }
+}
+
 diesel::table! {
     customers (id) {
         id -> Int4,
@@ -47,6 +74,74 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    inventory_audit_discrepancies (id) {
+        id -> Int4,
+        audit_id -> Int4,
+        kind -> Varchar,
+        item_id -> Varchar,
+        expected_slot -> Nullable<Int4>,
+        found_slot -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    inventory_audit_scans (id) {
+        id -> Int4,
+        audit_id -> Int4,
+        slot_number -> Int4,
+        item_id -> Varchar,
+        scanned_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    inventory_audits (id) {
+        id -> Int4,
+        audit_status -> Varchar,
+        started_by -> Nullable<Int4>,
+        started_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+        applied_by -> Nullable<Int4>,
+        applied_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    motion_profile_applications (id) {
+        id -> Int4,
+        profile_id -> Nullable<Int4>,
+        profile_name -> Varchar,
+        speed -> Int4,
+        acceleration -> Int4,
+        applied_by -> Nullable<Int4>,
+        applied_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    motion_profiles (id) {
+        id -> Int4,
+        name -> Varchar,
+        speed -> Int4,
+        acceleration -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    run_requests (id) {
+        id -> Int4,
+        target_slot -> Int4,
+        outcome -> Varchar,
+        detail -> Nullable<Text>,
+        duration_ms -> Int8,
+        requested_by -> Nullable<Int4>,
+        requested_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     sessions (id) {
         id -> Int4,
@@ -59,6 +154,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    slot_history (id) {
+        id -> Int4,
+        slot_number -> Int4,
+        from_state -> Nullable<Varchar>,
+        to_state -> Varchar,
+        ticket -> Nullable<Varchar>,
+        item_id -> Nullable<Varchar>,
+        user_id -> Nullable<Int4>,
+        session_id -> Nullable<Int4>,
+        reason -> Varchar,
+        changed_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     slots (slot_number) {
         slot_number -> Int4,
@@ -67,6 +177,13 @@ diesel::table! {
         item_id -> Nullable<Varchar>,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        frame_number -> Int4,
+        frame_position -> Int4,
+        retired -> Bool,
+        garment_count -> Int4,
+        reserved_at -> Nullable<Timestamp>,
+        state_reason -> Nullable<Varchar>,
+        blocked_by_layout -> Bool,
     }
 }
 
@@ -98,14 +215,26 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(inventory_audit_discrepancies -> inventory_audits (audit_id));
+diesel::joinable!(inventory_audit_scans -> inventory_audits (audit_id));
+diesel::joinable!(motion_profile_applications -> motion_profiles (profile_id));
 diesel::joinable!(sessions -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    alarms,
     app_state,
+    conveyor_snapshots,
     conveyoractivity,
     customers,
     garments,
+    inventory_audit_discrepancies,
+    inventory_audit_scans,
+    inventory_audits,
+    motion_profile_applications,
+    motion_profiles,
+    run_requests,
     sessions,
+    slot_history,
     slots,
     tickets,
     users,
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FrameConfig {
     pub latches: u8,
     pub slots: Vec<bool>,
@@ -136,6 +136,387 @@ impl Default for PrinterSettings {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PickupZone {
+    /// "today", "tomorrow", "thisWeek" or "later"
+    pub bucket: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub slot_start: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub slot_end: Option<i32>,
+    /// When non-empty, the zone is these frames and the slot range is ignored.
+    #[serde(default)]
+    pub frames: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PickupZoningSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub zones: Vec<PickupZone>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotAllocationSettings {
+    /// "spread", "linear" or "roundRobin"
+    #[serde(default = "default_allocation_strategy")]
+    pub strategy: String,
+    #[serde(default = "default_spread_min_distance")]
+    pub spread_min_distance: i32,
+    #[serde(default = "default_spread_threshold_pct")]
+    pub spread_threshold_pct: f64,
+    /// Garments that fit on one slot before a ticket spills onto another.
+    #[serde(default = "default_slot_capacity")]
+    pub slot_capacity: i32,
+    /// Seconds a reservation may sit with nothing loaded before it is released; 0 disables.
+    #[serde(default = "default_reservation_ttl_secs")]
+    pub reservation_ttl_secs: i32,
+    /// How long the hanger sensor has to confirm a load before the slot is flagged as an error.
+    #[serde(default = "default_load_confirm_timeout_ms")]
+    pub load_confirm_timeout_ms: u64,
+    /// Apply consistency repairs at startup instead of only logging what was found.
+    #[serde(default)]
+    pub repair_on_startup: bool,
+    #[serde(default)]
+    pub zoning: PickupZoningSettings,
+}
+
+fn default_allocation_strategy() -> String { "spread".to_string() }
+fn default_spread_min_distance() -> i32 { 5 }
+fn default_spread_threshold_pct() -> f64 { 50.0 }
+fn default_slot_capacity() -> i32 { 10 }
+fn default_reservation_ttl_secs() -> i32 { 300 }
+fn default_load_confirm_timeout_ms() -> u64 { 10_000 }
+
+impl Default for SlotAllocationSettings {
+    fn default() -> Self {
+        Self {
+            strategy: default_allocation_strategy(),
+            spread_min_distance: default_spread_min_distance(),
+            spread_threshold_pct: default_spread_threshold_pct(),
+            slot_capacity: default_slot_capacity(),
+            reservation_ttl_secs: default_reservation_ttl_secs(),
+            load_confirm_timeout_ms: default_load_confirm_timeout_ms(),
+            repair_on_startup: false,
+            zoning: PickupZoningSettings::default(),
+        }
+    }
+}
+
+/// One PLC signal on the OPC UA server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpcTag {
+    /// Name used to find the tag in a UaExpert export.
+    pub browse_name: String,
+    /// e.g. "ns=1;i=83"
+    pub node_id: String,
+    /// OPC UA built-in type name, e.g. "Boolean" or "Int16".
+    pub data_type: String,
+}
+
+impl OpcTag {
+    fn new(browse_name: &str, node_id: &str, data_type: &str) -> Self {
+        Self {
+            browse_name: browse_name.to_string(),
+            node_id: node_id.to_string(),
+            data_type: data_type.to_string(),
+        }
+    }
+}
+
+/// Where each logical signal lives on this site's PLC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpcTagMap {
+    #[serde(default = "default_jog_forward_tag")]
+    pub jog_forward: OpcTag,
+    #[serde(default = "default_run_request_tag")]
+    pub run_request: OpcTag,
+    #[serde(default = "default_target_slot_tag")]
+    pub target_slot: OpcTag,
+    #[serde(default = "default_hanger_sensor_tag")]
+    pub hanger_sensor: OpcTag,
+    #[serde(default = "default_number_of_frames_tag")]
+    pub number_of_frames: OpcTag,
+    #[serde(default = "default_slots_per_frame_tag")]
+    pub slots_per_frame: OpcTag,
+    #[serde(default = "default_conveyor_go_tag")]
+    pub conveyor_go: OpcTag,
+    #[serde(default = "default_conveyor_status_tag")]
+    pub conveyor_status: OpcTag,
+    #[serde(default = "default_inverter_status_tag")]
+    pub inverter_status: OpcTag,
+    #[serde(default = "default_conveyor_target_tag")]
+    pub conveyor_target: OpcTag,
+    #[serde(default = "default_offset_inches_tag")]
+    pub offset_inches: OpcTag,
+    #[serde(default = "default_actual_slot_tag")]
+    pub actual_slot: OpcTag,
+    #[serde(default = "default_speed_tag")]
+    pub speed: OpcTag,
+    #[serde(default = "default_acceleration_tag")]
+    pub acceleration: OpcTag,
+}
+
+fn default_jog_forward_tag() -> OpcTag { OpcTag::new("SlotRunning", "ns=1;i=81", "Boolean") }
+fn default_run_request_tag() -> OpcTag { OpcTag::new("SlotRunRequest", "ns=1;i=83", "Boolean") }
+fn default_target_slot_tag() -> OpcTag { OpcTag::new("SlotID1", "ns=1;i=267", "Int16") }
+fn default_hanger_sensor_tag() -> OpcTag { OpcTag::new("LoadStationHangerSensorDebounce", "ns=1;i=102", "Boolean") }
+fn default_number_of_frames_tag() -> OpcTag { OpcTag::new("NumberFrames", "ns=1;i=259", "Int16") }
+fn default_slots_per_frame_tag() -> OpcTag { OpcTag::new("NumberSlotsPerFrame", "ns=1;i=258", "Int16") }
+fn default_conveyor_go_tag() -> OpcTag { OpcTag::new("ConveyorGo", "ns=1;i=292", "Boolean") }
+fn default_conveyor_status_tag() -> OpcTag { OpcTag::new("ConveyorStatus", "ns=1;i=289", "Int16") }
+fn default_inverter_status_tag() -> OpcTag { OpcTag::new("InverterStatus", "ns=1;i=290", "Int16") }
+fn default_conveyor_target_tag() -> OpcTag { OpcTag::new("TargetSlot", "ns=1;i=291", "Int16") }
+fn default_offset_inches_tag() -> OpcTag { OpcTag::new("CSOFFSETINCHES", "ns=1;i=288", "Int16") }
+fn default_actual_slot_tag() -> OpcTag { OpcTag::new("ActualSlotID1", "ns=1;i=166", "Int16") }
+fn default_speed_tag() -> OpcTag { OpcTag::new("MyIAISpeed", "ns=1;i=286", "Int32") }
+fn default_acceleration_tag() -> OpcTag { OpcTag::new("MyAIAAcc", "ns=1;i=287", "Int16") }
+
+impl Default for OpcTagMap {
+    fn default() -> Self {
+        Self {
+            jog_forward: default_jog_forward_tag(),
+            run_request: default_run_request_tag(),
+            target_slot: default_target_slot_tag(),
+            hanger_sensor: default_hanger_sensor_tag(),
+            number_of_frames: default_number_of_frames_tag(),
+            slots_per_frame: default_slots_per_frame_tag(),
+            conveyor_go: default_conveyor_go_tag(),
+            conveyor_status: default_conveyor_status_tag(),
+            inverter_status: default_inverter_status_tag(),
+            conveyor_target: default_conveyor_target_tag(),
+            offset_inches: default_offset_inches_tag(),
+            actual_slot: default_actual_slot_tag(),
+            speed: default_speed_tag(),
+            acceleration: default_acceleration_tag(),
+        }
+    }
+}
+
+/// What the PLC program reports on the ConveyorStatus tag. Any other value
+/// is a fault code. The defaults match the original PLC program.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConveyorStatusCodes {
+    #[serde(default = "default_status_stopped")]
+    pub stopped: i64,
+    #[serde(default = "default_status_running")]
+    pub running: i64,
+}
+
+fn default_status_stopped() -> i64 { 0 }
+fn default_status_running() -> i64 { 1 }
+
+impl Default for ConveyorStatusCodes {
+    fn default() -> Self {
+        Self { stopped: default_status_stopped(), running: default_status_running() }
+    }
+}
+
+/// How the OPC client secures its connection and who it logs in as.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpcSecuritySettings {
+    /// "None", "Basic256Sha256", "Aes128_Sha256_RsaOaep" or "Aes256_Sha256_RsaPss"
+    #[serde(default = "default_opc_security_policy")]
+    pub security_policy: String,
+    /// "none", "sign" or "signAndEncrypt"
+    #[serde(default = "default_opc_security_mode")]
+    pub security_mode: String,
+    /// Anonymous when empty.
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    /// Client certificate (DER or PEM), required for sign and signAndEncrypt.
+    #[serde(default)]
+    pub certificate_path: String,
+    /// Unencrypted private key for the certificate (DER or PEM).
+    #[serde(default)]
+    pub private_key_path: String,
+    /// Sent to the server as this client's application URI. Must match the
+    /// URI in the client certificate; the library's default when empty.
+    #[serde(default)]
+    pub application_uri: String,
+    /// Server or CA certificates (DER or PEM) that are trusted.
+    #[serde(default)]
+    pub trusted_certificate_paths: Vec<String>,
+    /// Intermediate CA certificates used to complete the server's chain.
+    /// They are not trusted on their own.
+    #[serde(default)]
+    pub issuer_certificate_paths: Vec<String>,
+    /// Skip verifying the server certificate. Only for commissioning.
+    #[serde(default)]
+    pub accept_any_server_certificate: bool,
+}
+
+fn default_opc_security_policy() -> String { "None".to_string() }
+fn default_opc_security_mode() -> String { "none".to_string() }
+
+impl Default for OpcSecuritySettings {
+    fn default() -> Self {
+        Self {
+            security_policy: default_opc_security_policy(),
+            security_mode: default_opc_security_mode(),
+            username: String::new(),
+            password: String::new(),
+            certificate_path: String::new(),
+            private_key_path: String::new(),
+            application_uri: String::new(),
+            trusted_certificate_paths: Vec::new(),
+            issuer_certificate_paths: Vec::new(),
+            accept_any_server_certificate: false,
+        }
+    }
+}
+
+/// Timing for slot run requests sent to the PLC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunRequestSettings {
+    /// How long the run request flag is held on.
+    #[serde(default = "default_run_pulse_ms")]
+    pub pulse_ms: u64,
+    /// How long the conveyor has to start moving after the pulse.
+    #[serde(default = "default_run_start_timeout_ms")]
+    pub start_timeout_ms: u64,
+    /// How long the whole move may take, pulse included.
+    #[serde(default = "default_run_arrival_timeout_ms")]
+    pub arrival_timeout_ms: u64,
+}
+
+fn default_run_pulse_ms() -> u64 { 300 }
+fn default_run_start_timeout_ms() -> u64 { 5_000 }
+fn default_run_arrival_timeout_ms() -> u64 { 120_000 }
+
+impl Default for RunRequestSettings {
+    fn default() -> Self {
+        Self {
+            pulse_ms: default_run_pulse_ms(),
+            start_timeout_ms: default_run_start_timeout_ms(),
+            arrival_timeout_ms: default_run_arrival_timeout_ms(),
+        }
+    }
+}
+
+/// Which controller drives the conveyor, and how the simulator behaves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControllerSettings {
+    /// "opc" for the PLC or "simulator".
+    #[serde(default = "default_controller_kind")]
+    pub kind: String,
+    /// Simulated time to move one slot.
+    #[serde(default = "default_sim_travel_ms_per_slot")]
+    pub travel_ms_per_slot: u64,
+    /// Simulated delay between a run request and the conveyor moving.
+    #[serde(default = "default_sim_start_delay_ms")]
+    pub start_delay_ms: u64,
+    /// How long after arriving the simulated hanger sensor turns on; 0 never does.
+    #[serde(default = "default_sim_hanger_delay_ms")]
+    pub hanger_delay_ms: u64,
+    /// How long the simulated hanger sensor stays on.
+    #[serde(default = "default_sim_hanger_pulse_ms")]
+    pub hanger_pulse_ms: u64,
+}
+
+fn default_controller_kind() -> String { "opc".to_string() }
+fn default_sim_travel_ms_per_slot() -> u64 { 250 }
+fn default_sim_start_delay_ms() -> u64 { 300 }
+fn default_sim_hanger_delay_ms() -> u64 { 1_500 }
+fn default_sim_hanger_pulse_ms() -> u64 { 1_000 }
+
+impl Default for ControllerSettings {
+    fn default() -> Self {
+        Self {
+            kind: default_controller_kind(),
+            travel_ms_per_slot: default_sim_travel_ms_per_slot(),
+            start_delay_ms: default_sim_start_delay_ms(),
+            hanger_delay_ms: default_sim_hanger_delay_ms(),
+            hanger_pulse_ms: default_sim_hanger_pulse_ms(),
+        }
+    }
+}
+
+/// What one value of an alarm tag means.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmMeaning {
+    /// The fault code, or the bit number for bit tags.
+    pub value: i64,
+    pub message: String,
+}
+
+/// A PLC tag watched for faults.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmTag {
+    /// Shown as the alarm source.
+    pub name: String,
+    /// A tag map signal, such as "inverterStatus", whose node id and data
+    /// type are used. When unset, `node_id` and `data_type` are used.
+    #[serde(default)]
+    pub signal: Option<String>,
+    #[serde(default)]
+    pub node_id: String,
+    #[serde(default = "default_alarm_data_type")]
+    pub data_type: String,
+    /// "code" raises one alarm for any value outside `normal_values`;
+    /// "bits" raises one alarm per set bit.
+    #[serde(default = "default_alarm_kind")]
+    pub kind: String,
+    #[serde(default)]
+    pub normal_values: Vec<i64>,
+    #[serde(default)]
+    pub meanings: Vec<AlarmMeaning>,
+}
+
+impl AlarmTag {
+    /// A code tag read from a signal of the tag map.
+    fn signal_code(name: &str, signal: &str, normal_values: Vec<i64>) -> Self {
+        Self {
+            name: name.to_string(),
+            signal: Some(signal.to_string()),
+            node_id: String::new(),
+            data_type: default_alarm_data_type(),
+            kind: default_alarm_kind(),
+            normal_values,
+            meanings: Vec::new(),
+        }
+    }
+}
+
+fn default_alarm_kind() -> String { "code".to_string() }
+fn default_alarm_data_type() -> String { "Int16".to_string() }
+
+/// PLC fault tags turned into alarms.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmSettings {
+    #[serde(default = "default_alarm_tags")]
+    pub tags: Vec<AlarmTag>,
+}
+
+fn default_alarm_tags() -> Vec<AlarmTag> {
+    let codes = ConveyorStatusCodes::default();
+    vec![
+        AlarmTag::signal_code("InverterStatus", "inverterStatus", vec![0]),
+        AlarmTag::signal_code("ConveyorStatus", "conveyorStatus", vec![codes.stopped, codes.running]),
+    ]
+}
+
+impl Default for AlarmSettings {
+    fn default() -> Self {
+        Self { tags: default_alarm_tags() }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(non_snake_case)]
 pub struct AppSettings {
@@ -155,6 +536,20 @@ pub struct AppSettings {
     pub printer: PrinterSettings,
     #[serde(default = "default_frames")]
     pub frames: Vec<FrameConfig>,
+    #[serde(default)]
+    pub slotAllocation: SlotAllocationSettings,
+    #[serde(default)]
+    pub opcTags: OpcTagMap,
+    #[serde(default)]
+    pub conveyorStatusCodes: ConveyorStatusCodes,
+    #[serde(default)]
+    pub runRequest: RunRequestSettings,
+    #[serde(default)]
+    pub opcSecurity: OpcSecuritySettings,
+    #[serde(default)]
+    pub controller: ControllerSettings,
+    #[serde(default)]
+    pub alarms: AlarmSettings,
 }
 
 fn default_pos_system() -> String {
@@ -176,6 +571,22 @@ impl Default for AppSettings {
             fieldMappings: FieldMappings::default(),
             printer: PrinterSettings::default(),
             frames: default_frames(),
+            slotAllocation: SlotAllocationSettings::default(),
+            opcTags: OpcTagMap::default(),
+            conveyorStatusCodes: ConveyorStatusCodes::default(),
+            runRequest: RunRequestSettings::default(),
+            opcSecurity: OpcSecuritySettings::default(),
+            controller: ControllerSettings::default(),
+            alarms: AlarmSettings::default(),
         }
     }
 }
+
+impl AppSettings {
+    /// False while `frames` is still the single default frame, which is also
+    /// what settings saved before frames existed load as. Slots are not synced
+    /// against it.
+    pub fn frames_configured(&self) -> bool {
+        self.frames != default_frames()
+    }
+}
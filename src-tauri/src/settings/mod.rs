@@ -28,6 +28,14 @@ pub fn load_settings(app: &AppHandle) -> AppSettings {
     })
 }
 
+/// Writes settings.json -> key "app_settings"
+pub fn save_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let store = app.store("settings.json").map_err(|e| format!("Store error: {}", e))?;
+
+    store.set("app_settings", serde_json::to_value(settings).map_err(|e| e.to_string())?);
+    store.save().map_err(|e| format!("Failed to save settings: {}", e))
+}
+
 /// Convenience helper: build Postgres DATABASE_URL from saved settings
 pub fn database_url(s: &AppSettings) -> String {
     let pw = urlencoding::encode(&s.dbPassword);
@@ -0,0 +1,140 @@
+use crate::model::{Slot, SlotState};
+use crate::settings::appsettings::SlotAllocationSettings;
+
+/// Snapshot of the conveyor handed to an allocation strategy.
+/// Slot numbers are expected in ascending order.
+#[derive(Debug, Clone, Default)]
+pub struct AllocationContext {
+    pub empty: Vec<i32>,
+    pub occupied: Vec<i32>,
+    pub last_used_slot: i32,
+}
+
+impl AllocationContext {
+    pub fn new(empty: Vec<i32>, occupied: Vec<i32>, last_used_slot: i32) -> Self {
+        Self { empty, occupied, last_used_slot }
+    }
+
+    /// Builds a context from slot rows. Retired slots are ignored entirely.
+    pub fn from_slots<'a>(slots: impl IntoIterator<Item = &'a Slot>, last_used_slot: i32) -> Self {
+        let mut empty = Vec::new();
+        let mut occupied = Vec::new();
+
+        for s in slots.into_iter().filter(|s| !s.retired) {
+            if SlotState::from_db(&s.slot_state) == Some(SlotState::Empty) {
+                empty.push(s.slot_number);
+            } else {
+                occupied.push(s.slot_number);
+            }
+        }
+
+        empty.sort_unstable();
+        occupied.sort_unstable();
+
+        Self { empty, occupied, last_used_slot }
+    }
+
+    pub fn occupancy_pct(&self) -> f64 {
+        let total = self.empty.len() + self.occupied.len();
+        if total > 0 {
+            (self.occupied.len() as f64 / total as f64) * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+pub trait SlotAllocationStrategy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Chooses an empty slot, or `None` if the conveyor is full.
+    fn pick(&self, ctx: &AllocationContext) -> Option<i32>;
+}
+
+/// Always takes the lowest numbered empty slot.
+pub struct LinearStrategy;
+
+impl SlotAllocationStrategy for LinearStrategy {
+    fn name(&self) -> &'static str {
+        "linear"
+    }
+
+    fn pick(&self, ctx: &AllocationContext) -> Option<i32> {
+        ctx.empty.first().copied()
+    }
+}
+
+/// Continues from `last_used_slot + 1`, wrapping back to the lowest empty slot.
+pub struct RoundRobinStrategy;
+
+impl SlotAllocationStrategy for RoundRobinStrategy {
+    fn name(&self) -> &'static str {
+        "roundRobin"
+    }
+
+    fn pick(&self, ctx: &AllocationContext) -> Option<i32> {
+        ctx.empty
+            .iter()
+            .copied()
+            .find(|&n| n > ctx.last_used_slot)
+            .or_else(|| ctx.empty.first().copied())
+    }
+}
+
+/// Below `threshold_pct` occupancy, keeps new tickets at least `min_distance`
+/// slots away from anything already in use. At or above it, behaves linearly.
+pub struct SpreadStrategy {
+    pub min_distance: i32,
+    pub threshold_pct: f64,
+}
+
+impl Default for SpreadStrategy {
+    fn default() -> Self {
+        Self { min_distance: 5, threshold_pct: 50.0 }
+    }
+}
+
+impl SlotAllocationStrategy for SpreadStrategy {
+    fn name(&self) -> &'static str {
+        "spread"
+    }
+
+    fn pick(&self, ctx: &AllocationContext) -> Option<i32> {
+        let first = ctx.empty.first().copied()?;
+
+        if ctx.occupancy_pct() >= self.threshold_pct || ctx.occupied.is_empty() {
+            return Some(first);
+        }
+
+        let candidate = ctx.empty.iter().copied().find(|&n| {
+            ctx.occupied.iter().all(|&o| (n - o).abs() >= self.min_distance)
+        });
+
+        // Fall back to first available if no slot is far enough away.
+        candidate.or(Some(first))
+    }
+}
+
+/// Picks the empty slot closest to any of `anchors`, preferring the lower
+/// number on a tie. Used to keep a multi-slot ticket together.
+pub fn pick_nearby(ctx: &AllocationContext, anchors: &[i32]) -> Option<i32> {
+    ctx.empty
+        .iter()
+        .copied()
+        .min_by_key(|&n| {
+            let distance = anchors.iter().map(|&a| (n - a).abs()).min().unwrap_or(i32::MAX);
+            (distance, n)
+        })
+}
+
+/// Builds the strategy configured for this store. Unknown names fall back to spread.
+pub fn strategy_from_settings(settings: &SlotAllocationSettings) -> Box<dyn SlotAllocationStrategy> {
+    match settings.strategy.as_str() {
+        "linear" => Box::new(LinearStrategy),
+        "roundRobin" => Box::new(RoundRobinStrategy),
+        _ => Box::new(SpreadStrategy {
+            min_distance: settings.spread_min_distance,
+            threshold_pct: settings.spread_threshold_pct,
+        }),
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::db::{garment_repo, slot_repo::SlotRepo, state_error::{StateError, StateResult}, ticket_repo};
+use crate::model::{Garment, Slot, SlotState, Ticket, TicketStatus};
+
+const REPAIR_REASON: &str = "consistency repair";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// Garment points at a slot number that has no row in `slots`.
+    GarmentOnMissingSlot,
+    /// Garment of a completed ticket still points at a slot.
+    GarmentOfFinishedTicket,
+    /// Garment hangs on an empty or reserved slot.
+    GarmentOnUnoccupiedSlot,
+    /// Slot holds garments from a ticket other than the one assigned to it.
+    GarmentTicketMismatch,
+    /// Occupied slot with nothing hanging on it.
+    OccupiedSlotWithoutGarments,
+    /// Slot is assigned to a ticket that does not exist.
+    SlotTicketMissing,
+    /// Slot is still assigned to a completed ticket.
+    FinishedTicketOnSlot,
+    /// `slots.garment_count` disagrees with the garments table.
+    GarmentCountMismatch,
+    /// Ticket is Processing but has no slot and nothing on the conveyor.
+    ProcessingTicketOffConveyor,
+    /// `garments_processed` is below what is on the conveyor or above the item count.
+    ProcessedCountMismatch,
+}
+
+/// What apply mode does to fix an issue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RepairAction {
+    TakeGarmentOff { item_id: String },
+    OccupySlot { slot_number: i32, ticket: String },
+    FreeSlot { slot_number: i32 },
+    /// Needs someone to look at the hook; the slot is flagged as an error.
+    FlagSlotError { slot_number: i32 },
+    SetGarmentCount { slot_number: i32, count: i32, latest_item: Option<String> },
+    ResetTicket { ticket: String },
+    SetGarmentsProcessed { ticket: String, count: i32 },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsistencyIssue {
+    pub kind: IssueKind,
+    pub description: String,
+    pub slot_number: Option<i32>,
+    pub ticket: Option<String>,
+    pub repair: RepairAction,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsistencyReport {
+    pub issues: Vec<ConsistencyIssue>,
+    /// False for a dry run.
+    pub applied: bool,
+}
+
+fn is_finished(ticket: &Ticket) -> bool {
+    TicketStatus::from_db(&ticket.ticket_status).is_some_and(TicketStatus::is_finished)
+}
+
+/// Compares slots, garments on the conveyor and tickets, and returns every
+/// inconsistency with the repair that resolves it.
+///
+/// `garments` only needs the garments whose slot is not -1; `tickets` needs
+/// every Processing ticket plus any ticket referenced by a slot or garment.
+pub fn find_issues(slots: &[Slot], garments: &[Garment], tickets: &[Ticket]) -> Vec<ConsistencyIssue> {
+    let mut issues = Vec::new();
+
+    let slot_numbers: HashSet<i32> = slots.iter().map(|s| s.slot_number).collect();
+    let tickets_by_invoice: HashMap<&str, &Ticket> = tickets
+        .iter()
+        .map(|t| (t.full_invoice_number.as_str(), t))
+        .collect();
+
+    // Garments that should still be on their slot once the garment-level issues are repaired
+    let mut on_slot: HashMap<i32, Vec<&Garment>> = HashMap::new();
+
+    for g in garments.iter().filter(|g| g.slot_number != -1) {
+        if !slot_numbers.contains(&g.slot_number) {
+            issues.push(ConsistencyIssue {
+                kind: IssueKind::GarmentOnMissingSlot,
+                description: format!("Garment {} points at slot {}, which does not exist", g.item_id, g.slot_number),
+                slot_number: Some(g.slot_number),
+                ticket: Some(g.full_invoice_number.clone()),
+                repair: RepairAction::TakeGarmentOff { item_id: g.item_id.clone() },
+            });
+            continue;
+        }
+
+        if let Some(ticket) = tickets_by_invoice.get(g.full_invoice_number.as_str()) {
+            if is_finished(ticket) {
+                issues.push(ConsistencyIssue {
+                    kind: IssueKind::GarmentOfFinishedTicket,
+                    description: format!(
+                        "Garment {} of {} ticket {} is still on slot {}",
+                        g.item_id, ticket.ticket_status, g.full_invoice_number, g.slot_number
+                    ),
+                    slot_number: Some(g.slot_number),
+                    ticket: Some(g.full_invoice_number.clone()),
+                    repair: RepairAction::TakeGarmentOff { item_id: g.item_id.clone() },
+                });
+                continue;
+            }
+        }
+
+        on_slot.entry(g.slot_number).or_default().push(g);
+    }
+
+    for slot in slots {
+        let hanging = on_slot.get(&slot.slot_number).map(Vec::as_slice).unwrap_or(&[]);
+        let hanging_tickets: BTreeSet<&str> = hanging.iter().map(|g| g.full_invoice_number.as_str()).collect();
+        let slot_issue = |kind, description: String, repair| ConsistencyIssue {
+            kind,
+            description,
+            slot_number: Some(slot.slot_number),
+            ticket: slot.assigned_ticket.clone(),
+            repair,
+        };
+        let clear_repair = if hanging.is_empty() {
+            RepairAction::FreeSlot { slot_number: slot.slot_number }
+        } else {
+            RepairAction::FlagSlotError { slot_number: slot.slot_number }
+        };
+
+        let assigned = slot.assigned_ticket.as_deref();
+        let state = SlotState::from_db(&slot.slot_state);
+
+        match assigned.map(|t| tickets_by_invoice.get(t)) {
+            Some(None) => issues.push(slot_issue(
+                IssueKind::SlotTicketMissing,
+                format!("Slot {} is assigned to ticket {}, which does not exist", slot.slot_number, assigned.unwrap_or_default()),
+                clear_repair,
+            )),
+            Some(Some(ticket)) if is_finished(ticket) => issues.push(slot_issue(
+                IssueKind::FinishedTicketOnSlot,
+                format!("Slot {} is still assigned to {} ticket {}", slot.slot_number, ticket.ticket_status, ticket.full_invoice_number),
+                clear_repair,
+            )),
+            _ => {
+                let foreign = match assigned {
+                    Some(t) => hanging_tickets.iter().any(|h| *h != t),
+                    None => hanging_tickets.len() > 1,
+                };
+
+                if foreign {
+                    issues.push(slot_issue(
+                        IssueKind::GarmentTicketMismatch,
+                        format!(
+                            "Slot {} is assigned to {} but holds garments from {}",
+                            slot.slot_number,
+                            assigned.unwrap_or("no ticket"),
+                            hanging_tickets.iter().copied().collect::<Vec<_>>().join(", ")
+                        ),
+                        RepairAction::FlagSlotError { slot_number: slot.slot_number },
+                    ));
+                } else if let Some(ticket) = hanging_tickets.first() {
+                    let unoccupied = matches!(state, Some(SlotState::Empty | SlotState::Reserved));
+                    if unoccupied || (state == Some(SlotState::Occupied) && assigned.is_none()) {
+                        issues.push(slot_issue(
+                            IssueKind::GarmentOnUnoccupiedSlot,
+                            format!(
+                                "Slot {} is {} but holds {} garment(s) of ticket {}",
+                                slot.slot_number, slot.slot_state, hanging.len(), ticket
+                            ),
+                            RepairAction::OccupySlot { slot_number: slot.slot_number, ticket: ticket.to_string() },
+                        ));
+                    }
+                } else if state == Some(SlotState::Occupied) {
+                    issues.push(slot_issue(
+                        IssueKind::OccupiedSlotWithoutGarments,
+                        format!("Slot {} is occupied but no garment points at it", slot.slot_number),
+                        RepairAction::FreeSlot { slot_number: slot.slot_number },
+                    ));
+                }
+            }
+        }
+
+        let actual = hanging.len() as i32;
+        if slot.garment_count != actual {
+            let latest_item = hanging.iter().max_by_key(|g| g.id).map(|g| g.item_id.clone());
+            issues.push(slot_issue(
+                IssueKind::GarmentCountMismatch,
+                format!("Slot {} counts {} garment(s) but {} point at it", slot.slot_number, slot.garment_count, actual),
+                RepairAction::SetGarmentCount { slot_number: slot.slot_number, count: actual, latest_item },
+            ));
+        }
+    }
+
+    let slotted_tickets: HashSet<&str> = slots.iter().filter_map(|s| s.assigned_ticket.as_deref()).collect();
+    let mut on_conveyor: HashMap<&str, i32> = HashMap::new();
+    for g in on_slot.values().flatten() {
+        *on_conveyor.entry(g.full_invoice_number.as_str()).or_default() += 1;
+    }
+
+    let mut processing: Vec<&Ticket> = tickets.iter().filter(|t| t.ticket_status == TicketStatus::Processing.as_str()).collect();
+    processing.sort_by(|a, b| a.full_invoice_number.cmp(&b.full_invoice_number));
+
+    for ticket in processing {
+        let invoice = ticket.full_invoice_number.as_str();
+        let loaded = on_conveyor.get(invoice).copied().unwrap_or(0);
+
+        if loaded == 0 && !slotted_tickets.contains(invoice) {
+            issues.push(ConsistencyIssue {
+                kind: IssueKind::ProcessingTicketOffConveyor,
+                description: format!("Ticket {} is Processing but has no slot and no garment on the conveyor", invoice),
+                slot_number: None,
+                ticket: Some(invoice.to_string()),
+                repair: RepairAction::ResetTicket { ticket: invoice.to_string() },
+            });
+        } else if ticket.garments_processed < loaded || ticket.garments_processed > ticket.number_of_items {
+            let count = ticket.garments_processed.min(ticket.number_of_items).max(loaded);
+            issues.push(ConsistencyIssue {
+                kind: IssueKind::ProcessedCountMismatch,
+                description: format!(
+                    "Ticket {} has {} of {} garments processed but {} on the conveyor",
+                    invoice, ticket.garments_processed, ticket.number_of_items, loaded
+                ),
+                slot_number: None,
+                ticket: Some(invoice.to_string()),
+                repair: RepairAction::SetGarmentsProcessed { ticket: invoice.to_string(), count },
+            });
+        }
+    }
+
+    issues
+}
+
+fn apply_repair(conn: &mut PgConnection, repair: &RepairAction) -> StateResult<()> {
+    match repair {
+        // Counts are recomputed by their own repair, so only the garment moves here
+        RepairAction::TakeGarmentOff { item_id } => {
+            garment_repo::move_garment(conn, item_id, -1)?;
+        }
+        RepairAction::OccupySlot { slot_number, ticket } => {
+            SlotRepo::set_occupied(conn, *slot_number, Some(ticket), REPAIR_REASON)?;
+        }
+        RepairAction::FreeSlot { slot_number } => {
+            // Freeing would also lift the block, which is not this repair's call
+            let blocked = SlotRepo::get(conn, *slot_number)?
+                .is_some_and(|s| s.slot_state == SlotState::Blocked.as_str());
+            if blocked {
+                SlotRepo::clear_assignment(conn, *slot_number, REPAIR_REASON)?;
+            } else {
+                SlotRepo::free_slot(conn, *slot_number, REPAIR_REASON)?;
+            }
+        }
+        RepairAction::FlagSlotError { slot_number } => {
+            SlotRepo::set_error(conn, *slot_number, "consistency check: garments do not match the slot's ticket")?;
+        }
+        RepairAction::SetGarmentCount { slot_number, count, latest_item } => {
+            SlotRepo::set_garment_count(conn, *slot_number, *count, latest_item.as_deref(), REPAIR_REASON)?;
+        }
+        RepairAction::ResetTicket { ticket } => {
+            ticket_repo::reset_ticket_progress(conn, ticket)?;
+        }
+        RepairAction::SetGarmentsProcessed { ticket, count } => {
+            ticket_repo::set_garments_processed(conn, ticket, *count)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks the database for drift between slots, garments and tickets.
+/// With `apply`, every repair runs in one transaction.
+pub fn run_consistency_check(conn: &mut PgConnection, apply: bool) -> StateResult<ConsistencyReport> {
+    conn.transaction::<ConsistencyReport, StateError, _>(|conn| {
+        let slots = SlotRepo::list_all(conn)?;
+        let garments = garment_repo::list_garments_on_conveyor(conn)?;
+
+        let referenced: Vec<String> = slots
+            .iter()
+            .filter_map(|s| s.assigned_ticket.clone())
+            .chain(garments.iter().map(|g| g.full_invoice_number.clone()))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let tickets = ticket_repo::list_tickets_with_status_or_in(conn, TicketStatus::Processing.as_str(), &referenced)?;
+
+        let issues = find_issues(&slots, &garments, &tickets);
+
+        if apply {
+            for issue in &issues {
+                apply_repair(conn, &issue.repair)?;
+            }
+        }
+
+        Ok(ConsistencyReport { issues, applied: apply })
+    })
+}
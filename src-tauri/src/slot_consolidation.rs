@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::db::{garment_repo, slot_repo::SlotRepo, state_error::StateError};
+use crate::model::{Slot, SlotState};
+use crate::pos::spot::output::{
+    conveyor_file_utils::{write_load_item, write_unload_item},
+    conveyor_ops_types::ConveyorOpsTypes,
+};
+use crate::slot_allocation::{pick_nearby, AllocationContext};
+use crate::slot_manager::SlotManager;
+
+static ACTIVE_RUN: RwLock<Option<ConsolidationRun>> = RwLock::new(None);
+
+/// Move everything on `from_slot` to the empty `to_slot`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SlotMove {
+    pub from_slot: i32,
+    pub to_slot: i32,
+    pub ticket: String,
+    pub garment_count: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConsolidationPlan {
+    /// Inclusive slot numbers of the run the moves free up.
+    pub run_start: i32,
+    pub run_end: i32,
+    pub moves: Vec<SlotMove>,
+}
+
+/// A plan being worked through by an operator.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsolidationRun {
+    pub plan: ConsolidationPlan,
+    /// Index of the next move; equal to `plan.moves.len()` once done.
+    pub next_move: usize,
+    pub skipped: Vec<i32>,
+    /// Feedback the last confirmed move could not write to the POS. The move
+    /// itself is recorded; the POS needs correcting by hand.
+    pub feedback_errors: Vec<String>,
+}
+
+impl ConsolidationRun {
+    pub fn is_done(&self) -> bool {
+        self.next_move >= self.plan.moves.len()
+    }
+}
+
+fn is_empty(slot: &Slot) -> bool {
+    slot.slot_state == SlotState::Empty.as_str()
+}
+
+/// Only settled slots are moved; reserved slots still have garments on the way.
+fn is_movable(slot: &Slot) -> bool {
+    slot.slot_state == SlotState::Occupied.as_str() && slot.assigned_ticket.is_some()
+}
+
+/// Finds the `run_length` consecutive slots that need the fewest moves to
+/// empty, and where to move what is on them. Blocked, error, reserved and
+/// retired slots break a run. Returns `None` if no run can be freed.
+pub fn plan_consolidation(slots: &[Slot], run_length: usize) -> Option<ConsolidationPlan> {
+    if run_length == 0 {
+        return None;
+    }
+
+    let mut active: Vec<&Slot> = slots.iter().filter(|s| !s.retired).collect();
+    active.sort_by_key(|s| s.slot_number);
+    let empty_total = active.iter().filter(|s| is_empty(s)).count();
+
+    // (moves needed, index of the first slot in the window)
+    let mut best: Option<(usize, usize)> = None;
+    let mut segment_start = 0;
+    for i in 0..active.len() {
+        let usable = is_empty(active[i]) || is_movable(active[i]);
+        let follows = i > 0 && active[i].slot_number == active[i - 1].slot_number + 1;
+        if !usable {
+            segment_start = i + 1;
+            continue;
+        }
+        if !follows {
+            segment_start = i;
+        }
+        if i + 1 < segment_start + run_length {
+            continue;
+        }
+
+        let start = i + 1 - run_length;
+        let window = &active[start..=i];
+        let occupied = window.iter().filter(|s| is_movable(s)).count();
+        let empty_outside = empty_total - (run_length - occupied);
+
+        if occupied <= empty_outside && best.is_none_or(|(cost, _)| occupied < cost) {
+            best = Some((occupied, start));
+        }
+    }
+
+    let (_, start) = best?;
+    let window = &active[start..start + run_length];
+    let run_start = window[0].slot_number;
+    let run_end = window[run_length - 1].slot_number;
+
+    let mut free: Vec<i32> = active
+        .iter()
+        .filter(|s| is_empty(s) && !(run_start..=run_end).contains(&s.slot_number))
+        .map(|s| s.slot_number)
+        .collect();
+
+    let mut moves = Vec::new();
+    for slot in window.iter().filter(|s| is_movable(s)) {
+        let ticket = slot.assigned_ticket.clone().unwrap_or_default();
+
+        // Keep the ticket next to its other slots, else close to where it was
+        let mut anchors: Vec<i32> = active
+            .iter()
+            .filter(|s| s.assigned_ticket.as_deref() == Some(ticket.as_str()))
+            .map(|s| s.slot_number)
+            .filter(|n| !(run_start..=run_end).contains(n))
+            .chain(moves.iter().filter(|m: &&SlotMove| m.ticket == ticket).map(|m| m.to_slot))
+            .collect();
+        if anchors.is_empty() {
+            anchors.push(slot.slot_number);
+        }
+
+        let to_slot = pick_nearby(&AllocationContext::new(free.clone(), Vec::new(), 0), &anchors)?;
+        free.retain(|&n| n != to_slot);
+        moves.push(SlotMove {
+            from_slot: slot.slot_number,
+            to_slot,
+            ticket,
+            garment_count: slot.garment_count,
+        });
+    }
+
+    Some(ConsolidationPlan { run_start, run_end, moves })
+}
+
+/// Plans a consolidation against the current slots and makes it the active
+/// run, replacing any run that was not finished.
+pub fn start_consolidation(conn: &mut PgConnection, run_length: i32) -> Result<ConsolidationRun, String> {
+    if run_length < 1 {
+        return Err("Run length must be at least 1".into());
+    }
+    let slots = SlotRepo::list_all(conn).map_err(|e| format!("DB Error: {}", e))?;
+    let plan = plan_consolidation(&slots, run_length as usize)
+        .ok_or_else(|| format!("Cannot free {} consecutive slots", run_length))?;
+
+    let run = ConsolidationRun { plan, next_move: 0, skipped: Vec::new(), feedback_errors: Vec::new() };
+    *ACTIVE_RUN.write().unwrap() = Some(run.clone());
+    Ok(run)
+}
+
+pub fn active_consolidation() -> Option<ConsolidationRun> {
+    ACTIVE_RUN.read().unwrap().clone()
+}
+
+pub fn cancel_consolidation() {
+    *ACTIVE_RUN.write().unwrap() = None;
+}
+
+/// The operator has moved the garments for the next step: record the move
+/// and write the unload/load feedback for each garment.
+pub fn confirm_next_move(conn: &mut PgConnection) -> Result<ConsolidationRun, String> {
+    let mut run = active_consolidation().ok_or("No consolidation in progress")?;
+    let step = run.plan.moves.get(run.next_move).cloned().ok_or("Consolidation is already done")?;
+
+    let moved = move_slot(conn, &step)?;
+    run.feedback_errors.clear();
+    for item in &moved {
+        if let Err(e) = write_unload_item(ConveyorOpsTypes::UnloadItem, &step.ticket, item, step.from_slot as u32) {
+            run.feedback_errors.push(format!("Failed to write unload feedback for {}: {}", item, e));
+        }
+        if let Err(e) = write_load_item(ConveyorOpsTypes::LoadItem, &step.ticket, item, step.to_slot as u32) {
+            run.feedback_errors.push(format!("Failed to write load feedback for {}: {}", item, e));
+        }
+    }
+
+    run.next_move += 1;
+    finish_step(run)
+}
+
+/// Leaves the next move undone, e.g. when the garments cannot be reached.
+pub fn skip_next_move() -> Result<ConsolidationRun, String> {
+    let mut run = active_consolidation().ok_or("No consolidation in progress")?;
+    let step = run.plan.moves.get(run.next_move).ok_or("Consolidation is already done")?;
+    run.skipped.push(step.from_slot);
+    run.feedback_errors.clear();
+    run.next_move += 1;
+    finish_step(run)
+}
+
+fn finish_step(run: ConsolidationRun) -> Result<ConsolidationRun, String> {
+    *ACTIVE_RUN.write().unwrap() = if run.is_done() { None } else { Some(run.clone()) };
+    Ok(run)
+}
+
+/// Returns the garments moved. Refused if either slot changed since planning.
+fn move_slot(conn: &mut PgConnection, step: &SlotMove) -> Result<Vec<String>, String> {
+    // The outer error is a failed write, the inner one a refused move
+    conn.transaction::<Result<Vec<String>, String>, StateError, _>(|conn| {
+        // Locked, so neither slot can change between the checks and the move
+        let slots: HashMap<i32, Slot> = SlotRepo::lock(conn, &[step.from_slot, step.to_slot])?
+            .into_iter()
+            .map(|s| (s.slot_number, s))
+            .collect();
+        let Some(from) = slots.get(&step.from_slot) else {
+            return Ok(Err(format!("Slot {} does not exist", step.from_slot)));
+        };
+        let Some(to) = slots.get(&step.to_slot) else {
+            return Ok(Err(format!("Slot {} does not exist", step.to_slot)));
+        };
+
+        if !is_movable(from) || from.assigned_ticket.as_deref() != Some(step.ticket.as_str()) {
+            return Ok(Err(format!("Slot {} no longer holds ticket {}; plan again", step.from_slot, step.ticket)));
+        }
+        if !is_empty(to) || to.retired {
+            return Ok(Err(format!("Slot {} is no longer empty; plan again", step.to_slot)));
+        }
+
+        let garments = garment_repo::list_garments_in_slot(conn, step.from_slot)?;
+
+        SlotRepo::set_occupied(conn, step.to_slot, Some(&step.ticket), &format!("consolidated from slot {}", step.from_slot))?;
+        for g in &garments {
+            SlotManager::load_garment(conn, &g.item_id, step.to_slot)?;
+        }
+        SlotRepo::free_slot(conn, step.from_slot, &format!("consolidated to slot {}", step.to_slot))?;
+
+        Ok(Ok(garments.into_iter().map(|g| g.item_id).collect()))
+    })
+    .map_err(|e| e.to_string())?
+}
@@ -1,7 +1,14 @@
+use std::sync::RwLock;
+
 use diesel::prelude::*;
 use serde::Serialize;
-use crate::db::{slot_repo::SlotRepo, app_state_repo::AppStateRepo};
-use crate::model::Slot;
+use crate::db::{slot_repo::SlotRepo, app_state_repo::AppStateRepo, garment_repo, state_error::{StateError, StateResult}, ticket_repo};
+use crate::model::{GarmentState, Slot, SlotState, TicketStatus};
+use crate::settings::appsettings::{PickupZoningSettings, SlotAllocationSettings};
+use crate::slot_allocation::{pick_nearby, strategy_from_settings, AllocationContext, SlotAllocationStrategy};
+use crate::slot_zoning::{self, PickupBucket};
+
+static ALLOCATION_SETTINGS: RwLock<Option<SlotAllocationSettings>> = RwLock::new(None);
 
 
 #[derive(Serialize)]
@@ -9,6 +16,11 @@ pub struct SlotManagerStats {
     pub occupied_slots: i64,
     pub total_slots: i64,
     pub occupancy_percentage: f64,
+    /// Garments hanging on the conveyor right now
+    pub garment_count: i64,
+    /// Garments the conveyor can hold at the configured slot capacity
+    pub garment_capacity: i64,
+    pub item_fill_percentage: f64,
 }
 
 impl SlotManagerStats {
@@ -22,54 +34,130 @@ impl SlotManagerStats {
             0.0
         };
 
+        let garment_count = SlotRepo::total_garment_count(conn)?;
+        let garment_capacity = total * SlotManager::allocation_settings().slot_capacity.max(0) as i64;
+
+        let item_fill_percentage = if garment_capacity > 0 {
+            (garment_count as f64 / garment_capacity as f64) * 100.0
+        } else {
+            0.0
+        };
+
         Ok(SlotManagerStats {
             occupied_slots: occupied,
             total_slots: total,
             occupancy_percentage,
+            garment_count,
+            garment_capacity,
+            item_fill_percentage,
         })
     }
 }
 
 
+/// The slot a scanned garment goes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SlotAssignment {
+    pub slot_number: i32,
+    /// True when a slot was reserved for this garment, false when it joins
+    /// one of the ticket's slots that still has room.
+    pub newly_reserved: bool,
+}
+
 pub struct SlotManager;
 
 impl SlotManager {
 
+    pub fn set_allocation_settings(settings: &SlotAllocationSettings) {
+        let mut w = ALLOCATION_SETTINGS.write().unwrap();
+        *w = Some(settings.clone());
+    }
+
+    pub fn allocation_settings() -> SlotAllocationSettings {
+        ALLOCATION_SETTINGS.read().unwrap().clone().unwrap_or_default()
+    }
+
     pub fn reserve_next_slot(
         conn: &mut PgConnection,
         ticket: Option<&str>,
     ) -> Result<i32, String> {
-        conn.transaction::<i32, diesel::result::Error, _>(|conn| {
+        Self::assign_slot(conn, ticket).map(|a| a.slot_number)
+    }
+
+    /// The ticket's slots that can still take garments, those with room first.
+    /// Blocked, error and retired slots never take more garments.
+    fn ticket_slots_in_service(conn: &mut PgConnection, ticket: &str, capacity: i32) -> QueryResult<Vec<Slot>> {
+        let mut slots: Vec<Slot> = SlotRepo::find_ticket_slots(conn, ticket)?
+            .into_iter()
+            .filter(|s| {
+                !s.retired
+                    && matches!(SlotState::from_db(&s.slot_state), Some(SlotState::Reserved | SlotState::Occupied))
+            })
+            .collect();
+        slots.sort_by_key(|s| s.garment_count >= capacity);
+        Ok(slots)
+    }
+
+    /// One of the slots the ticket already uses, without reserving anything.
+    pub fn current_ticket_slot(conn: &mut PgConnection, ticket: &str) -> QueryResult<Option<i32>> {
+        let capacity = Self::allocation_settings().slot_capacity;
+        Ok(Self::ticket_slots_in_service(conn, ticket, capacity)?.first().map(|s| s.slot_number))
+    }
 
+    /// Picks the slot for the next garment of `ticket` and says whether it had to be reserved.
+    pub fn assign_slot(
+        conn: &mut PgConnection,
+        ticket: Option<&str>,
+    ) -> Result<SlotAssignment, String> {
+        let settings = Self::allocation_settings();
+        let strategy = strategy_from_settings(&settings);
+        let reserved = |slot_number| SlotAssignment { slot_number, newly_reserved: true };
+
+        conn.transaction::<SlotAssignment, diesel::result::Error, _>(|conn| {
+
+            // A ticket already on the conveyor keeps filling its own slots, and
+            // spills onto the closest empty slot once they are all at capacity.
+            let mut anchors = Vec::new();
             if let Some(t) = ticket {
-                if let Some(existing) = SlotRepo::find_ticket_slot(conn, t)? {
-                    if existing.slot_state != "blocked" && existing.slot_state != "error" {
-                        return Ok(existing.slot_number);
+                for existing in Self::ticket_slots_in_service(conn, t, settings.slot_capacity)? {
+                    if existing.garment_count < settings.slot_capacity {
+                        return Ok(SlotAssignment { slot_number: existing.slot_number, newly_reserved: false });
                     }
+                    anchors.push(existing.slot_number);
                 }
             }
 
-            let empties = Self::list_empty_slots(conn)?;
-            let occupied = Self::list_occupied_slot_numbers(conn)?;
+            if !anchors.is_empty() {
+                for _ in 0..10 {
+                    let all = SlotRepo::list_all(conn)?;
+                    let ctx = AllocationContext::from_slots(&all, 0);
+                    let chosen = pick_nearby(&ctx, &anchors).ok_or(diesel::result::Error::NotFound)?;
 
-            let chosen = Self::pick_spread(&empties, &occupied)
-                .ok_or(diesel::result::Error::NotFound)?;
+                    if SlotRepo::try_reserve(conn, chosen, ticket, settings.slot_capacity, "ticket slots full, reserved nearby slot")? {
+                        AppStateRepo::set_last_used_slot(conn, chosen)?;
+                        return Ok(reserved(chosen));
+                    }
+                }
+
+                return Err(diesel::result::Error::NotFound);
+            }
+
+            let bucket = match ticket {
+                Some(t) if settings.zoning.enabled => ticket_repo::get_pickup_date(conn, t)?
+                    .map(|d| PickupBucket::for_pickup(d, chrono::Local::now().date_naive())),
+                _ => None,
+            };
 
             for _ in 0..10 {
-                if SlotRepo::try_reserve(conn, chosen, ticket)? {
+                let chosen = Self::pick_slot(conn, strategy.as_ref(), &settings.zoning, bucket)?
+                    .ok_or(diesel::result::Error::NotFound)?;
+
+                if SlotRepo::try_reserve(conn, chosen, ticket, settings.slot_capacity, "reserved for ticket")? {
                     AppStateRepo::set_last_used_slot(conn, chosen)?;
-                    return Ok(chosen);
+                    return Ok(reserved(chosen));
                 }
 
-                // Slot was taken concurrently; re-query and spread-pick again.
-                let empties = Self::list_empty_slots(conn)?;
-                let occupied = Self::list_occupied_slot_numbers(conn)?;
-                if let Some(next) = Self::pick_spread(&empties, &occupied) {
-                    if SlotRepo::try_reserve(conn, next, ticket)? {
-                        AppStateRepo::set_last_used_slot(conn, next)?;
-                        return Ok(next);
-                    }
-                }
+                // Slot was taken concurrently; re-query and pick again.
             }
 
             Err(diesel::result::Error::NotFound)
@@ -77,63 +165,230 @@ impl SlotManager {
         .map_err(|e| format!("No available slots: {e}"))
     }
 
-
     pub fn free_slot(
         conn: &mut PgConnection,
         slot_number: i32,
+        reason: &str,
+    ) -> StateResult<()> {
+
+        SlotRepo::free_slot(conn, slot_number, reason)
+    }
+
+    /// Records a garment hanging on `slot_number`, taking it off any slot it
+    /// was on before. A `slot_number` of -1 takes it off the conveyor.
+    pub fn load_garment(
+        conn: &mut PgConnection,
+        barcode: &str,
+        slot_number: i32,
     ) -> diesel::QueryResult<()> {
+        conn.transaction::<(), diesel::result::Error, _>(|conn| {
+            let previous = garment_repo::move_garment(conn, barcode, slot_number)?;
+            if previous == slot_number {
+                return Ok(());
+            }
 
-        SlotRepo::free_slot(conn, slot_number)
+            if previous > 0 {
+                let latest = garment_repo::latest_garment_in_slot(conn, previous)?;
+                SlotRepo::record_unload(conn, previous, barcode, latest.as_deref())?;
+            }
+            if slot_number > 0 {
+                SlotRepo::record_load(conn, slot_number, barcode)?;
+                garment_repo::set_garment_state(conn, barcode, GarmentState::Processing)?;
+            } else {
+                // Off the conveyor: processed if the ticket went out with it
+                let ticket = garment_repo::get_garment_ticket_status(conn, barcode)?;
+                let state = if TicketStatus::from_db(&ticket).is_some_and(TicketStatus::is_finished) {
+                    GarmentState::Processed
+                } else {
+                    GarmentState::NotProcessed
+                };
+                garment_repo::set_garment_state(conn, barcode, state)?;
+            }
+            Ok(())
+        })
     }
 
-    fn list_empty_slots(conn: &mut PgConnection) -> diesel::QueryResult<Vec<Slot>> {
-        use crate::schema::slots::dsl::*;
-        slots
-            .filter(slot_state.eq("empty"))
-            .order(slot_number.asc())
-            .load::<Slot>(conn)
+    /// The hanger sensor saw the garment arrive: record it on the slot and
+    /// move the slot from reserved to occupied. Refused unless the slot is
+    /// still reserved or occupied for `ticket`.
+    pub fn confirm_load(
+        conn: &mut PgConnection,
+        barcode: &str,
+        ticket: &str,
+        slot_number: i32,
+    ) -> StateResult<()> {
+        conn.transaction::<(), StateError, _>(|conn| {
+            let slot = SlotRepo::lock(conn, &[slot_number])?
+                .pop()
+                .ok_or_else(|| StateError::Rejected(format!("Slot {} does not exist", slot_number)))?;
+            let in_use = matches!(SlotState::from_db(&slot.slot_state), Some(SlotState::Reserved | SlotState::Occupied));
+            if !in_use || slot.assigned_ticket.as_deref() != Some(ticket) {
+                return Err(StateError::Rejected(format!(
+                    "Slot {} is {} for ticket {}, not held for ticket {}",
+                    slot_number,
+                    slot.slot_state,
+                    slot.assigned_ticket.as_deref().unwrap_or("(none)"),
+                    ticket
+                )));
+            }
+
+            Self::load_garment(conn, barcode, slot_number)?;
+            SlotRepo::set_occupied(conn, slot_number, Some(ticket), "hanger sensor confirmed load")
+        })
     }
 
-    fn list_occupied_slot_numbers(conn: &mut PgConnection) -> diesel::QueryResult<Vec<i32>> {
-        use crate::schema::slots::dsl::*;
-        slots
-            .filter(slot_state.ne("empty"))
-            .select(slot_number)
-            .order(slot_number.asc())
-            .load::<i32>(conn)
+    /// No sensor confirmation arrived in time: the garment is taken back off the
+    /// slot. A slot with nothing else on it goes to error so the hook gets
+    /// checked; a slot already holding garments stays in service.
+    /// Returns true if the slot was flagged.
+    pub fn fail_load(
+        conn: &mut PgConnection,
+        barcode: &str,
+        slot_number: i32,
+    ) -> StateResult<bool> {
+        conn.transaction::<bool, StateError, _>(|conn| {
+            let recorded = garment_repo::list_garments_in_slot(conn, slot_number)?
+                .iter()
+                .any(|g| g.item_id == barcode);
+            if recorded {
+                Self::unload_garment(conn, barcode)?;
+            }
+
+            let holds_garments = SlotRepo::get(conn, slot_number)?.is_some_and(|s| s.garment_count > 0);
+            if holds_garments {
+                return Ok(false);
+            }
+            SlotRepo::set_error(conn, slot_number, "no hanger sensor confirmation")?;
+            Ok(true)
+        })
     }
 
-    fn pick_spread(empties: &[Slot], occupied: &[i32]) -> Option<i32> {
-        if empties.is_empty() {
-            return None;
-        }
+    pub fn unload_garment(
+        conn: &mut PgConnection,
+        barcode: &str,
+    ) -> diesel::QueryResult<()> {
+        Self::load_garment(conn, barcode, -1)
+    }
 
-        let total = empties.len() + occupied.len();
-        let capacity_pct = if total > 0 {
-            (occupied.len() as f64 / total as f64) * 100.0
-        } else {
-            0.0
+    /// Frees every slot assigned to a ticket and returns the freed slot numbers.
+    pub fn free_ticket_slots(
+        conn: &mut PgConnection,
+        ticket: &str,
+        reason: &str,
+    ) -> StateResult<Vec<i32>> {
+        conn.transaction::<Vec<i32>, StateError, _>(|conn| {
+            let mut freed = Vec::new();
+            for slot in SlotRepo::find_ticket_slots(conn, ticket)? {
+                // A blocked slot only gives up the ticket; it stays out of service
+                if slot.slot_state == SlotState::Blocked.as_str() {
+                    SlotRepo::clear_assignment(conn, slot.slot_number, reason)?;
+                } else {
+                    SlotRepo::free_slot(conn, slot.slot_number, reason)?;
+                }
+                freed.push(slot.slot_number);
+            }
+            Ok(freed)
+        })
+    }
+
+    /// Takes a slot out of service, e.g. for a broken hook. Only empty or
+    /// error slots with no ticket and nothing loaded can be blocked; anything
+    /// holding a ticket or garments must be unloaded first.
+    pub fn block_slot(
+        conn: &mut PgConnection,
+        slot_number: i32,
+        reason: &str,
+    ) -> Result<(), String> {
+        let slot = SlotRepo::get(conn, slot_number)
+            .map_err(|e| format!("DB Error: {e}"))?
+            .ok_or_else(|| format!("Slot {} does not exist", slot_number))?;
+
+        let in_use = || {
+            format!(
+                "Slot {} is in use by ticket {} with {} garment(s); unload it before blocking",
+                slot_number,
+                slot.assigned_ticket.as_deref().unwrap_or("(none)"),
+                slot.garment_count
+            )
         };
+        match SlotState::from_db(&slot.slot_state) {
+            Some(SlotState::Empty | SlotState::Error)
+                if slot.garment_count == 0 && slot.assigned_ticket.is_none() => {}
+            Some(SlotState::Blocked) => return Err(format!("Slot {} is already blocked", slot_number)),
+            _ => return Err(in_use()),
+        }
 
-        // ≥ 50% full: just take the next available slot linearly.
-        if capacity_pct >= 50.0 {
-            return empties.first().map(|s| s.slot_number);
+        let blocked = SlotRepo::set_blocked(conn, slot_number, reason, false)
+            .map_err(|e| e.to_string())?;
+        if !blocked {
+            return Err(format!("Slot {} changed while it was being blocked; try again", slot_number));
         }
+        Ok(())
+    }
+
+    pub fn unblock_slot(
+        conn: &mut PgConnection,
+        slot_number: i32,
+        reason: &str,
+    ) -> Result<(), String> {
+        let slot = SlotRepo::get(conn, slot_number)
+            .map_err(|e| format!("DB Error: {e}"))?
+            .ok_or_else(|| format!("Slot {} does not exist", slot_number))?;
 
-        // < 50% full: find the first empty slot that is at least 5 away from
-        // every currently occupied slot.
-        if occupied.is_empty() {
-            return empties.first().map(|s| s.slot_number);
+        if SlotState::from_db(&slot.slot_state) != Some(SlotState::Blocked) {
+            return Err(format!("Slot {} is not blocked", slot_number));
+        }
+        if slot.blocked_by_layout {
+            return Err(format!(
+                "Slot {} is disabled in the frame configuration; enable it there instead",
+                slot_number
+            ));
         }
 
-        let candidate = empties.iter().find(|s| {
-            occupied.iter().all(|&o| (s.slot_number - o).abs() >= 5)
-        });
+        SlotRepo::set_unblocked(conn, slot_number, reason)
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn clear_slot_error(
+        conn: &mut PgConnection,
+        slot_number: i32,
+        reason: &str,
+    ) -> Result<(), String> {
+        let cleared = SlotRepo::clear_error(conn, slot_number, reason)
+            .map_err(|e| e.to_string())?;
+
+        if !cleared {
+            return Err(format!("Slot {} is not in error", slot_number));
+        }
+        Ok(())
+    }
+
+    /// Runs the strategy inside the ticket's pickup zone, falling back to
+    /// neighbouring zones and finally the whole conveyor.
+    fn pick_slot(
+        conn: &mut PgConnection,
+        strategy: &dyn SlotAllocationStrategy,
+        zoning: &PickupZoningSettings,
+        bucket: Option<PickupBucket>,
+    ) -> diesel::QueryResult<Option<i32>> {
+        let all = SlotRepo::list_all(conn)?;
+        let last_used = AppStateRepo::get_last_used_slot(conn)
+            .optional()?
+            .unwrap_or(0);
+
+        if let Some(bucket) = bucket {
+            for zone in slot_zoning::zones_in_fallback_order(zoning, bucket) {
+                let in_zone = all
+                    .iter()
+                    .filter(|s| slot_zoning::zone_contains(zone, s.slot_number, s.frame_number));
+
+                if let Some(chosen) = strategy.pick(&AllocationContext::from_slots(in_zone, last_used)) {
+                    return Ok(Some(chosen));
+                }
+            }
+        }
 
-        // Fall back to first available if no 5-apart slot exists.
-        candidate
-            .or_else(|| empties.first())
-            .map(|s| s.slot_number)
+        Ok(strategy.pick(&AllocationContext::from_slots(&all, last_used)))
     }
 
     pub fn get_number_occupied_slots(
@@ -161,6 +416,7 @@ impl SlotManager {
     ) -> diesel::QueryResult<i64> {
         use crate::schema::slots::dsl::*;
         slots
+            .filter(retired.eq(false))
             .count()
             .get_result::<i64>(conn)
     }
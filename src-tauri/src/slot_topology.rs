@@ -0,0 +1,148 @@
+use std::collections::{HashMap, HashSet};
+
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::db::{garment_repo, slot_repo::{SlotRepo, RETIRED_DELETE_REASON}, state_error::{StateError, StateResult}};
+use crate::model::{NewSlot, SlotState};
+use crate::settings::appsettings::FrameConfig;
+
+const LAYOUT_BLOCK_REASON: &str = "position disabled in frame configuration";
+
+/// Where a slot sits on the conveyor according to the frame configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SlotLayout {
+    pub slot_number: i32,
+    pub frame_number: i32,
+    pub frame_position: i32,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SlotSyncReport {
+    pub added: Vec<i32>,
+    pub blocked: Vec<i32>,
+    pub unblocked: Vec<i32>,
+    pub retired: Vec<i32>,
+    /// Disabled positions that still hold a ticket; blocked once emptied.
+    pub pending_block: Vec<i32>,
+    /// Removed positions that still hold a ticket or garments; deleted once emptied.
+    pub pending_retirement: Vec<i32>,
+    /// Removed positions left untouched because removal was not confirmed.
+    pub awaiting_removal: Vec<i32>,
+}
+
+/// Expands the frame configuration into one entry per slot.
+/// Slots are numbered from 1 across frames in order; frames and positions are 1-based.
+pub fn expected_slots(frames: &[FrameConfig]) -> Vec<SlotLayout> {
+    let mut layout = Vec::new();
+    let mut next_slot = 1;
+
+    for (frame_idx, frame) in frames.iter().enumerate() {
+        for (pos_idx, enabled) in frame.slots.iter().enumerate() {
+            layout.push(SlotLayout {
+                slot_number: next_slot,
+                frame_number: frame_idx as i32 + 1,
+                frame_position: pos_idx as i32 + 1,
+                enabled: *enabled,
+            });
+            next_slot += 1;
+        }
+    }
+
+    layout
+}
+
+/// Brings the `slots` table in line with the frame configuration.
+///
+/// New positions are inserted, disabled positions are blocked, re-enabled
+/// positions are unblocked. Positions that no longer exist are only touched
+/// when `remove` is set: they are deleted, or marked retired and deleted as
+/// soon as they are emptied if still in use. Otherwise they are listed in
+/// [`SlotSyncReport::awaiting_removal`].
+pub fn sync_slots_with_frames(
+    conn: &mut PgConnection,
+    frames: &[FrameConfig],
+    remove: bool,
+) -> StateResult<SlotSyncReport> {
+    let layout = expected_slots(frames);
+
+    conn.transaction::<SlotSyncReport, StateError, _>(|conn| {
+        let mut report = SlotSyncReport::default();
+
+        let existing: HashMap<i32, _> = SlotRepo::list_all(conn)?
+            .into_iter()
+            .map(|s| (s.slot_number, s))
+            .collect();
+
+        for entry in &layout {
+            let Some(slot) = existing.get(&entry.slot_number) else {
+                let state = if entry.enabled { SlotState::Empty } else { SlotState::Blocked };
+                SlotRepo::insert(conn, &NewSlot {
+                    slot_number: entry.slot_number,
+                    slot_state: state.as_str().to_string(),
+                    frame_number: entry.frame_number,
+                    frame_position: entry.frame_position,
+                    state_reason: (!entry.enabled).then(|| LAYOUT_BLOCK_REASON.to_string()),
+                    blocked_by_layout: !entry.enabled,
+                }, "added by frame configuration")?;
+                report.added.push(entry.slot_number);
+                continue;
+            };
+
+            SlotRepo::set_layout(conn, entry.slot_number, entry.frame_number, entry.frame_position, false)?;
+
+            let state = SlotState::from_db(&slot.slot_state);
+            match (entry.enabled, state) {
+                (false, Some(SlotState::Empty)) => {
+                    if SlotRepo::set_blocked(conn, entry.slot_number, LAYOUT_BLOCK_REASON, true)? {
+                        report.blocked.push(entry.slot_number);
+                    } else {
+                        report.pending_block.push(entry.slot_number);
+                    }
+                }
+                (false, Some(SlotState::Reserved | SlotState::Occupied)) => {
+                    report.pending_block.push(entry.slot_number);
+                }
+                // Admin blocks (broken hooks etc.) stay until an admin lifts them
+                (true, Some(SlotState::Blocked)) if slot.blocked_by_layout => {
+                    SlotRepo::set_unblocked(conn, entry.slot_number, "position enabled in frame configuration")?;
+                    report.unblocked.push(entry.slot_number);
+                }
+                _ => {}
+            }
+        }
+
+        let wanted: HashSet<i32> = layout.iter().map(|l| l.slot_number).collect();
+        let mut removed: Vec<_> = existing
+            .values()
+            .filter(|s| !wanted.contains(&s.slot_number))
+            .collect();
+        removed.sort_by_key(|s| s.slot_number);
+
+        for slot in removed {
+            if !remove {
+                report.awaiting_removal.push(slot.slot_number);
+                continue;
+            }
+            let in_use = slot.assigned_ticket.is_some()
+                || matches!(
+                    SlotState::from_db(&slot.slot_state),
+                    Some(SlotState::Reserved | SlotState::Occupied)
+                )
+                || garment_repo::count_garments_in_slot(conn, slot.slot_number)? > 0;
+
+            if in_use {
+                if !slot.retired {
+                    SlotRepo::set_layout(conn, slot.slot_number, slot.frame_number, slot.frame_position, true)?;
+                }
+                report.pending_retirement.push(slot.slot_number);
+            } else {
+                SlotRepo::delete(conn, slot.slot_number, RETIRED_DELETE_REASON)?;
+                report.retired.push(slot.slot_number);
+            }
+        }
+
+        Ok(report)
+    })
+}
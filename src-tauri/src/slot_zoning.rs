@@ -0,0 +1,140 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::db::slot_repo::SlotRepo;
+use crate::model::SlotState;
+use crate::settings::appsettings::{PickupZone, PickupZoningSettings};
+
+/// Groups tickets by how soon they will be picked up.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub enum PickupBucket {
+    Today,
+    Tomorrow,
+    ThisWeek,
+    Later,
+}
+
+impl PickupBucket {
+    /// Buckets in retrieval order; neighbouring zones are adjacent entries.
+    pub const ALL: [PickupBucket; 4] = [
+        PickupBucket::Today,
+        PickupBucket::Tomorrow,
+        PickupBucket::ThisWeek,
+        PickupBucket::Later,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PickupBucket::Today => "today",
+            PickupBucket::Tomorrow => "tomorrow",
+            PickupBucket::ThisWeek => "thisWeek",
+            PickupBucket::Later => "later",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "today" => Some(Self::Today),
+            "tomorrow" => Some(Self::Tomorrow),
+            "thisWeek" => Some(Self::ThisWeek),
+            "later" => Some(Self::Later),
+            _ => None,
+        }
+    }
+
+    /// Overdue pickups count as today. "This week" is two to six days out,
+    /// so today, tomorrow and this week together cover seven days.
+    pub fn for_pickup(pickup: NaiveDateTime, today: NaiveDate) -> Self {
+        let days = (pickup.date() - today).num_days();
+        match days {
+            d if d <= 0 => PickupBucket::Today,
+            1 => PickupBucket::Tomorrow,
+            2..=6 => PickupBucket::ThisWeek,
+            _ => PickupBucket::Later,
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|b| *b == self).unwrap_or(0)
+    }
+}
+
+pub fn zone_contains(zone: &PickupZone, slot_number: i32, frame_number: i32) -> bool {
+    if !zone.frames.is_empty() {
+        return zone.frames.contains(&frame_number);
+    }
+
+    zone.slot_start.is_none_or(|start| slot_number >= start)
+        && zone.slot_end.is_none_or(|end| slot_number <= end)
+}
+
+/// Zones to try for a bucket: its own zone first, then neighbours by distance.
+/// On a tie the later bucket is tried first so today's zone stays free for today.
+pub fn zones_in_fallback_order(
+    settings: &PickupZoningSettings,
+    bucket: PickupBucket,
+) -> Vec<&PickupZone> {
+    let origin = bucket.index() as i32;
+    let mut order: Vec<PickupBucket> = PickupBucket::ALL.to_vec();
+    order.sort_by_key(|b| {
+        let idx = b.index() as i32;
+        ((idx - origin).abs(), -idx)
+    });
+
+    order
+        .into_iter()
+        .flat_map(|b| {
+            settings
+                .zones
+                .iter()
+                .filter(move |z| PickupBucket::from_db(&z.bucket) == Some(b))
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct ZoneFill {
+    pub bucket: String,
+    pub total_slots: i64,
+    pub occupied_slots: i64,
+    pub occupancy_percentage: f64,
+}
+
+/// How full each configured pickup zone is.
+pub fn zone_fill(conn: &mut PgConnection, settings: &PickupZoningSettings) -> QueryResult<Vec<ZoneFill>> {
+    let all = SlotRepo::list_all(conn)?;
+
+    let fills = settings
+        .zones
+        .iter()
+        .map(|zone| {
+            let in_zone = all
+                .iter()
+                .filter(|s| !s.retired && zone_contains(zone, s.slot_number, s.frame_number));
+
+            let (mut total, mut occupied) = (0i64, 0i64);
+            for s in in_zone {
+                total += 1;
+                if SlotState::from_db(&s.slot_state) != Some(SlotState::Empty) {
+                    occupied += 1;
+                }
+            }
+
+            let occupancy_percentage = if total > 0 {
+                (occupied as f64 / total as f64) * 100.0
+            } else {
+                0.0
+            };
+
+            ZoneFill {
+                bucket: zone.bucket.clone(),
+                total_slots: total,
+                occupied_slots: occupied,
+                occupancy_percentage,
+            }
+        })
+        .collect();
+
+    Ok(fills)
+}
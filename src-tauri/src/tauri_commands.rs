@@ -6,9 +6,14 @@ use notify::null;
 use serde::Serialize;
 use tokio::time::{sleep, timeout};
 
-use crate::{db::{connection::establish_connection, conveyor_activity_repo, garment_repo::{self, garment_exists}, sessions_repo, slot_repo::{self, SlotRepo}, ticket_repo, users_repo}, domain::auth, model::{ConveyorActionType, ConveyorActivity, Customer, NewConveyorActivity, Ticket, UpdateTicket, User}, opc::{opc_client::AppState, opc_commands::get_load_hanger_sensor}, pos::spot::output::{conveyor_file_utils::{self, write_load_item, write_print_invoice, write_split_invoice, write_unload_item}, conveyor_ops_types::{ConveyorOpsTypes}}, slot_manager::{self, SlotManager, SlotManagerStats}};
+use crate::{db::{connection::establish_connection, conveyor_activity_repo, garment_repo::{self, garment_exists}, sessions_repo, slot_history_repo::SlotHistoryRepo, slot_repo::{self, SlotRepo}, ticket_repo, users_repo}, domain::auth, model::{ConveyorActionType, ConveyorActivity, ConveyorSnapshot, Customer, InventoryAudit, InventoryAuditScan, NewConveyorActivity, Slot, SlotHistory, Ticket, TicketStatus, UpdateTicket, User}, opc::opc_client::AppState, pos::spot::output::{conveyor_file_utils::{self, write_load_item, write_print_invoice, write_split_invoice, write_unload_item}, conveyor_ops_types::{ConveyorOpsTypes}}, slot_manager::{self, SlotAssignment, SlotManager, SlotManagerStats}, slot_topology::SlotSyncReport};
 
 use crate::admin::report_generator;
+use crate::conveyor_clear::{self, ClearReport, ClearScope};
+use crate::inventory_audit::{self, AuditApplyReport, AuditReport};
+use crate::slot_consistency::{run_consistency_check, ConsistencyReport};
+use crate::slot_consolidation::{self, ConsolidationRun};
+use crate::retrieval_queue::{self, RetrievalQueue};
 
 #[derive(Serialize)]
 pub struct LoginResult {
@@ -34,6 +39,7 @@ pub fn auth_login_user_tauri(pin_input: String) -> Result<User, String> {
 
     
     let user = auth::login_user_with_pin(&pin_input)?;
+    crate::domain::session::set_authenticated_user(user.id);
 
     Ok(user)
 }
@@ -55,7 +61,7 @@ pub fn get_all_users_tauri() -> Result<Vec<User>, String> {
 }
 
 #[tauri::command]
-pub fn handle_scan_tauri(scan_code: String) -> Result<Option<i32>, String> {
+pub fn handle_scan_tauri(scan_code: String) -> Result<SlotAssignment, String> {
     println!("Handling scan for code: {}", scan_code);
     let code = scan_code.trim().to_string();
     if code.len() < 4 {
@@ -95,14 +101,18 @@ pub fn handle_scan_tauri(scan_code: String) -> Result<Option<i32>, String> {
 
 
 
-        let _res = ticket_repo::update_ticket(&mut conn, ticket_info.id, update_ticket);
+        ticket_repo::update_ticket(&mut conn, ticket_info.id, update_ticket)
+            .map_err(|e| format!("DB Error (update ticket): {e}"))?;
 
-        let slot = slot_repo::SlotRepo::find_ticket_slot(&mut conn, &ticket_info.full_invoice_number)
-            .map_err(|e| format!("DB Error (find slot): {e}"))?
-            .ok_or_else(|| "Garment ticket not on conveyor".to_string())?;
+        // One of the ticket's slots with room left, or a nearby slot once they are full
+        let assignment = SlotManager::assign_slot(&mut conn, Some(&ticket_info.full_invoice_number))
+            .map_err(|e| format!("DB Error (reserve slot): {e}"))?;
+        if assignment.newly_reserved {
+            println!("Ticket {} slots are full, reserved slot {}", ticket_info.full_invoice_number, assignment.slot_number);
+        }
 
-        let _ = write_load_item(ConveyorOpsTypes::LoadItem, &ticket_info.full_invoice_number, &garment.item_id, slot.slot_number as u32);
-        return Ok(Some(slot.slot_number));
+        let _ = write_load_item(ConveyorOpsTypes::LoadItem, &ticket_info.full_invoice_number, &garment.item_id, assignment.slot_number as u32);
+        return Ok(assignment);
     }
 
     ticket_info.garments_processed += 1;
@@ -113,16 +123,17 @@ pub fn handle_scan_tauri(scan_code: String) -> Result<Option<i32>, String> {
         number_of_items: Some(ticket_info.number_of_items),
         garments_processed: Some(ticket_info.garments_processed),
         invoice_pickup_date: ticket_info.invoice_pickup_date,
-        ticket_status: Some("Processing".to_string()),
+        ticket_status: Some(TicketStatus::Processing.as_str().to_string()),
     };
-    let _res = ticket_repo::update_ticket(&mut conn, ticket_info.id, update_ticket);
+    // Fails for a finished ticket until it is reopened
+    ticket_repo::update_ticket(&mut conn, ticket_info.id, update_ticket)?;
 
-    let reserved_slot = SlotManager::reserve_next_slot(&mut conn, Some(&ticket_info.full_invoice_number))
+    let assignment = SlotManager::assign_slot(&mut conn, Some(&ticket_info.full_invoice_number))
         .map_err(|e| format!("DB Error (reserve slot): {e}"))?;
 
-    let _ = write_load_item(ConveyorOpsTypes::LoadItem, &ticket_info.full_invoice_number, &garment.item_id, reserved_slot as u32);
+    let _ = write_load_item(ConveyorOpsTypes::LoadItem, &ticket_info.full_invoice_number, &garment.item_id, assignment.slot_number as u32);
 
-    Ok(Some(reserved_slot))
+    Ok(assignment)
 }
 
 
@@ -214,7 +225,7 @@ pub async fn wait_for_hanger_sensor(
     println!("Waiting for hanger sensor...");
     let result = timeout(Duration::from_secs(10), async {
         loop {
-            match get_load_hanger_sensor(&state.opc).await {
+            match state.controller.hanger_sensor().await {
                 Ok(true) => {
                     println!("Hanger sensor detected!");
                     return true;
@@ -230,6 +241,44 @@ pub async fn wait_for_hanger_sensor(
     Ok(result.is_ok())
 }
 
+/// Waits for the hanger sensor to confirm the garment at the load station, then
+/// records it on the slot and marks the slot occupied. If nothing is sensed within
+/// the configured window `false` is returned and the garment stays off the slot;
+/// the slot goes to the error state unless other garments already hang on it.
+/// A confirmation is refused if the slot no longer holds the garment's ticket.
+#[tauri::command]
+pub async fn confirm_garment_load_tauri(
+    state: tauri::State<'_, AppState>,
+    barcode: String,
+    slot_number: i32,
+) -> Result<bool, String> {
+    let garment = {
+        let mut conn = establish_connection()?;
+        garment_repo::get_garment(&mut conn, &barcode)
+            .map_err(|_| format!("Garment not found: {}", barcode))?
+    };
+
+    let window = Duration::from_millis(SlotManager::allocation_settings().load_confirm_timeout_ms);
+    let confirmed = crate::opc::sensor::wait_for_hanger(state.controller.as_ref(), window).await;
+
+    let mut conn = establish_connection()?;
+    if confirmed {
+        SlotManager::confirm_load(&mut conn, &barcode, &garment.full_invoice_number, slot_number)
+            .map_err(|e| e.to_string())?;
+    } else {
+        let flagged = SlotManager::fail_load(&mut conn, &barcode, slot_number)
+            .map_err(|e| e.to_string())?;
+        println!(
+            "No hanger confirmation for {} on slot {}{}",
+            barcode,
+            slot_number,
+            if flagged { "; flagged slot" } else { "" }
+        );
+    }
+
+    Ok(confirmed)
+}
+
 #[tauri::command]
 pub fn load_sensor_hanger_tauri(state: tauri::State<'_, AppState>) -> Result<bool, String> {
     Ok(state.hanger_detected.load(Ordering::Relaxed))
@@ -237,7 +286,7 @@ pub fn load_sensor_hanger_tauri(state: tauri::State<'_, AppState>) -> Result<boo
 
 #[tauri::command]
 pub fn check_opc_connection_tauri(state: tauri::State<'_, AppState>) -> bool {
-    crate::opc::opc_commands::check_opc_connection(&state.opc)
+    state.controller.is_connected()
 }
 
 #[tauri::command]
@@ -260,11 +309,20 @@ pub async fn get_slot_number_from_barcode_tauri(
     }
 }
 
+#[derive(Serialize)]
+pub struct TicketCompletion {
+    /// Slot the final garment was loaded onto
+    pub slot_number: i32,
+    /// Every slot the ticket occupied, all now freed
+    pub freed_slots: Vec<i32>,
+}
+
 /// Called when the last garment on a ticket is scanned.
 /// Writes load_item for the scanned garment, then unload_item for every
-/// garment on the ticket, and marks the ticket as Complete.
+/// garment on the ticket, frees every slot the ticket used and marks the
+/// ticket as Complete.
 #[tauri::command]
-pub fn complete_ticket_tauri(barcode: String) -> Result<Option<i32>, String> {
+pub fn complete_ticket_tauri(barcode: String) -> Result<TicketCompletion, String> {
     let mut conn = establish_connection()?;
 
     let garment = garment_repo::get_garment(&mut conn, &barcode)
@@ -273,28 +331,29 @@ pub fn complete_ticket_tauri(barcode: String) -> Result<Option<i32>, String> {
     let mut ticket = ticket_repo::get_ticket_by_invoice_number(&mut conn, &garment.full_invoice_number)
         .map_err(|_| format!("Ticket not found for garment: {}", barcode))?;
 
-    // Find the existing conveyor slot, or reserve one for single-item tickets
-    let slot_number = match SlotRepo::find_ticket_slot(&mut conn, &ticket.full_invoice_number)
-        .map_err(|e| format!("DB Error (find slot): {e}"))?
-    {
-        Some(slot) => slot.slot_number,
+    // One of the ticket's slots; only single-item tickets need a slot reserved
+    let existing_slot = SlotManager::current_ticket_slot(&mut conn, &ticket.full_invoice_number)
+        .map_err(|e| format!("DB Error (find slot): {e}"))?;
+    let slot_number = match existing_slot {
+        Some(slot) => slot,
         None => SlotManager::reserve_next_slot(&mut conn, Some(&ticket.full_invoice_number))
             .map_err(|e| format!("DB Error (reserve slot): {e}"))?,
     };
 
-    // Clear the slot
-    slot_manager::SlotManager::free_slot(&mut conn, slot_number)
-        .map_err(|e| format!("DB Error (free slot): {e}"))?;
+    // Clear every slot the ticket is hanging on
+    let freed_slots = SlotManager::free_ticket_slots(&mut conn, &ticket.full_invoice_number, "ticket complete")
+        .map_err(|e| e.to_string())?;
 
     // Load the last garment onto the conveyor
     let _ = write_load_item(ConveyorOpsTypes::LoadItem, &ticket.full_invoice_number, &garment.item_id, slot_number as u32);
 
-    // Unload every garment — ticket is now complete
+    // Unload every garment from the slot it hangs on — ticket is now complete
     let all_garments = garment_repo::list_garments_for_ticket(&mut conn, &ticket.full_invoice_number)
         .map_err(|e| format!("DB Error (list garments): {e}"))?;
 
     for g in &all_garments {
-        let _ = write_unload_item(ConveyorOpsTypes::UnloadItem, &ticket.full_invoice_number, &g.item_id, slot_number as u32);
+        let g_slot = if freed_slots.contains(&g.slot_number) { g.slot_number } else { slot_number };
+        let _ = write_unload_item(ConveyorOpsTypes::UnloadItem, &ticket.full_invoice_number, &g.item_id, g_slot as u32);
     }
 
     // Mark ticket complete
@@ -305,13 +364,14 @@ pub fn complete_ticket_tauri(barcode: String) -> Result<Option<i32>, String> {
         number_of_items: Some(ticket.number_of_items),
         garments_processed: Some(ticket.garments_processed),
         invoice_pickup_date: ticket.invoice_pickup_date,
-        ticket_status: Some("Complete".to_string()),
+        ticket_status: Some(TicketStatus::Complete.as_str().to_string()),
     };
-    let _res = ticket_repo::update_ticket(&mut conn, ticket.id, update_ticket);
+    ticket_repo::update_ticket(&mut conn, ticket.id, update_ticket)
+        .map_err(|e| format!("DB Error (complete ticket): {e}"))?;
 
     write_print_invoice(ConveyorOpsTypes::PrintInvoice, &ticket.full_invoice_number, 1)?;
 
-    Ok(Some(slot_number))
+    Ok(TicketCompletion { slot_number, freed_slots })
 }
 
 #[tauri::command]
@@ -344,7 +404,7 @@ pub async fn handle_last_scan(barcode: String, slot_num: i32) -> Result<i32, Str
     );
 
     let ticket_info = ticket.unwrap();
-    let new_status: &str = "Processed";
+    let new_status: &str = TicketStatus::Processed.as_str();
 
     let update_ticket = &UpdateTicket {
         full_invoice_number: Some(ticket_info.full_invoice_number),
@@ -355,9 +415,11 @@ pub async fn handle_last_scan(barcode: String, slot_num: i32) -> Result<i32, Str
         ticket_status: Some(new_status.to_string())
     };
 
-    let _res = ticket_repo::update_ticket(&mut conn, ticket_info.id, update_ticket);
+    ticket_repo::update_ticket(&mut conn, ticket_info.id, update_ticket)
+        .map_err(|e| format!("DB Error (update ticket): {e}"))?;
 
-    let _ = slot_repo::SlotRepo::free_slot(&mut conn, slot_num);
+    slot_repo::SlotRepo::free_slot(&mut conn, slot_num, "last garment scanned")
+        .map_err(|e| e.to_string())?;
 
     Ok(slot_num)
 }
@@ -369,19 +431,28 @@ pub fn get_slot_manager_stats() -> Result<SlotManagerStats, String> {
         .map_err(|e| format!("DB Error: {}", e))
 }
 
+/// Starts a session for the user who just logged in with their PIN.
 #[tauri::command]
 pub fn start_user_session(user_id_input: i32) -> Result<crate::model::Session, String> {
+    if crate::domain::session::authenticated_user() != Some(user_id_input) {
+        return Err("Log in with your PIN before starting a session".into());
+    }
     let mut conn = establish_connection()?;
-    conn.transaction::<crate::model::Session, diesel::result::Error, _>(|conn| {
+    let session = conn.transaction::<crate::model::Session, diesel::result::Error, _>(|conn| {
         sessions_repo::close_active_sessions_for_user(conn, user_id_input)?;
         sessions_repo::create_session(conn, user_id_input)
     })
-    .map_err(|e| format!("DB Error: {}", e))
+    .map_err(|e| format!("DB Error: {}", e))?;
+
+    // Slot changes from here on are attributed to this operator
+    crate::domain::session::set_active_session(&session);
+    Ok(session)
 }
 
 #[tauri::command]
 pub fn end_user_session(session_id: i32) -> Result<crate::model::Session, String> {
     let mut conn = establish_connection()?;
+    crate::domain::session::clear_active_session(session_id);
     sessions_repo::end_session(&mut conn, session_id)
         .map_err(|e| format!("DB Error: {}", e))
 }
@@ -408,44 +479,144 @@ pub fn get_session_by_id_tauri(session_id: i32) -> Result<Option<crate::model::S
         .map_err(|e| format!("DB Error: {}", e))
 }
 
+/// Empties the whole conveyor; a snapshot is taken first so an admin can undo it.
+#[tauri::command]
+pub fn clear_conveyor_tauri() -> Result<ClearReport, String> {
+    let mut conn = establish_connection()?;
+    conveyor_clear::clear_conveyor(&mut conn, &ClearScope::All)
+        .map_err(|e| e.to_string())
+}
+
+/// Empties a slot range, a frame or one ticket's slots, touching only the tickets on them.
+#[tauri::command]
+pub fn clear_conveyor_scope_tauri(scope: ClearScope) -> Result<ClearReport, String> {
+    let mut conn = establish_connection()?;
+    if let ClearScope::SlotRange { start, end } = scope {
+        if start > end {
+            return Err(format!("Invalid slot range {}-{}", start, end));
+        }
+    }
+    conveyor_clear::clear_conveyor(&mut conn, &scope)
+        .map_err(|e| e.to_string())
+}
+
+/// Admin only: snapshots taken before recent clears, newest first.
+#[tauri::command]
+pub fn list_conveyor_snapshots_tauri() -> Result<Vec<ConveyorSnapshot>, String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+    conveyor_clear::list_snapshots(&mut conn)
+        .map_err(|e| format!("DB Error: {}", e))
+}
+
+/// Admin only: undoes a clear, provided nothing it emptied has been reused.
 #[tauri::command]
-pub fn clear_conveyor_tauri() -> Result<(), String> {
+pub fn restore_conveyor_snapshot_tauri(snapshot_id: i32) -> Result<(), String> {
     let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+    conveyor_clear::restore_snapshot(&mut conn, snapshot_id)
+}
 
-    conn.transaction::<(), diesel::result::Error, _>(|conn| {
-        use crate::schema::garments::dsl as garments_dsl;
-        use crate::schema::slots::dsl as slots_dsl;
-        use crate::schema::tickets::dsl as tickets_dsl;
+/// Starts a physical inventory audit; fails if one is already open.
+#[tauri::command]
+pub fn start_inventory_audit_tauri() -> Result<InventoryAudit, String> {
+    let mut conn = establish_connection()?;
+    inventory_audit::start_audit(&mut conn)
+}
 
-        let _affected_tickets: Vec<String> = garments_dsl::garments
-            .filter(garments_dsl::slot_number.ne(-1))
-            .select(garments_dsl::full_invoice_number)
-            .distinct()
-            .load(conn)?;
+/// Records a garment barcode found on a slot. Scanning it again moves it.
+#[tauri::command]
+pub fn record_audit_scan_tauri(audit_id: i32, slot_number: i32, item_id: String) -> Result<InventoryAuditScan, String> {
+    let mut conn = establish_connection()?;
+    inventory_audit::record_scan(&mut conn, audit_id, slot_number, &item_id)
+}
 
-        diesel::update(slots_dsl::slots)
-            .set((
-                slots_dsl::slot_state.eq("empty"),
-                slots_dsl::assigned_ticket.eq::<Option<String>>(None),
-                slots_dsl::item_id.eq::<Option<String>>(None),
-                slots_dsl::updated_at.eq(diesel::dsl::now),
-            ))
-            .execute(conn)?;
+#[tauri::command]
+pub fn get_inventory_audit_report_tauri(audit_id: i32) -> Result<AuditReport, String> {
+    let mut conn = establish_connection()?;
+    inventory_audit::audit_report(&mut conn, audit_id)
+}
 
-        diesel::update(garments_dsl::garments.filter(garments_dsl::slot_number.ne(-1)))
-            .set(garments_dsl::slot_number.eq(-1))
-            .execute(conn)?;
+/// Closes the audit and stores its discrepancy report.
+#[tauri::command]
+pub fn finish_inventory_audit_tauri(audit_id: i32) -> Result<AuditReport, String> {
+    let mut conn = establish_connection()?;
+    inventory_audit::finish_audit(&mut conn, audit_id)
+}
 
-        diesel::update(tickets_dsl::tickets)
-            .set((
-                tickets_dsl::garments_processed.eq(0),
-                tickets_dsl::ticket_status.eq("Not Processed"),
-            ))
-            .execute(conn)?;
+/// Admin only: moves garments to where a finished audit found them.
+#[tauri::command]
+pub fn apply_inventory_audit_tauri(audit_id: i32) -> Result<AuditApplyReport, String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+    inventory_audit::apply_audit(&mut conn, audit_id)
+}
 
-        Ok(())
-    })
-    .map_err(|e| format!("DB Error: {}", e))
+#[tauri::command]
+pub fn list_inventory_audits_tauri() -> Result<Vec<InventoryAudit>, String> {
+    let mut conn = establish_connection()?;
+    inventory_audit::list_audits(&mut conn)
+        .map_err(|e| format!("DB Error: {}", e))
+}
+
+/// Plans the fewest slot moves that free `run_length` consecutive slots.
+#[tauri::command]
+pub fn start_slot_consolidation_tauri(run_length: i32) -> Result<ConsolidationRun, String> {
+    let mut conn = establish_connection()?;
+    slot_consolidation::start_consolidation(&mut conn, run_length)
+}
+
+#[tauri::command]
+pub fn get_slot_consolidation_tauri() -> Result<Option<ConsolidationRun>, String> {
+    Ok(slot_consolidation::active_consolidation())
+}
+
+/// Call once the operator has moved the garments for the next step.
+#[tauri::command]
+pub fn confirm_consolidation_move_tauri() -> Result<ConsolidationRun, String> {
+    let mut conn = establish_connection()?;
+    slot_consolidation::confirm_next_move(&mut conn)
+}
+
+#[tauri::command]
+pub fn skip_consolidation_move_tauri() -> Result<ConsolidationRun, String> {
+    slot_consolidation::skip_next_move()
+}
+
+#[tauri::command]
+pub fn cancel_slot_consolidation_tauri() -> Result<(), String> {
+    slot_consolidation::cancel_consolidation();
+    Ok(())
+}
+
+/// Queues every slot of each ticket for retrieval. With `optimize` the
+/// pending list is reordered to minimise conveyor travel.
+#[tauri::command]
+pub fn enqueue_retrievals_tauri(app: tauri::AppHandle, tickets: Vec<String>, optimize: bool) -> Result<RetrievalQueue, String> {
+    let mut conn = establish_connection()?;
+    retrieval_queue::enqueue_tickets(&mut conn, &app, &tickets, optimize)
+}
+
+#[tauri::command]
+pub fn get_retrieval_queue_tauri() -> Result<RetrievalQueue, String> {
+    Ok(retrieval_queue::snapshot())
+}
+
+#[tauri::command]
+pub fn cancel_retrieval_tauri(app: tauri::AppHandle, id: u64) -> Result<RetrievalQueue, String> {
+    retrieval_queue::cancel_item(&app, id)
+}
+
+/// Moves a pending retrieval to `position` in the queue; 0 runs it next.
+#[tauri::command]
+pub fn reprioritize_retrieval_tauri(app: tauri::AppHandle, id: u64, position: usize) -> Result<RetrievalQueue, String> {
+    retrieval_queue::move_item(&app, id, position)
+}
+
+/// The queue pauses itself after a failed run; resuming continues with the next slot.
+#[tauri::command]
+pub fn set_retrieval_queue_paused_tauri(app: tauri::AppHandle, paused: bool) -> Result<RetrievalQueue, String> {
+    Ok(retrieval_queue::set_paused(&app, paused))
 }
 
 
@@ -549,8 +720,6 @@ pub fn save_settings_tauri(
     conveyor_csv_output_dir: String,
     frames: Vec<crate::settings::appsettings::FrameConfig>,
 ) -> Result<(), String> {
-    use tauri_plugin_store::StoreExt;
-
     let existing = crate::settings::load_settings(&app);
     let settings = crate::settings::appsettings::AppSettings {
         posCsvDir: pos_csv_dir,
@@ -568,12 +737,16 @@ pub fn save_settings_tauri(
         fieldMappings: existing.fieldMappings,
         printer: existing.printer,
         frames,
+        slotAllocation: existing.slotAllocation,
+        opcTags: existing.opcTags,
+        conveyorStatusCodes: existing.conveyorStatusCodes,
+        runRequest: existing.runRequest,
+        opcSecurity: existing.opcSecurity,
+        controller: existing.controller,
+        alarms: existing.alarms,
     };
 
-    let store = app.store("settings.json").map_err(|e| format!("Store error: {}", e))?;
-
-    store.set("app_settings", serde_json::to_value(&settings).map_err(|e| e.to_string())?);
-    store.save().map_err(|e| format!("Failed to save settings: {}", e))?;
+    crate::settings::save_settings(&app, &settings)?;
 
     // Update the global database URL so establish_connection() uses the new settings
     let database_url = crate::settings::database_url(&settings);
@@ -582,12 +755,19 @@ pub fn save_settings_tauri(
     // Update the global conveyor CSV output directory
     crate::pos::spot::output::conveyor_file_utils::set_conveyor_csv_output_dir(&settings.conveyorCsvOutputDir);
 
-    // Run migrations on the new database
+    SlotManager::set_allocation_settings(&settings.slotAllocation);
+
+    // Run migrations on the new database, then add and block slots from the
+    // frame layout. Removed positions wait for an admin sync.
     match crate::db::connection::establish_connection() {
         Ok(mut conn) => {
             if let Err(e) = crate::db::db_migrations::run_db_migrations(&mut conn) {
                 return Err(format!("Failed to run migrations: {}", e));
             }
+            if settings.frames_configured() {
+                crate::slot_topology::sync_slots_with_frames(&mut conn, &settings.frames, false)
+                    .map_err(|e| format!("Failed to sync slot topology: {}", e))?;
+            }
         }
         Err(e) => return Err(format!("Failed to connect with new settings: {}", e)),
     }
@@ -617,6 +797,20 @@ pub fn test_database_connection_tauri(
     }
 }
 
+/// Admin only. Syncs slots with the frame configuration, including deleting
+/// or retiring positions that were removed from it.
+#[tauri::command]
+pub fn sync_slot_topology_tauri(app: tauri::AppHandle) -> Result<SlotSyncReport, String> {
+    let settings = crate::settings::load_settings(&app);
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+    if !settings.frames_configured() {
+        return Err("Configure the conveyor frames before syncing slots".into());
+    }
+    crate::slot_topology::sync_slots_with_frames(&mut conn, &settings.frames, true)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_current_settings_tauri(app: tauri::AppHandle) -> Result<crate::settings::appsettings::AppSettings, String> {
     Ok(crate::settings::load_settings(&app))
@@ -719,10 +913,8 @@ pub fn update_garment_slot_tauri(barcode: String, slot_number: i32) -> Result<()
         return Err("Garment Not Found".to_string());
     }
 
-    let mut garment_info = garment.unwrap();
-    garment_info.slot_number = slot_number;
-
-    garment_repo::update_garment_slot(&mut conn, &barcode, garment_info.slot_number)
+    // Keeps the slot's garment count and latest item in step with the garment
+    SlotManager::load_garment(&mut conn, &barcode, slot_number)
         .map_err(|e| format!("DB Error: {}", e))?;
 
     Ok(())
@@ -744,7 +936,7 @@ pub fn remove_garment_from_slot_tauri(ticket: String, slot_num: i32) -> Result<(
 
     for garment in garments {
         if garment.slot_number != -1 {
-            garment_repo::update_garment_slot(&mut conn, &garment.item_id, -1)
+            SlotManager::unload_garment(&mut conn, &garment.item_id)
                 .map_err(|e| format!("DB Error (update garment slot): {e}"))?;
 
             let _ = write_unload_item(ConveyorOpsTypes::UnloadItem, &garment.full_invoice_number, &garment.item_id, garment.slot_number as u32);
@@ -755,23 +947,27 @@ pub fn remove_garment_from_slot_tauri(ticket: String, slot_num: i32) -> Result<(
     let ticket = ticket_repo::get_ticket_by_invoice_number(&mut conn, &ticket)
         .map_err(|e| format!("DB Error (get ticket): {e}"))?;
 
+    // A completed ticket keeps its status and count once its garments come off
+    let finished = TicketStatus::from_db(&ticket.ticket_status).is_some_and(TicketStatus::is_finished);
     let updated_ticket =  UpdateTicket {
         full_invoice_number: Some(ticket.full_invoice_number.clone()),
         display_invoice_number: Some(ticket.display_invoice_number.clone()),
-        garments_processed: Some(0),
+        garments_processed: (!finished).then_some(0),
         number_of_items: Some(ticket.number_of_items),
         invoice_pickup_date: ticket.invoice_pickup_date,
-        ticket_status: Some("Not Processed".to_string()),
+        ticket_status: (!finished).then(|| TicketStatus::NotProcessed.as_str().to_string()),
     };
 
     ticket_repo::update_ticket(&mut conn, ticket.id, &updated_ticket)
         .map_err(|e| format!("DB Error (update garments processed): {e}"))?;
 
 
-    println!("Freeing slot {}", slot_num);
+    println!("Freeing slot {} and any other slots held by {}", slot_num, ticket.full_invoice_number);
 
-    slot_manager::SlotManager::free_slot(&mut conn, slot_num)
-        .map_err(|e| format!("DB Error (free slot): {e}"))?;
+    slot_manager::SlotManager::free_slot(&mut conn, slot_num, "garments removed from conveyor")
+        .map_err(|e| e.to_string())?;
+    slot_manager::SlotManager::free_ticket_slots(&mut conn, &ticket.full_invoice_number, "garments removed from conveyor")
+        .map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -783,7 +979,7 @@ pub fn is_ticket_complete_tauri(ticket: String) -> Result<bool, String> {
     let ticket_info = ticket_repo::get_ticket_by_invoice_number(&mut conn, &ticket)
         .map_err(|e| format!("DB Error (get ticket): {e}"))?;
 
-    Ok(ticket_info.ticket_status == "Complete")
+    Ok(ticket_info.ticket_status == TicketStatus::Complete.as_str())
 }
 
 #[tauri::command]
@@ -810,6 +1006,91 @@ pub fn get_conveyor_activity_report_tauri() -> Result<Vec<ConveyorActivity>, Str
     return Ok(activity)
 }
 
+#[tauri::command]
+pub fn get_pickup_zone_report_tauri() -> Result<Vec<crate::slot_zoning::ZoneFill>, String> {
+    report_generator::generate_pickup_zone_report()
+}
+
+/// Every change to one slot, oldest first.
+#[tauri::command]
+pub fn get_slot_timeline_tauri(slot_number: i32) -> Result<Vec<SlotHistory>, String> {
+    let mut conn = establish_connection()?;
+    SlotHistoryRepo::for_slot(&mut conn, slot_number)
+        .map_err(|e| format!("DB Error: {}", e))
+}
+
+/// Every slot a ticket has reserved, loaded onto or left, oldest first.
+#[tauri::command]
+pub fn get_ticket_slot_history_tauri(ticket: String) -> Result<Vec<SlotHistory>, String> {
+    let mut conn = establish_connection()?;
+    SlotHistoryRepo::for_ticket(&mut conn, &ticket)
+        .map_err(|e| format!("DB Error: {}", e))
+}
+
+/// Admin only: takes a slot out of service so allocation skips it.
+#[tauri::command]
+pub fn block_slot_tauri(slot_number: i32, reason: String) -> Result<(), String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+
+    if reason.trim().is_empty() {
+        return Err("A reason is required to block a slot".into());
+    }
+    SlotManager::block_slot(&mut conn, slot_number, reason.trim())
+}
+
+/// Admin only: returns an admin-blocked slot to service.
+#[tauri::command]
+pub fn unblock_slot_tauri(slot_number: i32, reason: String) -> Result<(), String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+    let reason = match reason.trim() {
+        "" => "unblocked by admin",
+        r => r,
+    };
+    SlotManager::unblock_slot(&mut conn, slot_number, reason)
+}
+
+/// Admin only: clears an error slot once the hook has been checked.
+#[tauri::command]
+pub fn clear_slot_error_tauri(slot_number: i32, reason: String) -> Result<(), String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+    let reason = match reason.trim() {
+        "" => "error cleared by admin",
+        r => r,
+    };
+    SlotManager::clear_slot_error(&mut conn, slot_number, reason)
+}
+
+/// Admin only: every blocked or error slot with the reason it was taken out.
+#[tauri::command]
+pub fn get_out_of_service_slots_tauri() -> Result<Vec<Slot>, String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+    SlotRepo::list_out_of_service(&mut conn)
+        .map_err(|e| format!("DB Error: {}", e))
+}
+
+/// Lists drift between slots, garments and tickets. With `apply` (admin only)
+/// the repairs are written to the database.
+#[tauri::command]
+pub fn check_slot_consistency_tauri(apply: bool) -> Result<ConsistencyReport, String> {
+    let mut conn = establish_connection()?;
+    if apply {
+        auth::require_admin(&mut conn)?;
+    }
+    run_consistency_check(&mut conn, apply)
+        .map_err(|e| e.to_string())
+}
+
+/// Admin only: reopens a completed ticket so its garments can be scanned again.
+#[tauri::command]
+pub fn reopen_ticket_tauri(ticket: String) -> Result<Ticket, String> {
+    let mut conn = establish_connection()?;
+    auth::require_admin(&mut conn)?;
+    ticket_repo::reopen_ticket(&mut conn, &ticket)
+}
 
 #[tauri::command]
 pub fn add_conveyor_activity_load_tauri(ticket: String, garment: String, slot_num: i32, customer_identifier: String) -> Result<ConveyorActivity, String> {
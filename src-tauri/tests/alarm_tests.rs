@@ -0,0 +1,72 @@
+#[path = "common/mod.rs"]
+mod common;
+
+use common::fixtures::{alarm_tag, open_alarm};
+use conveyoros_oas_lib::{
+    opc::alarms::{decode_conditions, reconcile, validate_alarm_settings, AlarmCondition},
+    settings::appsettings::{AlarmSettings, AlarmTag},
+};
+
+#[test]
+fn code_tags_alarm_outside_their_normal_values() {
+    let tag = alarm_tag("code", vec![0, 1]);
+    assert!(decode_conditions(&tag, 0, 16).is_empty());
+    assert!(decode_conditions(&tag, 1, 16).is_empty());
+    assert_eq!(
+        decode_conditions(&tag, 2, 16),
+        vec![AlarmCondition { code: 2, message: "Overcurrent".into() }]
+    );
+    assert_eq!(decode_conditions(&tag, 9, 16)[0].message, "InverterStatus fault code 9");
+}
+
+#[test]
+fn bit_tags_alarm_once_per_set_bit() {
+    let conditions = decode_conditions(&alarm_tag("bits", vec![]), 0b101, 16);
+    let codes: Vec<i64> = conditions.iter().map(|c| c.code).collect();
+    assert_eq!(codes, vec![0, 2]);
+    assert_eq!(conditions[1].message, "Overcurrent");
+}
+
+#[test]
+fn reconcile_raises_new_conditions_and_clears_gone_ones() {
+    let open = [open_alarm(1, 0), open_alarm(2, 2)];
+    let active = decode_conditions(&alarm_tag("bits", vec![]), 0b1001, 16);
+
+    let changes = reconcile(&open, &active);
+    assert_eq!(changes.clear, vec![2]);
+    assert_eq!(changes.raise.iter().map(|c| c.code).collect::<Vec<_>>(), vec![3]);
+
+    assert_eq!(reconcile(&open[..1], &active[..1]), Default::default());
+}
+
+#[test]
+fn settings_reject_duplicates_and_unknown_kinds() {
+    assert!(validate_alarm_settings(&AlarmSettings::default()).is_ok());
+
+    let dup = AlarmSettings { tags: vec![alarm_tag("code", vec![0]), alarm_tag("code", vec![0])] };
+    assert!(validate_alarm_settings(&dup).is_err());
+
+    let unknown = AlarmSettings { tags: vec![alarm_tag("level", vec![])] };
+    assert!(validate_alarm_settings(&unknown).is_err());
+}
+
+#[test]
+fn bit_tags_only_scan_the_width_of_their_type() {
+    // An Int16 of -1 read as i64 has all 64 bits set
+    let conditions = decode_conditions(&alarm_tag("bits", vec![]), -1, 16);
+    assert_eq!(conditions.len(), 16);
+    assert_eq!(conditions.last().unwrap().code, 15);
+
+    let mut tag = alarm_tag("bits", vec![]);
+    tag.meanings[0].value = 16;
+    assert!(validate_alarm_settings(&AlarmSettings { tags: vec![tag] }).is_err());
+}
+
+#[test]
+fn default_tags_are_read_through_the_tag_map() {
+    let defaults = AlarmSettings::default();
+    assert!(defaults.tags.iter().all(|t| t.signal.is_some() && t.node_id.is_empty()));
+
+    let unknown = AlarmTag { signal: Some("beltSpeed".into()), ..alarm_tag("code", vec![0]) };
+    assert!(validate_alarm_settings(&AlarmSettings { tags: vec![unknown] }).is_err());
+}
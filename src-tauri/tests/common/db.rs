@@ -0,0 +1,81 @@
+//! Connection to a migrated test database.
+
+use conveyoros_oas_lib::{
+    db::{
+        connection::{establish_connection, set_database_url},
+        garment_repo,
+        slot_repo::SlotRepo,
+        ticket_repo,
+    },
+    model::{Garment, GarmentState, NewGarment, NewSlot, NewTicket, Slot, SlotState, Ticket, TicketStatus},
+};
+use diesel::prelude::*;
+
+/// Connects to the database named by `DATABASE_URL`. Run the test body in
+/// `test_transaction` so nothing it writes is kept.
+pub fn test_connection() -> PgConnection {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must name a migrated test database");
+    set_database_url(&url);
+    establish_connection().expect("Failed to connect to the test database")
+}
+
+/// Inserts a ticket in `status` with `items` garments expected.
+pub fn insert_ticket(conn: &mut PgConnection, invoice: &str, items: i32, status: TicketStatus) -> Ticket {
+    let day = chrono::NaiveDate::from_ymd_opt(2026, 6, 1).unwrap().and_hms_opt(9, 0, 0).unwrap();
+    ticket_repo::create_ticket(
+        conn,
+        NewTicket {
+            full_invoice_number: invoice.to_string(),
+            display_invoice_number: invoice.to_string(),
+            number_of_items: items,
+            customer_identifier: "TEST".to_string(),
+            customer_first_name: "Test".to_string(),
+            customer_last_name: "Customer".to_string(),
+            customer_phone_number: "555-0100".to_string(),
+            invoice_dropoff_date: day,
+            invoice_pickup_date: day,
+            ticket_status: status.as_str().to_string(),
+        },
+    )
+    .expect("Failed to insert the ticket")
+}
+
+/// Inserts a garment of `invoice` hanging on `slot` (-1 for off the conveyor).
+pub fn insert_garment(conn: &mut PgConnection, invoice: &str, item: &str, slot: i32) -> Garment {
+    let day = chrono::NaiveDate::from_ymd_opt(2026, 6, 1).unwrap().and_hms_opt(9, 0, 0).unwrap();
+    let state = if slot > 0 { GarmentState::Processing } else { GarmentState::NotProcessed };
+    garment_repo::create_garment(
+        conn,
+        NewGarment {
+            full_invoice_number: invoice.to_string(),
+            display_invoice_number: invoice.to_string(),
+            item_id: item.to_string(),
+            item_description: "Shirt".to_string(),
+            invoice_dropoff_date: day,
+            invoice_pickup_date: day,
+            invoice_comments: String::new(),
+            slot_number: slot,
+            garment_state: state.as_str().to_string(),
+        },
+    )
+    .expect("Failed to insert the garment")
+}
+
+/// Inserts an empty slot for the test to use, so tests do not depend on the
+/// seeded slots. Use numbers above 9000 to stay clear of the seeded ones.
+pub fn insert_slot(conn: &mut PgConnection, slot_number: i32) -> Slot {
+    SlotRepo::insert(
+        conn,
+        &NewSlot {
+            slot_number,
+            slot_state: SlotState::Empty.as_str().to_string(),
+            frame_number: 900,
+            frame_position: slot_number,
+            state_reason: None,
+            blocked_by_layout: false,
+        },
+        "test setup",
+    )
+    .expect("Failed to insert the slot");
+    SlotRepo::get(conn, slot_number).unwrap().expect("slot inserted")
+}
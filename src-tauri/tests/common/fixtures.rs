@@ -0,0 +1,126 @@
+//! In-memory rows for tests of the pure planning and decoding functions.
+
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use chrono::{NaiveDateTime, Utc};
+use conveyoros_oas_lib::{
+    model::{Alarm, Garment, InventoryAuditScan, Slot, Ticket},
+    settings::appsettings::{AlarmMeaning, AlarmTag},
+};
+
+static NEXT_GARMENT_ID: AtomicI32 = AtomicI32::new(1);
+
+/// Slot `num` in `state`, on frame 1 and holding nothing until told otherwise.
+pub fn slot(num: i32, state: &str) -> SlotBuilder {
+    SlotBuilder(Slot {
+        slot_number: num,
+        slot_state: state.to_string(),
+        assigned_ticket: None,
+        item_id: None,
+        created_at: NaiveDateTime::default(),
+        updated_at: NaiveDateTime::default(),
+        frame_number: 1,
+        frame_position: num,
+        retired: false,
+        garment_count: 0,
+        reserved_at: None,
+        state_reason: None,
+        blocked_by_layout: false,
+    })
+}
+
+pub struct SlotBuilder(Slot);
+
+impl SlotBuilder {
+    pub fn ticket(mut self, ticket: &str) -> Self {
+        self.0.assigned_ticket = Some(ticket.to_string());
+        self
+    }
+
+    pub fn garments(mut self, count: i32) -> Self {
+        self.0.garment_count = count;
+        self
+    }
+
+    pub fn frame(mut self, frame_number: i32) -> Self {
+        self.0.frame_number = frame_number;
+        self
+    }
+
+    pub fn build(self) -> Slot {
+        self.0
+    }
+}
+
+/// Garment `item` of `ticket` hanging on `slot_number` (-1 when off the
+/// conveyor). Ids increase in the order garments are made.
+pub fn garment(item: &str, ticket: &str, slot_number: i32) -> Garment {
+    Garment {
+        id: NEXT_GARMENT_ID.fetch_add(1, Ordering::Relaxed),
+        full_invoice_number: ticket.to_string(),
+        display_invoice_number: ticket.to_string(),
+        item_id: item.to_string(),
+        item_description: String::new(),
+        invoice_dropoff_date: NaiveDateTime::default(),
+        invoice_pickup_date: NaiveDateTime::default(),
+        invoice_comments: String::new(),
+        slot_number,
+        garment_state: "Processing".to_string(),
+    }
+}
+
+pub fn ticket(invoice: &str, status: &str, processed: i32, items: i32) -> Ticket {
+    Ticket {
+        id: 1,
+        full_invoice_number: invoice.to_string(),
+        display_invoice_number: invoice.to_string(),
+        number_of_items: items,
+        customer_identifier: "C1".to_string(),
+        customer_first_name: String::new(),
+        customer_last_name: String::new(),
+        customer_phone_number: String::new(),
+        invoice_dropoff_date: NaiveDateTime::default(),
+        invoice_pickup_date: NaiveDateTime::default(),
+        created_at: NaiveDateTime::default(),
+        garments_processed: processed,
+        ticket_status: status.to_string(),
+    }
+}
+
+/// Audit scan of `item` found on `slot_number`.
+pub fn scan(item: &str, slot_number: i32) -> InventoryAuditScan {
+    InventoryAuditScan {
+        id: 0,
+        audit_id: 1,
+        slot_number,
+        item_id: item.to_string(),
+        scanned_at: NaiveDateTime::default(),
+    }
+}
+
+/// Int16 InverterStatus alarm tag of `kind` where code 2 means "Overcurrent".
+pub fn alarm_tag(kind: &str, normal_values: Vec<i64>) -> AlarmTag {
+    AlarmTag {
+        name: "InverterStatus".into(),
+        signal: None,
+        node_id: "ns=1;i=290".into(),
+        data_type: "Int16".into(),
+        kind: kind.into(),
+        normal_values,
+        meanings: vec![AlarmMeaning { value: 2, message: "Overcurrent".into() }],
+    }
+}
+
+/// Uncleared InverterStatus alarm for `code`.
+pub fn open_alarm(id: i32, code: i64) -> Alarm {
+    Alarm {
+        id,
+        source: "InverterStatus".into(),
+        code,
+        message: String::new(),
+        raised_at: Utc::now().naive_utc(),
+        cleared_at: None,
+        acknowledged_at: None,
+        acknowledged_by: None,
+    }
+}
@@ -0,0 +1,6 @@
+// Each test file uses only some of these helpers
+#![allow(dead_code)]
+
+pub mod db;
+pub mod fixtures;
+pub mod opc_server;
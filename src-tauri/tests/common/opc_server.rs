@@ -0,0 +1,162 @@
+//! In-process OPC UA server exposing the same nodes as the conveyor PLC,
+//! taken from the default tag map.
+
+use std::net::TcpListener;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use conveyoros_oas_lib::{
+    opc::tag_map::{self, OpcSignal},
+    settings::appsettings::OpcTagMap,
+};
+use open62541::{ua, ScalarValue, Server, ServerBuilder, VariableNode, VariantValue};
+
+// Namespace 0 ids from the OPC UA specification.
+const OBJECTS_FOLDER: u32 = 85;
+const ORGANIZES: u32 = 35;
+const BASE_DATA_VARIABLE_TYPE: u32 = 63;
+const BOOLEAN_TYPE: u32 = 1;
+const INT16_TYPE: u32 = 4;
+const INT32_TYPE: u32 = 6;
+
+pub struct TestPlc {
+    pub port: u16,
+    server: Server,
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl TestPlc {
+    /// Starts a server on a free local port.
+    pub fn start() -> Self {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|l| l.local_addr())
+            .expect("free port")
+            .port();
+        Self::start_on(port)
+    }
+
+    pub fn start_on(port: u16) -> Self {
+        let (server, runner) = ServerBuilder::minimal(port, None).build();
+        let map = OpcTagMap::default();
+
+        for signal in OpcSignal::ALL {
+            let tag = signal.tag(&map);
+            let (data_type, initial) = match tag.data_type.as_str() {
+                "Boolean" => (BOOLEAN_TYPE, ua::Variant::scalar(ua::Boolean::new(false))),
+                "Int16" => (INT16_TYPE, ua::Variant::scalar(ua::Int16::new(0))),
+                "Int32" => (INT32_TYPE, ua::Variant::scalar(ua::Int32::new(0))),
+                other => panic!("no test node type for {other}"),
+            };
+            let node_id = server
+                .add_variable_node(VariableNode {
+                    requested_new_node_id: Some(tag_map::node_id(tag).unwrap()),
+                    parent_node_id: ua::NodeId::ns0(OBJECTS_FOLDER),
+                    reference_type_id: ua::NodeId::ns0(ORGANIZES),
+                    browse_name: ua::QualifiedName::new(1, &tag.browse_name),
+                    type_definition: ua::NodeId::ns0(BASE_DATA_VARIABLE_TYPE),
+                    attributes: ua::VariableAttributes::default()
+                        .with_data_type(&ua::NodeId::ns0(data_type))
+                        .with_access_level(&ua::AccessLevelType::NONE.with_current_read(true).with_current_write(true)),
+                })
+                .unwrap_or_else(|e| panic!("add {}: {e}", tag.browse_name));
+            server.write_value(&node_id, &initial).unwrap();
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let runner_stop = stop.clone();
+        let runner = thread::spawn(move || {
+            runner.run_until_cancelled(|| runner_stop.load(Ordering::Relaxed)).unwrap();
+        });
+
+        Self { port, server, stop, threads: vec![runner] }
+    }
+
+    pub fn url(&self) -> String {
+        format!("opc.tcp://127.0.0.1:{}", self.port)
+    }
+
+    fn node(signal: OpcSignal) -> ua::NodeId {
+        tag_map::node_id(signal.tag(&OpcTagMap::default())).unwrap()
+    }
+
+    fn read(server: &Server, signal: OpcSignal) -> ScalarValue {
+        let value = server
+            .read_attribute(&Self::node(signal), ua::AttributeId::VALUE_T)
+            .into_value()
+            .unwrap_or_else(|| panic!("{} has no value", signal.as_str()));
+        match value.to_value() {
+            VariantValue::Scalar(v) => v,
+            other => panic!("{}: expected a scalar, got {other:?}", signal.as_str()),
+        }
+    }
+
+    fn write_int16(server: &Server, signal: OpcSignal, value: i16) {
+        server.write_value(&Self::node(signal), &ua::Variant::scalar(ua::Int16::new(value))).unwrap();
+    }
+
+    fn write_bool_to(server: &Server, signal: OpcSignal, value: bool) {
+        server.write_value(&Self::node(signal), &ua::Variant::scalar(ua::Boolean::new(value))).unwrap();
+    }
+
+    pub fn read_bool(&self, signal: OpcSignal) -> bool {
+        match Self::read(&self.server, signal) {
+            ScalarValue::Boolean(b) => b.value(),
+            other => panic!("{}: expected Boolean, got {other:?}", signal.as_str()),
+        }
+    }
+
+    pub fn read_integer(&self, signal: OpcSignal) -> i64 {
+        match Self::read(&self.server, signal) {
+            ScalarValue::Int16(v) => v.value().into(),
+            ScalarValue::Int32(v) => v.value().into(),
+            other => panic!("{}: expected an integer, got {other:?}", signal.as_str()),
+        }
+    }
+
+    pub fn write_bool(&self, signal: OpcSignal, value: bool) {
+        Self::write_bool_to(&self.server, signal, value);
+    }
+
+    /// Behaves like the PLC program: on a rising run request it latches the
+    /// requested slot into `TargetSlot`, runs for `travel`, then stops with
+    /// that slot at the load station.
+    pub fn emulate_conveyor(&mut self, travel: Duration) {
+        let server = self.server.clone();
+        let stop = self.stop.clone();
+        self.threads.push(thread::spawn(move || {
+            let mut requested = false;
+            while !stop.load(Ordering::Relaxed) {
+                let run_request = matches!(Self::read(&server, OpcSignal::RunRequest), ScalarValue::Boolean(b) if b.value());
+                if run_request && !requested {
+                    let target = match Self::read(&server, OpcSignal::TargetSlot) {
+                        ScalarValue::Int16(v) => v.value(),
+                        other => panic!("TargetSlot: expected Int16, got {other:?}"),
+                    };
+                    Self::write_int16(&server, OpcSignal::ConveyorTarget, target);
+                    Self::write_bool_to(&server, OpcSignal::ConveyorGo, true);
+                    Self::write_int16(&server, OpcSignal::ConveyorStatus, 1);
+                    thread::sleep(travel);
+                    Self::write_int16(&server, OpcSignal::ActualSlot, target);
+                    Self::write_bool_to(&server, OpcSignal::ConveyorGo, false);
+                    Self::write_int16(&server, OpcSignal::ConveyorStatus, 0);
+                }
+                requested = run_request;
+                thread::sleep(Duration::from_millis(10));
+            }
+        }));
+    }
+}
+
+impl Drop for TestPlc {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
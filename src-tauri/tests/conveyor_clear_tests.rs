@@ -0,0 +1,110 @@
+#[path = "common/mod.rs"]
+mod common;
+
+use common::{
+    db::{insert_garment, insert_ticket, test_connection},
+    fixtures::{garment, slot},
+};
+use conveyoros_oas_lib::{
+    conveyor_clear::{clear_conveyor, restore_snapshot, ClearScope},
+    db::{garment_repo, slot_repo::SlotRepo, ticket_repo},
+    model::{Garment, Slot, SlotState, TicketStatus},
+    slot_manager::SlotManager,
+};
+use diesel::prelude::*;
+
+fn conveyor() -> (Vec<Slot>, Vec<Garment>) {
+    let slots = vec![
+        slot(1, "occupied").ticket("T1").garments(1).build(),
+        slot(2, "blocked").build(),
+        slot(3, "occupied").frame(2).ticket("T2").garments(1).build(),
+        slot(4, "occupied").frame(2).ticket("T1").garments(1).build(),
+        slot(5, "empty").frame(2).build(),
+    ];
+    let garments = vec![garment("G1", "T1", 1), garment("G2", "T2", 3), garment("G3", "T1", 4)];
+    (slots, garments)
+}
+
+#[test]
+pub fn test_frame_scope_skips_blocked_and_empty_slots() {
+    let (slots, garments) = conveyor();
+
+    let (to_free, removed) = ClearScope::Frame { frame_number: 2 }.select(&slots, &garments);
+
+    let freed: Vec<i32> = to_free.iter().map(|s| s.slot_number).collect();
+    let items: Vec<&str> = removed.iter().map(|g| g.item_id.as_str()).collect();
+    assert_eq!(freed, vec![3, 4]);
+    assert_eq!(items, vec!["G2", "G3"]);
+
+    let (to_free, _) = ClearScope::SlotRange { start: 1, end: 2 }.select(&slots, &garments);
+    assert_eq!(to_free.iter().map(|s| s.slot_number).collect::<Vec<_>>(), vec![1]);
+}
+
+#[test]
+pub fn test_ticket_scope_only_touches_that_ticket() {
+    let (slots, garments) = conveyor();
+
+    let (to_free, removed) = ClearScope::Ticket { ticket: "T1".to_string() }.select(&slots, &garments);
+
+    assert_eq!(to_free.iter().map(|s| s.slot_number).collect::<Vec<_>>(), vec![1, 4]);
+    assert!(removed.iter().all(|g| g.full_invoice_number == "T1"));
+    assert_eq!(removed.len(), 2);
+}
+
+/// Loads two garments of `invoice` onto `slot_number`, then clears that ticket.
+fn clear_loaded_ticket(conn: &mut PgConnection, invoice: &str, slot_number: i32) -> i32 {
+    insert_ticket(conn, invoice, 3, TicketStatus::Processing);
+    ticket_repo::set_garments_processed(conn, invoice, 2).unwrap();
+    assert!(SlotRepo::try_reserve(conn, slot_number, Some(invoice), 10, "scanned").unwrap());
+    for n in 1..=2 {
+        let item = format!("{invoice}-{n}");
+        insert_garment(conn, invoice, &item, -1);
+        SlotManager::confirm_load(conn, &item, invoice, slot_number).unwrap();
+    }
+
+    let report = clear_conveyor(conn, &ClearScope::Ticket { ticket: invoice.to_string() }).unwrap();
+    assert_eq!(report.freed_slots, vec![slot_number]);
+    report.snapshot_id.expect("snapshot taken")
+}
+
+#[test]
+pub fn test_restore_puts_back_a_cleared_ticket_once() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        let snapshot = clear_loaded_ticket(conn, "CLEAR-1", 83);
+        assert_eq!(SlotRepo::get(conn, 83)?.unwrap().slot_state, SlotState::Empty.as_str());
+
+        restore_snapshot(conn, snapshot).unwrap();
+
+        let slot = SlotRepo::get(conn, 83)?.unwrap();
+        assert_eq!(slot.slot_state, SlotState::Occupied.as_str());
+        assert_eq!(slot.assigned_ticket.as_deref(), Some("CLEAR-1"));
+        assert_eq!(slot.garment_count, 2);
+        assert_eq!(garment_repo::get_garment(conn, "CLEAR-1-2").unwrap().slot_number, 83);
+        let ticket = ticket_repo::get_ticket_by_invoice_number(conn, "CLEAR-1").unwrap();
+        assert_eq!(ticket.garments_processed, 2);
+        assert_eq!(ticket.ticket_status, TicketStatus::Processing.as_str());
+
+        assert!(restore_snapshot(conn, snapshot).unwrap_err().contains("already been restored"));
+        Ok(())
+    });
+}
+
+#[test]
+pub fn test_restore_is_refused_once_a_slot_is_reused() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        let snapshot = clear_loaded_ticket(conn, "CLEAR-2", 82);
+        assert!(SlotRepo::try_reserve(conn, 82, Some("CLEAR-3"), 10, "scanned")?);
+
+        let err = restore_snapshot(conn, snapshot).unwrap_err();
+        assert!(err.contains("slot 82 is reserved again"), "{err}");
+
+        // Nothing was written, and the snapshot can still be restored later
+        assert_eq!(SlotRepo::get(conn, 82)?.unwrap().assigned_ticket.as_deref(), Some("CLEAR-3"));
+        assert_eq!(garment_repo::get_garment(conn, "CLEAR-2-1").unwrap().slot_number, -1);
+        SlotRepo::release_reservation(conn, 82, "walked away")?;
+        restore_snapshot(conn, snapshot).unwrap();
+        Ok(())
+    });
+}
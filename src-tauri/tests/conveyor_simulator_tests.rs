@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use conveyoros_oas_lib::{
+    controller::{simulator::SimulatedConveyor, ConveyorController},
+    model::RunRequestOutcome,
+    settings::appsettings::ControllerSettings,
+};
+
+fn fast_settings() -> ControllerSettings {
+    ControllerSettings {
+        kind: "simulator".into(),
+        travel_ms_per_slot: 5,
+        start_delay_ms: 0,
+        hanger_delay_ms: 20,
+        hanger_pulse_ms: 200,
+    }
+}
+
+#[tokio::test]
+async fn runs_forward_to_the_slot_and_pulses_the_hanger_sensor() {
+    let sim = SimulatedConveyor::new(fast_settings(), 2, 5);
+    let mut hanger = sim.watch_hanger_sensor().await.unwrap();
+
+    let result = sim.run_to_slot(4).await.unwrap();
+    assert_eq!(result.outcome, RunRequestOutcome::Success);
+    assert_eq!(sim.state().position, 4);
+    assert!(!sim.state().running);
+    assert!(!sim.hanger_sensor().await.unwrap(), "hanger only appears after the delay");
+
+    let sensed = tokio::time::timeout(Duration::from_secs(1), hanger.recv()).await.unwrap().unwrap();
+    assert!(sensed);
+    assert!(sim.hanger_sensor().await.unwrap());
+
+    // Going back to slot 2 means travelling all the way round
+    let mut status = sim.status_events();
+    sim.run_to_slot(2).await.unwrap();
+    assert_eq!(sim.state().position, 2);
+    let mut moves = 0;
+    while let Ok(s) = status.try_recv() {
+        if s.state == conveyoros_oas_lib::opc::telemetry::ConveyorState::Running {
+            moves += 1;
+        }
+    }
+    assert!(moves >= 8, "expected 8 slots of travel, saw {moves} running updates");
+    assert_eq!(sim.target_slot().await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn frame_config_limits_the_slots() {
+    let sim = SimulatedConveyor::new(fast_settings(), 2, 5);
+    assert!(sim.run_to_slot(11).await.is_err());
+    assert!(sim.run_to_slot(0).await.is_err());
+
+    sim.set_number_of_frames(3).await.unwrap();
+    assert_eq!(sim.state().total_slots(), 15);
+    sim.run_to_slot(11).await.unwrap();
+
+    sim.set_slots_per_frame(2).await.unwrap();
+    assert_eq!(sim.state().total_slots(), 6);
+    assert_eq!(sim.state().position, 6, "position clamped to the shorter conveyor");
+    assert!(sim.set_number_of_frames(0).await.is_err());
+}
@@ -0,0 +1,33 @@
+use conveyoros_oas_lib::{
+    opc::telemetry::{decode_state, ConveyorState},
+    settings::appsettings::ConveyorStatusCodes,
+};
+
+#[test]
+fn decodes_running_idle_and_unknown() {
+    let codes = ConveyorStatusCodes::default();
+    assert_eq!(decode_state(Some(true), Some(1), Some(0), &codes).0, ConveyorState::Running);
+    assert_eq!(decode_state(Some(false), Some(0), Some(0), &codes).0, ConveyorState::Idle);
+    assert_eq!(decode_state(Some(true), None, None, &codes).0, ConveyorState::Running);
+    assert_eq!(decode_state(None, None, None, &codes).0, ConveyorState::Unknown);
+}
+
+#[test]
+fn fault_codes_mark_the_conveyor_faulted() {
+    let codes = ConveyorStatusCodes::default();
+    let (state, fault) = decode_state(Some(true), Some(1), Some(7), &codes);
+    assert_eq!(state, ConveyorState::Faulted);
+    assert_eq!(fault.as_deref(), Some("Inverter fault code 7"));
+
+    let (state, fault) = decode_state(Some(false), Some(12), Some(0), &codes);
+    assert_eq!(state, ConveyorState::Faulted);
+    assert_eq!(fault.as_deref(), Some("Conveyor status code 12"));
+}
+
+#[test]
+fn status_codes_come_from_settings() {
+    let codes = ConveyorStatusCodes { stopped: 2, running: 3 };
+    assert_eq!(decode_state(Some(false), Some(3), Some(0), &codes).0, ConveyorState::Running);
+    assert_eq!(decode_state(Some(false), Some(2), Some(0), &codes).0, ConveyorState::Idle);
+    assert_eq!(decode_state(Some(false), Some(1), Some(0), &codes).0, ConveyorState::Faulted);
+}
@@ -0,0 +1,68 @@
+#[path = "common/mod.rs"]
+mod common;
+
+use common::{
+    db::{insert_garment, insert_ticket, test_connection},
+    fixtures::{garment, scan},
+};
+use conveyoros_oas_lib::{
+    db::{garment_repo, slot_repo::SlotRepo, state_error::StateError},
+    inventory_audit::{apply_audit, find_discrepancies, finish_audit, record_scan, start_audit, Discrepancy, DiscrepancyKind},
+    model::{AuditStatus, TicketStatus},
+    slot_manager::SlotManager,
+};
+use diesel::prelude::*;
+
+#[test]
+fn matching_scans_have_no_discrepancies() {
+    let garments = vec![garment("A", "T1", 1), garment("B", "T1", 2)];
+    let scans = vec![scan("B", 2), scan("A", 1)];
+    assert!(find_discrepancies(&garments, &scans).is_empty());
+}
+
+#[test]
+fn reports_missing_wrong_slot_and_unexpected() {
+    let garments = vec![garment("A", "T1", 3), garment("B", "T1", 1), garment("C", "T1", 5), garment("OFF", "T1", -1)];
+    let scans = vec![scan("B", 2), scan("C", 5), scan("OFF", 4), scan("NEW", 1)];
+
+    assert_eq!(
+        find_discrepancies(&garments, &scans),
+        vec![
+            Discrepancy { kind: DiscrepancyKind::WrongSlot, item_id: "B".into(), expected_slot: Some(1), found_slot: Some(2) },
+            Discrepancy { kind: DiscrepancyKind::Missing, item_id: "A".into(), expected_slot: Some(3), found_slot: None },
+            Discrepancy { kind: DiscrepancyKind::Unexpected, item_id: "NEW".into(), expected_slot: None, found_slot: Some(1) },
+            Discrepancy { kind: DiscrepancyKind::Unexpected, item_id: "OFF".into(), expected_slot: None, found_slot: Some(4) },
+        ]
+    );
+}
+
+#[test]
+fn applying_skips_blocked_slots_and_applies_only_once() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, StateError, _>(|conn| {
+        insert_ticket(conn, "AUD-1", 2, TicketStatus::Processing);
+        assert!(SlotRepo::try_reserve(conn, 81, Some("AUD-1"), 10, "scanned")?);
+        for item in ["AUD-1-1", "AUD-1-2"] {
+            insert_garment(conn, "AUD-1", item, -1);
+            SlotManager::confirm_load(conn, item, "AUD-1", 81)?;
+        }
+        assert!(SlotRepo::set_blocked(conn, 80, "broken hook", false)?);
+
+        let audit = start_audit(conn).unwrap();
+        record_scan(conn, audit.id, 80, "AUD-1-1").unwrap();
+        record_scan(conn, audit.id, 79, "AUD-1-2").unwrap();
+        finish_audit(conn, audit.id).unwrap();
+
+        let result = apply_audit(conn, audit.id).unwrap();
+
+        assert_eq!(result.corrected, vec!["AUD-1-2".to_string()]);
+        assert_eq!(result.skipped, vec!["AUD-1-1: slot 80 is blocked".to_string()]);
+        assert_eq!(garment_repo::get_garment(conn, "AUD-1-1").unwrap().slot_number, 81);
+        assert_eq!(garment_repo::get_garment(conn, "AUD-1-2").unwrap().slot_number, 79);
+        assert_eq!(SlotRepo::get(conn, 80)?.unwrap().garment_count, 0);
+
+        let err = apply_audit(conn, audit.id).unwrap_err();
+        assert!(err.contains(AuditStatus::Applied.as_str()), "{err}");
+        Ok(())
+    });
+}
@@ -0,0 +1,122 @@
+#[path = "common/mod.rs"]
+mod common;
+
+use std::time::Duration;
+
+use common::db::{insert_garment, insert_slot, insert_ticket, test_connection};
+use conveyoros_oas_lib::{
+    controller::simulator::SimulatedConveyor,
+    db::{garment_repo, slot_repo::SlotRepo, state_error::StateError},
+    model::{SlotState, TicketStatus},
+    opc::sensor::wait_for_hanger,
+    settings::appsettings::ControllerSettings,
+    slot_manager::SlotManager,
+};
+use diesel::prelude::*;
+
+const WINDOW: Duration = Duration::from_millis(300);
+
+fn idle_conveyor() -> SimulatedConveyor {
+    // hangerDelayMs 0 keeps the simulator from pulsing the sensor on its own
+    let settings = ControllerSettings { hanger_delay_ms: 0, ..ControllerSettings::default() };
+    SimulatedConveyor::new(settings, 2, 5)
+}
+
+fn set_hanger_after(sim: &SimulatedConveyor, delay_ms: u64, present: bool) {
+    let sim = sim.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        sim.set_hanger(present);
+    });
+}
+
+#[tokio::test]
+async fn hanger_arriving_in_the_window_confirms_the_load() {
+    let sim = idle_conveyor();
+    set_hanger_after(&sim, 50, true);
+
+    assert!(wait_for_hanger(&sim, WINDOW).await);
+}
+
+#[tokio::test]
+async fn hanger_already_in_front_of_the_sensor_does_not_count() {
+    let sim = idle_conveyor();
+    sim.set_hanger(true);
+
+    assert!(!wait_for_hanger(&sim, WINDOW).await);
+}
+
+#[tokio::test]
+async fn sensor_must_clear_before_a_new_hanger_counts() {
+    let sim = idle_conveyor();
+    sim.set_hanger(true);
+    set_hanger_after(&sim, 50, false);
+    set_hanger_after(&sim, 100, true);
+
+    assert!(wait_for_hanger(&sim, WINDOW).await);
+}
+
+#[test]
+pub fn test_failed_load_flags_an_empty_slot() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, StateError, _>(|conn| {
+        insert_slot(conn, 9_094);
+        insert_ticket(conn, "LOAD-1", 1, TicketStatus::Processing);
+        insert_garment(conn, "LOAD-1", "LOAD-1-1", -1);
+        assert!(SlotRepo::try_reserve(conn, 9_094, Some("LOAD-1"), 10, "test reservation")?);
+
+        assert!(SlotManager::fail_load(conn, "LOAD-1-1", 9_094)?);
+
+        let slot = SlotRepo::get(conn, 9_094)?.unwrap();
+        assert_eq!(slot.slot_state, SlotState::Error.as_str());
+        assert_eq!(slot.state_reason.as_deref(), Some("no hanger sensor confirmation"));
+        Ok(())
+    });
+}
+
+#[test]
+pub fn test_failed_load_only_rolls_back_that_garment() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, StateError, _>(|conn| {
+        insert_slot(conn, 9_095);
+        insert_ticket(conn, "LOAD-2", 2, TicketStatus::Processing);
+        insert_garment(conn, "LOAD-2", "LOAD-2-1", -1);
+        insert_garment(conn, "LOAD-2", "LOAD-2-2", -1);
+        assert!(SlotRepo::try_reserve(conn, 9_095, Some("LOAD-2"), 10, "test reservation")?);
+        SlotManager::confirm_load(conn, "LOAD-2-1", "LOAD-2", 9_095)?;
+        SlotManager::load_garment(conn, "LOAD-2-2", 9_095)?;
+
+        assert!(!SlotManager::fail_load(conn, "LOAD-2-2", 9_095)?);
+
+        let slot = SlotRepo::get(conn, 9_095)?.unwrap();
+        assert_eq!(slot.slot_state, SlotState::Occupied.as_str());
+        assert_eq!(slot.garment_count, 1);
+        assert_eq!(slot.item_id.as_deref(), Some("LOAD-2-1"));
+        assert_eq!(garment_repo::get_garment(conn, "LOAD-2-2").unwrap().slot_number, -1);
+        assert_eq!(garment_repo::get_garment(conn, "LOAD-2-1").unwrap().slot_number, 9_095);
+        Ok(())
+    });
+}
+
+#[test]
+pub fn test_confirmation_is_refused_once_the_slot_changed_hands() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, StateError, _>(|conn| {
+        insert_slot(conn, 9_096);
+        insert_ticket(conn, "LOAD-3", 1, TicketStatus::Processing);
+        insert_garment(conn, "LOAD-3", "LOAD-3-1", -1);
+        assert!(SlotRepo::try_reserve(conn, 9_096, Some("LOAD-4"), 10, "test reservation")?);
+
+        let err = SlotManager::confirm_load(conn, "LOAD-3-1", "LOAD-3", 9_096).unwrap_err();
+        assert!(matches!(err, StateError::Rejected(_)), "{err}");
+
+        SlotRepo::release_reservation(conn, 9_096, "walked away")?;
+        assert!(SlotManager::confirm_load(conn, "LOAD-3-1", "LOAD-3", 9_096).is_err());
+
+        let slot = SlotRepo::get(conn, 9_096)?.unwrap();
+        assert_eq!(slot.slot_state, SlotState::Empty.as_str());
+        assert_eq!(slot.garment_count, 0);
+        assert_eq!(garment_repo::get_garment(conn, "LOAD-3-1").unwrap().slot_number, -1);
+        Ok(())
+    });
+}
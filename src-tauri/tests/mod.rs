@@ -4,3 +4,25 @@ pub mod ticket_repo_tests;
 pub mod spot_file_tests;
 pub mod conveyor_output_tests;
 pub mod printer_tests;
+pub mod slot_topology_tests;
+pub mod slot_allocation_tests;
+pub mod slot_zoning_tests;
+pub mod slot_consistency_tests;
+pub mod state_transition_tests;
+pub mod conveyor_clear_tests;
+pub mod inventory_audit_tests;
+pub mod slot_consolidation_tests;
+pub mod opc_tag_map_tests;
+pub mod opc_health_tests;
+pub mod conveyor_telemetry_tests;
+pub mod run_request_tests;
+pub mod retrieval_queue_tests;
+pub mod opc_security_tests;
+pub mod conveyor_simulator_tests;
+pub mod opc_integration_tests;
+pub mod alarm_tests;
+pub mod motion_profile_tests;
+pub mod reservation_sweeper_tests;
+pub mod load_confirmation_tests;
+pub mod slot_history_tests;
+pub mod slot_block_tests;
@@ -0,0 +1,40 @@
+use conveyoros_oas_lib::{
+    model::NewMotionProfile,
+    opc::{
+        motion_profile::{check_can_apply, validate_motion_profile},
+        telemetry::{ConveyorState, ConveyorStatus},
+    },
+    settings::appsettings::OpcTagMap,
+};
+
+fn profile(name: &str, speed: i32, acceleration: i32) -> NewMotionProfile {
+    NewMotionProfile { name: name.into(), speed, acceleration }
+}
+
+#[test]
+fn profiles_need_a_name_and_positive_values() {
+    let map = OpcTagMap::default();
+    assert!(validate_motion_profile(&profile("normal", 1200, 300), &map).is_ok());
+    assert!(validate_motion_profile(&profile(" ", 1200, 300), &map).is_err());
+    assert!(validate_motion_profile(&profile("night", 0, 300), &map).is_err());
+    assert!(validate_motion_profile(&profile("night", 1200, -1), &map).is_err());
+}
+
+#[test]
+fn values_must_fit_the_mapped_tag_types() {
+    let map = OpcTagMap::default();
+    // Speed is an Int32 tag, acceleration an Int16 one
+    assert!(validate_motion_profile(&profile("rush", 100_000, 300), &map).is_ok());
+    assert!(validate_motion_profile(&profile("rush", 1200, 40_000), &map).is_err());
+}
+
+#[test]
+fn profiles_are_only_applied_to_a_stopped_online_conveyor() {
+    let status = |state, plc_online| ConveyorStatus { state, plc_online, ..Default::default() };
+    assert!(check_can_apply(&status(ConveyorState::Idle, true)).is_ok());
+    assert!(check_can_apply(&status(ConveyorState::Faulted, true)).is_ok());
+    assert!(check_can_apply(&status(ConveyorState::Running, true)).is_err());
+    assert!(check_can_apply(&status(ConveyorState::Unknown, true)).is_err());
+    // Last-known values are kept while offline, so an idle state is not enough
+    assert!(check_can_apply(&status(ConveyorState::Idle, false)).unwrap_err().contains("offline"));
+}
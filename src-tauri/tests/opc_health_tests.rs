@@ -0,0 +1,15 @@
+use std::time::Duration;
+
+use conveyoros_oas_lib::opc::opc_client::next_backoff;
+
+#[test]
+fn backoff_doubles_up_to_the_cap() {
+    let max = Duration::from_secs(60);
+    let mut wait = Duration::from_secs(1);
+    let mut waits = vec![];
+    for _ in 0..8 {
+        waits.push(wait.as_secs());
+        wait = next_backoff(wait, max);
+    }
+    assert_eq!(waits, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+}
@@ -0,0 +1,164 @@
+// Also built as a submodule of tests/mod.rs, so the path is spelled out
+#[path = "common/mod.rs"]
+mod common;
+
+use std::time::Duration;
+
+use common::opc_server::TestPlc;
+use conveyoros_oas_lib::{
+    model::RunRequestOutcome,
+    opc::{
+        opc_client::{ConnectionState, OpcClient, OpcConfig},
+        motion_profile, opc_commands, run_request,
+        tag_map::{self, OpcSignal},
+        telemetry::{self, ConveyorState},
+    },
+    settings::appsettings::{OpcTagMap, RunRequestSettings},
+};
+use open62541::{ua, ScalarValue, VariantValue};
+use tokio::sync::broadcast;
+
+const WAIT: Duration = Duration::from_secs(10);
+
+async fn connect(plc: &TestPlc) -> OpcClient {
+    let client = OpcClient::new(OpcConfig::new(&plc.url()));
+    client.connect().await.expect("connect to the test server");
+    client
+}
+
+async fn wait_until(what: &str, mut done: impl FnMut() -> bool) {
+    tokio::time::timeout(WAIT, async {
+        while !done() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("timed out waiting for {what}"));
+}
+
+/// Waits for the monitored value to become `expected`, skipping earlier values.
+async fn wait_for_bool(rx: &mut broadcast::Receiver<ua::Variant>, expected: bool) {
+    tokio::time::timeout(WAIT, async {
+        loop {
+            let value = rx.recv().await.expect("subscription open");
+            if let VariantValue::Scalar(ScalarValue::Boolean(b)) = value.to_value() {
+                if b.value() == expected {
+                    return;
+                }
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("no {expected} from the subscription"));
+}
+
+fn hanger_node() -> ua::NodeId {
+    tag_map::node_id(&OpcTagMap::default().hanger_sensor).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn frame_configuration_and_target_slot_reach_the_plc() {
+    let plc = TestPlc::start();
+    let client = connect(&plc).await;
+
+    opc_commands::set_number_of_frames(&client, 4).await.unwrap();
+    opc_commands::set_slots_per_frame(&client, 6).await.unwrap();
+    opc_commands::set_target_slot(&client, 17).await.unwrap();
+
+    assert_eq!(plc.read_integer(OpcSignal::NumberOfFrames), 4);
+    assert_eq!(plc.read_integer(OpcSignal::SlotsPerFrame), 6);
+    assert_eq!(plc.read_integer(OpcSignal::TargetSlot), 17);
+    assert_eq!(tag_map::read_integer(&client, OpcSignal::TargetSlot).await, Ok(17));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn hanger_sensor_is_read_and_monitored() {
+    let plc = TestPlc::start();
+    let client = connect(&plc).await;
+
+    assert_eq!(opc_commands::get_load_hanger_sensor(&client).await, Ok(false));
+
+    let mut rx = client.subscribe_value(hanger_node()).await.unwrap();
+    plc.write_bool(OpcSignal::HangerSensor, true);
+    wait_for_bool(&mut rx, true).await;
+    assert_eq!(opc_commands::get_load_hanger_sensor(&client).await, Ok(true));
+
+    let subs = client.subscriptions().await;
+    assert_eq!(subs.len(), 1);
+    assert!(subs[0].active);
+
+    assert!(client.unsubscribe(&hanger_node()).await);
+    assert!(client.subscriptions().await.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn slot_run_request_succeeds_when_the_plc_arrives() {
+    let mut plc = TestPlc::start();
+    plc.emulate_conveyor(Duration::from_millis(300));
+    let client = connect(&plc).await;
+
+    run_request::set_run_request_settings(&RunRequestSettings {
+        pulse_ms: 50,
+        start_timeout_ms: 2_000,
+        arrival_timeout_ms: 5_000,
+    });
+    telemetry::monitor_telemetry(client.clone(), |_| {}).await;
+    wait_until("telemetry", || telemetry::last_status().state == ConveyorState::Idle).await;
+
+    let result = opc_commands::slot_run_request(&client, 9).await.unwrap();
+    assert_eq!(result.outcome, RunRequestOutcome::Success, "{:?}", result.detail);
+    assert_eq!(result.target_slot, 9);
+    assert!(result.duration_ms >= 300);
+
+    assert!(!plc.read_bool(OpcSignal::RunRequest), "run request flag left on");
+    assert_eq!(plc.read_integer(OpcSignal::TargetSlot), 9);
+    assert_eq!(telemetry::last_status().target_slot, Some(9));
+    assert_eq!(telemetry::last_status().actual_slot, Some(9));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn motion_values_are_written_and_verified() {
+    let plc = TestPlc::start();
+    let client = connect(&plc).await;
+
+    motion_profile::write_motion(&client, 1_500, 250).await.unwrap();
+    assert_eq!(plc.read_integer(OpcSignal::Speed), 1_500);
+    assert_eq!(plc.read_integer(OpcSignal::Acceleration), 250);
+
+    // Acceleration does not fit the Int16 tag, so the speed is rolled back
+    let err = motion_profile::write_motion(&client, 900, 70_000).await.unwrap_err();
+    assert!(!err.restore_failed);
+    assert!(err.message.contains("restored"), "{}", err.message);
+    assert_eq!(plc.read_integer(OpcSignal::Speed), 1_500);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reconnects_and_resubscribes_after_a_server_restart() {
+    let plc = TestPlc::start();
+    let port = plc.port;
+
+    let mut cfg = OpcConfig::new(&plc.url());
+    cfg.reconnect_backoff = Duration::from_millis(100);
+    cfg.max_reconnect_backoff = Duration::from_millis(500);
+    cfg.heartbeat_interval = Duration::from_millis(100);
+    cfg.heartbeat_timeout = Duration::from_millis(500);
+    let client = OpcClient::new(cfg);
+    client.start_reconnect_loop();
+    wait_until("first connect", || client.health().state == ConnectionState::Connected).await;
+
+    let mut rx = client.subscribe_value(hanger_node()).await.unwrap();
+    wait_for_bool(&mut rx, false).await;
+
+    drop(plc);
+    wait_until("disconnect", || client.health().state == ConnectionState::Disconnected).await;
+    assert!(!client.is_connected());
+    assert!(opc_commands::get_load_hanger_sensor(&client).await.is_err());
+
+    let plc = TestPlc::start_on(port);
+    wait_until("reconnect", || client.health().state == ConnectionState::Connected).await;
+
+    // The same receiver keeps working on the new session
+    plc.write_bool(OpcSignal::HangerSensor, true);
+    wait_for_bool(&mut rx, true).await;
+    assert_eq!(opc_commands::get_load_hanger_sensor(&client).await, Ok(true));
+}
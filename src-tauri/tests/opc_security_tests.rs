@@ -0,0 +1,47 @@
+use conveyoros_oas_lib::{
+    opc::security::{policy_uri, validate_security, SecurityMode},
+    settings::appsettings::OpcSecuritySettings,
+};
+
+#[test]
+fn anonymous_without_security_is_the_default() {
+    assert_eq!(validate_security(&OpcSecuritySettings::default()), Ok(SecurityMode::None));
+    assert_eq!(policy_uri("Basic256Sha256"), "http://opcfoundation.org/UA/SecurityPolicy#Basic256Sha256");
+}
+
+#[test]
+fn sign_and_encrypt_needs_a_policy_and_certificate() {
+    let mut security = OpcSecuritySettings {
+        security_mode: "signAndEncrypt".into(),
+        username: "plant".into(),
+        password: "secret".into(),
+        ..OpcSecuritySettings::default()
+    };
+    assert!(validate_security(&security).is_err(), "policy None with signAndEncrypt");
+
+    security.security_policy = "Basic256Sha256".into();
+    assert!(validate_security(&security).is_err(), "missing certificate");
+
+    security.certificate_path = "/etc/conveyor/client.der".into();
+    security.private_key_path = "/etc/conveyor/client.key".into();
+    assert!(validate_security(&security).is_err(), "no trusted server certificate");
+
+    security.trusted_certificate_paths = vec!["/etc/conveyor/plc-ca.der".into()];
+    assert_eq!(validate_security(&security), Ok(SecurityMode::SignAndEncrypt));
+
+    security.security_policy = "Basic128Rsa15".into();
+    assert!(validate_security(&security).is_err(), "deprecated policy");
+}
+
+#[test]
+fn accepting_any_server_certificate_needs_no_trust_list() {
+    let security = OpcSecuritySettings {
+        security_policy: "Basic256Sha256".into(),
+        security_mode: "sign".into(),
+        certificate_path: "/etc/conveyor/client.der".into(),
+        private_key_path: "/etc/conveyor/client.key".into(),
+        accept_any_server_certificate: true,
+        ..OpcSecuritySettings::default()
+    };
+    assert_eq!(validate_security(&security), Ok(SecurityMode::Sign));
+}
@@ -0,0 +1,52 @@
+use conveyoros_oas_lib::{
+    opc::tag_map::{check_tag_type, encode_integer, import_tags, parse_uaexpert_export, OpcSignal},
+    settings::appsettings::{OpcTag, OpcTagMap},
+};
+
+const CONVEYOR_EXPORT: &str = include_str!("../../conveyor.csv");
+
+#[test]
+fn parses_repo_uaexpert_export() {
+    let tags = parse_uaexpert_export(CONVEYOR_EXPORT).unwrap();
+
+    let go = tags.iter().find(|t| t.browse_name == "ConveyorGo").unwrap();
+    assert_eq!(go.node_id, "ns=1;i=292");
+    assert_eq!(go.data_type, "Boolean");
+
+    // Quoted values with spaces do not shift the later columns
+    let position = tags.iter().find(|t| t.browse_name == "MyAIASlotPosition").unwrap();
+    assert_eq!(position.data_type, "DWORD");
+}
+
+#[test]
+fn import_matches_default_browse_names() {
+    let tags = parse_uaexpert_export(CONVEYOR_EXPORT).unwrap();
+    let report = import_tags(&OpcTagMap::default(), &tags).unwrap();
+
+    assert!(report.missing.is_empty(), "missing: {:?}", report.missing);
+    assert_eq!(report.tags.hanger_sensor.node_id, "ns=1;i=102");
+    assert_eq!(report.tags.run_request.node_id, "ns=1;i=83");
+    assert_eq!(report.tags, OpcTagMap::default());
+}
+
+#[test]
+fn import_follows_renamed_browse_name() {
+    let tags = parse_uaexpert_export(CONVEYOR_EXPORT).unwrap();
+    let mut map = OpcTagMap::default();
+    map.target_slot.browse_name = "TargetSlot".into();
+
+    let report = import_tags(&map, &tags).unwrap();
+    assert_eq!(report.tags.target_slot.node_id, "ns=1;i=291");
+}
+
+#[test]
+fn rejects_types_that_cannot_carry_the_signal() {
+    let float = OpcTag { browse_name: "X".into(), node_id: "ns=1;i=1".into(), data_type: "Float".into() };
+    assert!(check_tag_type(OpcSignal::TargetSlot, &float).is_err());
+    assert!(check_tag_type(OpcSignal::HangerSensor, &float).is_err());
+
+    let int16 = OpcTag { data_type: "Int16".into(), ..float };
+    assert!(check_tag_type(OpcSignal::TargetSlot, &int16).is_ok());
+    assert!(encode_integer(&int16, 40_000).is_err());
+    assert!(encode_integer(&int16, 120).is_ok());
+}
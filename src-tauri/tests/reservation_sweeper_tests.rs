@@ -0,0 +1,68 @@
+#[path = "common/mod.rs"]
+mod common;
+
+use common::db::{insert_slot, test_connection};
+use conveyoros_oas_lib::{
+    db::{slot_history_repo::SlotHistoryRepo, slot_repo::SlotRepo},
+    model::SlotState,
+    reservation_sweeper::release_expired_reservations,
+};
+use diesel::prelude::*;
+
+const TTL_SECS: i32 = 600;
+
+fn reserve_minutes_ago(conn: &mut PgConnection, num: i32, ticket: &str, minutes: i32) {
+    use conveyoros_oas_lib::schema::slots::dsl::*;
+    use diesel::dsl::{now, IntervalDsl};
+
+    assert!(SlotRepo::try_reserve(conn, num, Some(ticket), 10, "test reservation").unwrap());
+    diesel::update(slots.filter(slot_number.eq(num)))
+        .set(reserved_at.eq((now - minutes.minutes()).nullable()))
+        .execute(conn)
+        .unwrap();
+}
+
+#[test]
+pub fn test_expired_reservation_is_released_and_logged() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        insert_slot(conn, 9_091);
+        reserve_minutes_ago(conn, 9_091, "SWEEP-1", 30);
+
+        let released = release_expired_reservations(conn, TTL_SECS)?;
+
+        let entry = released.iter().find(|r| r.slot_number == 9_091).expect("slot 9091 released");
+        assert_eq!(entry.ticket.as_deref(), Some("SWEEP-1"));
+        let slot = SlotRepo::get(conn, 9_091)?.unwrap();
+        assert_eq!(slot.slot_state, SlotState::Empty.as_str());
+        assert_eq!(slot.assigned_ticket, None);
+        assert_eq!(slot.reserved_at, None);
+
+        let last = SlotHistoryRepo::for_slot(conn, 9_091)?.into_iter().max_by_key(|h| h.id).unwrap();
+        assert_eq!(last.reason, "reservation expired");
+        assert_eq!(last.ticket.as_deref(), Some("SWEEP-1"));
+        Ok(())
+    });
+}
+
+#[test]
+pub fn test_loaded_or_recent_reservations_are_kept() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        for num in [9_092, 9_093] {
+            insert_slot(conn, num);
+        }
+        reserve_minutes_ago(conn, 9_092, "SWEEP-2", 30);
+        SlotRepo::record_load(conn, 9_092, "SWEEP-2-1")?;
+        reserve_minutes_ago(conn, 9_093, "SWEEP-3", 1);
+
+        let released = release_expired_reservations(conn, TTL_SECS)?;
+
+        assert!(!released.iter().any(|r| r.slot_number == 9_092 || r.slot_number == 9_093));
+        for num in [9_092, 9_093] {
+            let slot = SlotRepo::get(conn, num)?.unwrap();
+            assert_eq!(slot.slot_state, SlotState::Reserved.as_str());
+        }
+        Ok(())
+    });
+}
@@ -0,0 +1,49 @@
+use conveyoros_oas_lib::retrieval_queue::{forward_distance, RetrievalQueue, RetrievalState};
+
+fn pending_slots(q: &RetrievalQueue) -> Vec<i32> {
+    q.pending.iter().map(|i| i.slot_number).collect()
+}
+
+#[test]
+fn optimize_orders_one_forward_pass() {
+    assert_eq!(forward_distance(90, 5, 100), 15);
+    assert_eq!(forward_distance(5, 5, 100), 0);
+
+    let mut q = RetrievalQueue::new();
+    q.enqueue("A", &[10, 95]);
+    q.enqueue("B", &[3, 50]);
+    q.optimize(90, 100);
+    assert_eq!(pending_slots(&q), vec![95, 3, 10, 50]);
+}
+
+#[test]
+fn duplicates_cancel_and_reprioritize() {
+    let mut q = RetrievalQueue::new();
+    let ids = q.enqueue("A", &[10, 20, 30]);
+    assert!(q.enqueue("A", &[20]).is_empty());
+
+    assert!(q.move_to(ids[2], 0));
+    assert_eq!(pending_slots(&q), vec![30, 10, 20]);
+
+    assert!(q.cancel(ids[0]));
+    assert!(!q.cancel(ids[0]));
+    assert_eq!(pending_slots(&q), vec![30, 20]);
+    assert_eq!(q.finished[0].state, RetrievalState::Cancelled);
+}
+
+#[test]
+fn failed_run_pauses_the_queue() {
+    let mut q = RetrievalQueue::new();
+    q.enqueue("A", &[10, 20]);
+
+    let first = q.start_next().unwrap();
+    assert_eq!(first.slot_number, 10);
+    assert!(q.start_next().is_none(), "one run at a time");
+
+    q.finish_current(RetrievalState::Failed, Some("timeout".into()), None);
+    assert!(q.paused);
+    assert!(q.start_next().is_none());
+
+    q.paused = false;
+    assert_eq!(q.start_next().unwrap().slot_number, 20);
+}
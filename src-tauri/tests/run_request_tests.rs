@@ -0,0 +1,60 @@
+use conveyoros_oas_lib::{
+    model::RunRequestOutcome,
+    opc::{
+        run_request::{evaluate_progress, RunProgress},
+        telemetry::{ConveyorState, ConveyorStatus},
+    },
+};
+
+fn status(state: ConveyorState, actual_slot: Option<i64>) -> ConveyorStatus {
+    ConveyorStatus { state, actual_slot, ..ConveyorStatus::default() }
+}
+
+#[test]
+fn succeeds_once_the_conveyor_has_moved_and_stopped() {
+    let idle = status(ConveyorState::Idle, Some(12));
+    assert_eq!(evaluate_progress(&idle, 12, false, false), RunProgress::Waiting);
+    assert_eq!(evaluate_progress(&status(ConveyorState::Running, Some(12)), 12, false, false), RunProgress::Moving);
+    assert_eq!(evaluate_progress(&idle, 12, true, false), RunProgress::Finished(RunRequestOutcome::Success, None));
+}
+
+#[test]
+fn faults_and_wrong_stops_fail_the_request() {
+    let mut faulted = status(ConveyorState::Faulted, None);
+    faulted.fault = Some("Inverter fault code 7".into());
+    assert_eq!(
+        evaluate_progress(&faulted, 12, true, false),
+        RunProgress::Finished(RunRequestOutcome::Fault, Some("Inverter fault code 7".into()))
+    );
+
+    let wrong = evaluate_progress(&status(ConveyorState::Idle, Some(30)), 12, true, false);
+    assert!(matches!(wrong, RunProgress::Finished(RunRequestOutcome::Fault, Some(_))));
+}
+
+#[test]
+fn arrival_is_judged_by_the_actual_slot_not_the_commanded_one() {
+    // Stopped part-way although the PLC was sent to slot 12
+    let short = ConveyorStatus { target_slot: Some(12), ..status(ConveyorState::Idle, Some(9)) };
+    assert_eq!(
+        evaluate_progress(&short, 12, true, false),
+        RunProgress::Finished(RunRequestOutcome::Fault, Some("conveyor stopped at slot 9, expected 12".into()))
+    );
+
+    // An ignored request still targeting 12 from before is not "already there"
+    let ignored = ConveyorStatus { target_slot: Some(12), ..status(ConveyorState::Idle, Some(4)) };
+    assert_eq!(evaluate_progress(&ignored, 12, false, true), RunProgress::Waiting);
+
+    let unknown = evaluate_progress(&status(ConveyorState::Idle, None), 12, true, false);
+    assert!(matches!(unknown, RunProgress::Finished(RunRequestOutcome::Fault, Some(_))));
+}
+
+#[test]
+fn idle_on_the_requested_slot_counts_as_arrived_after_the_start_timeout() {
+    let there = status(ConveyorState::Idle, Some(12));
+    assert_eq!(evaluate_progress(&there, 12, false, false), RunProgress::Waiting);
+    assert_eq!(
+        evaluate_progress(&there, 12, false, true),
+        RunProgress::Finished(RunRequestOutcome::Success, Some("conveyor was already at the slot".into()))
+    );
+    assert_eq!(evaluate_progress(&status(ConveyorState::Idle, Some(30)), 12, false, true), RunProgress::Waiting);
+}
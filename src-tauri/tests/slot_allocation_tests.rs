@@ -0,0 +1,80 @@
+use conveyoros_oas_lib::{
+    settings::appsettings::SlotAllocationSettings,
+    slot_allocation::{
+        pick_nearby, strategy_from_settings, AllocationContext, LinearStrategy, RoundRobinStrategy,
+        SlotAllocationStrategy, SpreadStrategy,
+    },
+};
+
+// Ten-slot conveyor with slots 1, 2 and 9 in use.
+fn fixture(last_used_slot: i32) -> AllocationContext {
+    AllocationContext::new(vec![3, 4, 5, 6, 7, 8, 10], vec![1, 2, 9], last_used_slot)
+}
+
+#[test]
+pub fn test_linear_takes_lowest_empty() {
+    assert_eq!(LinearStrategy.pick(&fixture(0)), Some(3));
+}
+
+#[test]
+pub fn test_linear_full_conveyor() {
+    let ctx = AllocationContext::new(vec![], vec![1, 2, 3], 0);
+    assert_eq!(LinearStrategy.pick(&ctx), None);
+}
+
+#[test]
+pub fn test_round_robin_continues_after_last_used() {
+    assert_eq!(RoundRobinStrategy.pick(&fixture(5)), Some(6));
+    assert_eq!(RoundRobinStrategy.pick(&fixture(8)), Some(10));
+}
+
+#[test]
+pub fn test_round_robin_wraps_around() {
+    assert_eq!(RoundRobinStrategy.pick(&fixture(10)), Some(3));
+}
+
+#[test]
+pub fn test_spread_keeps_distance_when_sparse() {
+    let ctx = AllocationContext::new((2..=20).collect(), vec![1], 0);
+    assert_eq!(SpreadStrategy::default().pick(&ctx), Some(6));
+}
+
+#[test]
+pub fn test_spread_falls_back_when_no_gap() {
+    // 30% occupied but nothing is 5 slots from everything in use.
+    assert_eq!(SpreadStrategy::default().pick(&fixture(0)), Some(3));
+}
+
+#[test]
+pub fn test_spread_goes_linear_above_threshold() {
+    let ctx = AllocationContext::new(vec![2, 9, 10], vec![1, 3, 4, 5, 6, 7, 8], 0);
+    let strategy = SpreadStrategy { min_distance: 2, threshold_pct: 50.0 };
+    assert_eq!(strategy.pick(&ctx), Some(2));
+}
+
+#[test]
+pub fn test_strategy_from_settings() {
+    let mut settings = SlotAllocationSettings::default();
+    assert_eq!(strategy_from_settings(&settings).name(), "spread");
+
+    settings.strategy = "roundRobin".to_string();
+    assert_eq!(strategy_from_settings(&settings).name(), "roundRobin");
+
+    settings.strategy = "linear".to_string();
+    assert_eq!(strategy_from_settings(&settings).name(), "linear");
+
+    settings.strategy = "unknown".to_string();
+    assert_eq!(strategy_from_settings(&settings).name(), "spread");
+}
+
+#[test]
+pub fn test_pick_nearby_prefers_adjacent_slot() {
+    // Ticket already fills slot 9; 8 and 10 are both contiguous, lower wins.
+    assert_eq!(pick_nearby(&fixture(0), &[9]), Some(8));
+}
+
+#[test]
+pub fn test_pick_nearby_uses_closest_anchor() {
+    let ctx = AllocationContext::new(vec![1, 7, 19], vec![4, 18], 0);
+    assert_eq!(pick_nearby(&ctx, &[4, 18]), Some(19));
+}
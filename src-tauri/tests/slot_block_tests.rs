@@ -0,0 +1,67 @@
+#[path = "common/mod.rs"]
+mod common;
+
+use common::db::{insert_slot, test_connection};
+use conveyoros_oas_lib::{
+    db::{slot_repo::SlotRepo, state_error::StateError},
+    model::SlotState,
+    slot_manager::SlotManager,
+};
+use diesel::prelude::*;
+
+#[test]
+pub fn test_block_and_unblock_an_empty_slot() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        insert_slot(conn, 9_088);
+        SlotManager::block_slot(conn, 9_088, "broken hook").unwrap();
+        let slot = SlotRepo::get(conn, 9_088)?.unwrap();
+        assert_eq!(slot.slot_state, SlotState::Blocked.as_str());
+        assert_eq!(slot.state_reason.as_deref(), Some("broken hook"));
+        assert!(!slot.blocked_by_layout);
+        assert!(SlotRepo::list_out_of_service(conn)?.iter().any(|s| s.slot_number == 9_088));
+
+        assert!(SlotManager::block_slot(conn, 9_088, "again").is_err());
+
+        SlotManager::unblock_slot(conn, 9_088, "hook repaired").unwrap();
+        let slot = SlotRepo::get(conn, 9_088)?.unwrap();
+        assert_eq!(slot.slot_state, SlotState::Empty.as_str());
+        assert_eq!(slot.state_reason, None);
+        Ok(())
+    });
+}
+
+#[test]
+pub fn test_error_slot_holding_garments_cannot_be_blocked() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, StateError, _>(|conn| {
+        insert_slot(conn, 9_087);
+        assert!(SlotRepo::try_reserve(conn, 9_087, Some("BLOCK-1"), 10, "scanned")?);
+        SlotRepo::record_load(conn, 9_087, "BLOCK-1-1")?;
+        SlotRepo::set_error(conn, 9_087, "jammed")?;
+
+        let err = SlotManager::block_slot(conn, 9_087, "broken hook").unwrap_err();
+        assert!(err.contains("BLOCK-1"), "{err}");
+        assert!(!SlotRepo::set_blocked(conn, 9_087, "broken hook", false)?);
+
+        let slot = SlotRepo::get(conn, 9_087)?.unwrap();
+        assert_eq!(slot.slot_state, SlotState::Error.as_str());
+        assert_eq!(slot.garment_count, 1);
+        Ok(())
+    });
+}
+
+#[test]
+pub fn test_only_admin_blocks_can_be_lifted() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, StateError, _>(|conn| {
+        insert_slot(conn, 9_086);
+        assert!(SlotManager::unblock_slot(conn, 9_086, "not blocked").is_err());
+
+        assert!(SlotRepo::set_blocked(conn, 9_086, "position disabled", true)?);
+        assert!(SlotManager::unblock_slot(conn, 9_086, "hook repaired").is_err());
+        assert!(matches!(SlotRepo::free_slot(conn, 9_086, "freed"), Err(StateError::Rejected(_))));
+        assert_eq!(SlotRepo::get(conn, 9_086)?.unwrap().slot_state, SlotState::Blocked.as_str());
+        Ok(())
+    });
+}
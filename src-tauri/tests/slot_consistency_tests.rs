@@ -0,0 +1,98 @@
+#[path = "common/mod.rs"]
+mod common;
+
+use common::{
+    db::{insert_slot, test_connection},
+    fixtures::{garment, slot, ticket},
+};
+use conveyoros_oas_lib::{
+    db::{slot_repo::SlotRepo, state_error::StateError},
+    model::SlotState,
+    slot_consistency::{find_issues, run_consistency_check, IssueKind, RepairAction},
+};
+use diesel::prelude::*;
+
+#[test]
+pub fn test_consistent_conveyor_has_no_issues() {
+    let slots = vec![slot(1, "occupied").ticket("T1").garments(2).build(), slot(2, "empty").build()];
+    let garments = vec![garment("G1", "T1", 1), garment("G2", "T1", 1)];
+    let tickets = vec![ticket("T1", "Processing", 2, 3)];
+
+    assert!(find_issues(&slots, &garments, &tickets).is_empty());
+}
+
+#[test]
+pub fn test_garment_on_empty_slot_occupies_it() {
+    let slots = vec![slot(1, "empty").build()];
+    let garments = vec![garment("G1", "T1", 1)];
+    let tickets = vec![ticket("T1", "Processing", 1, 2)];
+
+    let issues = find_issues(&slots, &garments, &tickets);
+    let kinds: Vec<_> = issues.iter().map(|i| i.kind).collect();
+
+    assert_eq!(kinds, vec![IssueKind::GarmentOnUnoccupiedSlot, IssueKind::GarmentCountMismatch]);
+    assert_eq!(issues[0].repair, RepairAction::OccupySlot { slot_number: 1, ticket: "T1".to_string() });
+}
+
+#[test]
+pub fn test_completed_ticket_garments_are_taken_off() {
+    // Completing a ticket frees its slot; garments left pointing at it are stale
+    let slots = vec![slot(1, "empty").build()];
+    let garments = vec![garment("G1", "T1", 1)];
+    let tickets = vec![ticket("T1", "Complete", 1, 1)];
+
+    let issues = find_issues(&slots, &garments, &tickets);
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].kind, IssueKind::GarmentOfFinishedTicket);
+    assert_eq!(issues[0].repair, RepairAction::TakeGarmentOff { item_id: "G1".to_string() });
+}
+
+#[test]
+pub fn test_processing_ticket_without_slot_is_reset() {
+    let slots = vec![slot(1, "empty").build()];
+    let tickets = vec![ticket("T1", "Processing", 2, 3)];
+
+    let issues = find_issues(&slots, &[], &tickets);
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].kind, IssueKind::ProcessingTicketOffConveyor);
+    assert_eq!(issues[0].repair, RepairAction::ResetTicket { ticket: "T1".to_string() });
+}
+
+#[test]
+pub fn test_foreign_garment_flags_slot_error() {
+    let slots = vec![slot(1, "occupied").ticket("T1").garments(2).build()];
+    let garments = vec![garment("G1", "T1", 1), garment("G2", "T2", 1)];
+    let tickets = vec![ticket("T1", "Processing", 1, 2), ticket("T2", "Processing", 1, 2)];
+
+    let issues = find_issues(&slots, &garments, &tickets);
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].kind, IssueKind::GarmentTicketMismatch);
+    assert_eq!(issues[0].repair, RepairAction::FlagSlotError { slot_number: 1 });
+}
+
+#[test]
+pub fn test_stale_ticket_on_blocked_slot_keeps_the_block() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, StateError, _>(|conn| {
+        insert_slot(conn, 9_084);
+        assert!(SlotRepo::set_blocked(conn, 9_084, "position disabled", true)?);
+        SlotRepo::restore(conn, 9_084, SlotState::Blocked, Some("GONE-1"), None, 0, None, "test setup")?;
+
+        let report = run_consistency_check(conn, true)?;
+
+        let issue = report.issues.iter().find(|i| i.slot_number == Some(9_084)).expect("slot 9084 reported");
+        assert_eq!(issue.kind, IssueKind::SlotTicketMissing);
+        assert_eq!(issue.repair, RepairAction::FreeSlot { slot_number: 9_084 });
+
+        let slot = SlotRepo::get(conn, 9_084)?.unwrap();
+        assert_eq!(slot.slot_state, SlotState::Blocked.as_str());
+        assert!(slot.blocked_by_layout);
+        assert_eq!(slot.state_reason.as_deref(), Some("position disabled"));
+        assert_eq!(slot.assigned_ticket, None);
+        assert_eq!(slot.garment_count, 0);
+        Ok(())
+    });
+}
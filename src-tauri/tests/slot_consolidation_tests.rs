@@ -0,0 +1,55 @@
+#[path = "common/mod.rs"]
+mod common;
+
+use common::fixtures::slot;
+use conveyoros_oas_lib::{
+    model::Slot,
+    slot_consolidation::{plan_consolidation, SlotMove},
+};
+
+/// Builds slots 1.. from a pattern: '.' empty, 'B' blocked, 'R' reserved,
+/// any other letter an occupied slot holding that ticket.
+fn conveyor(pattern: &str) -> Vec<Slot> {
+    pattern
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let num = i as i32 + 1;
+            let ticket = c.to_string();
+            match c {
+                '.' => slot(num, "empty").build(),
+                'B' => slot(num, "blocked").build(),
+                'R' => slot(num, "reserved").ticket("R").build(),
+                _ => slot(num, "occupied").ticket(&ticket).garments(1).build(),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn existing_run_needs_no_moves() {
+    let plan = plan_consolidation(&conveyor("a...b"), 3).unwrap();
+    assert_eq!((plan.run_start, plan.run_end), (2, 4));
+    assert!(plan.moves.is_empty());
+}
+
+#[test]
+fn picks_lowest_window_with_fewest_moves() {
+    let plan = plan_consolidation(&conveyor("a..b.c"), 3).unwrap();
+    assert_eq!((plan.run_start, plan.run_end), (1, 3));
+    assert_eq!(plan.moves, vec![SlotMove { from_slot: 1, to_slot: 5, ticket: "a".into(), garment_count: 1 }]);
+}
+
+#[test]
+fn moves_next_to_the_tickets_other_slots() {
+    let plan = plan_consolidation(&conveyor("..a.a.B.."), 3).unwrap();
+    assert_eq!((plan.run_start, plan.run_end), (1, 3));
+    assert_eq!(plan.moves.len(), 1);
+    assert_eq!(plan.moves[0].to_slot, 4);
+}
+
+#[test]
+fn blocked_and_reserved_slots_break_runs() {
+    assert!(plan_consolidation(&conveyor(".B.R."), 2).is_none());
+    assert!(plan_consolidation(&conveyor("ab"), 2).is_none());
+}
@@ -0,0 +1,67 @@
+#[path = "common/mod.rs"]
+mod common;
+
+use common::db::{insert_slot, test_connection};
+use conveyoros_oas_lib::{
+    db::{slot_history_repo::SlotHistoryRepo, slot_repo::SlotRepo, state_error::StateError},
+    model::{SlotHistory, SlotState},
+};
+use diesel::prelude::*;
+
+fn transitions(rows: &[SlotHistory]) -> Vec<(Option<&str>, &str, &str)> {
+    rows.iter()
+        .map(|h| (h.from_state.as_deref(), h.to_state.as_str(), h.reason.as_str()))
+        .collect()
+}
+
+#[test]
+pub fn test_every_change_is_recorded_for_slot_and_ticket() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, StateError, _>(|conn| {
+        insert_slot(conn, 9_090);
+        let earlier = SlotHistoryRepo::for_slot(conn, 9_090)?.len();
+
+        assert!(SlotRepo::try_reserve(conn, 9_090, Some("HIST-1"), 10, "scanned")?);
+        SlotRepo::record_load(conn, 9_090, "HIST-1-1")?;
+        SlotRepo::set_occupied(conn, 9_090, Some("HIST-1"), "hanger sensor confirmed load")?;
+        SlotRepo::free_slot(conn, 9_090, "ticket completed")?;
+
+        let timeline = SlotHistoryRepo::for_slot(conn, 9_090)?.split_off(earlier);
+        assert_eq!(
+            transitions(&timeline),
+            vec![
+                (Some("empty"), "reserved", "scanned"),
+                (Some("reserved"), "reserved", "garment loaded"),
+                (Some("reserved"), "occupied", "hanger sensor confirmed load"),
+                (Some("occupied"), "empty", "ticket completed"),
+            ]
+        );
+        assert!(timeline.iter().all(|h| h.ticket.as_deref() == Some("HIST-1")));
+        assert_eq!(timeline[1].item_id.as_deref(), Some("HIST-1-1"));
+        // A freed slot still shows which ticket and item left it
+        assert_eq!(timeline[3].item_id.as_deref(), Some("HIST-1-1"));
+
+        let for_ticket = SlotHistoryRepo::for_ticket(conn, "HIST-1")?;
+        assert_eq!(transitions(&for_ticket), transitions(&timeline));
+        Ok(())
+    });
+}
+
+#[test]
+pub fn test_rejected_or_empty_changes_are_not_recorded() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        insert_slot(conn, 9_089);
+        assert!(SlotRepo::try_reserve(conn, 9_089, Some("HIST-2"), 10, "scanned")?);
+        let earlier = SlotHistoryRepo::for_slot(conn, 9_089)?.len();
+
+        // Reserved slots cannot be blocked, and there is nothing to unload
+        assert!(SlotRepo::set_blocked(conn, 9_089, "broken hook", false).is_err());
+        SlotRepo::record_unload(conn, 9_089, "HIST-2-1", None)?;
+
+        assert_eq!(SlotHistoryRepo::for_slot(conn, 9_089)?.len(), earlier);
+        assert_eq!(SlotRepo::get(conn, 9_089)?.unwrap().slot_state, SlotState::Reserved.as_str());
+        assert!(SlotHistoryRepo::for_ticket(conn, "HIST-UNKNOWN")?.is_empty());
+        Ok(())
+    });
+}
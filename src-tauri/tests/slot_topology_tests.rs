@@ -0,0 +1,58 @@
+#[path = "common/mod.rs"]
+mod common;
+
+use common::db::test_connection;
+use conveyoros_oas_lib::{
+    db::{slot_repo::SlotRepo, state_error::StateError},
+    model::{NewSlot, SlotState},
+    settings::appsettings::FrameConfig,
+    slot_topology::{expected_slots, sync_slots_with_frames},
+};
+use diesel::prelude::*;
+
+#[test]
+pub fn test_expected_slots_numbers_across_frames() {
+    let frames = vec![
+        FrameConfig { latches: 3, slots: vec![true, true, true] },
+        FrameConfig { latches: 2, slots: vec![true, false] },
+    ];
+
+    let layout = expected_slots(&frames);
+
+    assert_eq!(layout.len(), 5);
+    assert_eq!(layout[3].slot_number, 4);
+    assert_eq!(layout[3].frame_number, 2);
+    assert_eq!(layout[3].frame_position, 1);
+    assert!(layout[3].enabled);
+    assert!(!layout[4].enabled);
+}
+
+#[test]
+pub fn test_expected_slots_empty_config() {
+    assert!(expected_slots(&[]).is_empty());
+}
+
+#[test]
+pub fn test_removed_positions_wait_for_a_confirmed_sync() {
+    let mut conn = test_connection();
+    conn.test_transaction::<_, StateError, _>(|conn| {
+        SlotRepo::insert(conn, &NewSlot {
+            slot_number: 9_001,
+            slot_state: SlotState::Empty.as_str().to_string(),
+            frame_number: 900,
+            frame_position: 1,
+            state_reason: None,
+            blocked_by_layout: false,
+        }, "test")?;
+        let frames = vec![FrameConfig { latches: 2, slots: vec![true, true] }];
+
+        let report = sync_slots_with_frames(conn, &frames, false)?;
+        assert!(report.awaiting_removal.contains(&9_001));
+        assert!(SlotRepo::get(conn, 9_001)?.is_some());
+
+        let report = sync_slots_with_frames(conn, &frames, true)?;
+        assert!(report.retired.contains(&9_001));
+        assert!(SlotRepo::get(conn, 9_001)?.is_none());
+        Ok(())
+    });
+}
@@ -0,0 +1,76 @@
+use chrono::NaiveDate;
+use conveyoros_oas_lib::{
+    settings::appsettings::{PickupZone, PickupZoningSettings},
+    slot_zoning::{zone_contains, zones_in_fallback_order, PickupBucket},
+};
+
+fn zone(bucket: &str, start: i32, end: i32) -> PickupZone {
+    PickupZone { bucket: bucket.to_string(), slot_start: Some(start), slot_end: Some(end), frames: vec![] }
+}
+
+fn zoning() -> PickupZoningSettings {
+    PickupZoningSettings {
+        enabled: true,
+        zones: vec![
+            zone("today", 1, 10),
+            zone("tomorrow", 11, 20),
+            zone("thisWeek", 21, 40),
+            zone("later", 41, 100),
+        ],
+    }
+}
+
+#[test]
+pub fn test_pickup_buckets() {
+    let today = NaiveDate::from_ymd_opt(2026, 6, 1).unwrap();
+    let at = |d: u32| NaiveDate::from_ymd_opt(2026, 6, d).unwrap().and_hms_opt(9, 0, 0).unwrap();
+
+    assert_eq!(PickupBucket::for_pickup(at(1), today), PickupBucket::Today);
+    assert_eq!(PickupBucket::for_pickup(at(2), today), PickupBucket::Tomorrow);
+    assert_eq!(PickupBucket::for_pickup(at(7), today), PickupBucket::ThisWeek);
+    assert_eq!(PickupBucket::for_pickup(at(8), today), PickupBucket::Later);
+
+    let overdue = NaiveDate::from_ymd_opt(2026, 5, 28).unwrap().and_hms_opt(9, 0, 0).unwrap();
+    assert_eq!(PickupBucket::for_pickup(overdue, today), PickupBucket::Today);
+}
+
+#[test]
+pub fn test_this_week_ends_six_days_out() {
+    let today = NaiveDate::from_ymd_opt(2026, 6, 1).unwrap();
+    let days_out = |n: u64| (today + chrono::Days::new(n)).and_hms_opt(18, 0, 0).unwrap();
+
+    assert_eq!(PickupBucket::for_pickup(days_out(2), today), PickupBucket::ThisWeek);
+    assert_eq!(PickupBucket::for_pickup(days_out(6), today), PickupBucket::ThisWeek);
+    assert_eq!(PickupBucket::for_pickup(days_out(7), today), PickupBucket::Later);
+}
+
+#[test]
+pub fn test_zone_contains_range_and_frames() {
+    let range = zone("today", 1, 10);
+    assert!(zone_contains(&range, 10, 2));
+    assert!(!zone_contains(&range, 11, 3));
+
+    let frames = PickupZone { bucket: "later".to_string(), slot_start: None, slot_end: None, frames: vec![3, 4] };
+    assert!(zone_contains(&frames, 11, 3));
+    assert!(!zone_contains(&frames, 1, 1));
+}
+
+#[test]
+pub fn test_fallback_order_prefers_neighbours() {
+    let settings = zoning();
+    let order: Vec<&str> = zones_in_fallback_order(&settings, PickupBucket::Tomorrow)
+        .iter()
+        .map(|z| z.bucket.as_str())
+        .collect();
+
+    assert_eq!(order, vec!["tomorrow", "thisWeek", "today", "later"]);
+}
+
+#[test]
+pub fn test_fallback_skips_unconfigured_buckets() {
+    let mut settings = zoning();
+    settings.zones.retain(|z| z.bucket != "today");
+
+    let first = zones_in_fallback_order(&settings, PickupBucket::Today)[0];
+    assert_eq!(first.bucket, "tomorrow");
+}
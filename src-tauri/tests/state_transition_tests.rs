@@ -0,0 +1,33 @@
+use conveyoros_oas_lib::model::{GarmentState, SlotState, TicketStatus};
+
+#[test]
+pub fn test_finished_ticket_needs_reopen() {
+    assert!(TicketStatus::Complete.transition(TicketStatus::Processing).is_err());
+    assert!(TicketStatus::Processed.transition(TicketStatus::NotProcessed).is_err());
+    assert!(TicketStatus::Complete.transition(TicketStatus::Complete).is_ok());
+    assert!(TicketStatus::NotProcessed.transition(TicketStatus::Complete).is_ok());
+    assert!(TicketStatus::Processing.transition(TicketStatus::NotProcessed).is_ok());
+}
+
+#[test]
+pub fn test_complete_ticket_can_be_marked_processed() {
+    assert!(TicketStatus::Complete.transition(TicketStatus::Processed).is_ok());
+    assert!(TicketStatus::Processed.transition(TicketStatus::Complete).is_err());
+}
+
+#[test]
+pub fn test_slot_in_use_cannot_be_blocked() {
+    assert!(SlotState::Occupied.transition(SlotState::Blocked).is_err());
+    assert!(SlotState::Reserved.transition(SlotState::Blocked).is_err());
+    assert!(SlotState::Blocked.transition(SlotState::Reserved).is_err());
+    assert!(SlotState::Blocked.transition(SlotState::Empty).is_err());
+    assert!(SlotState::Empty.transition(SlotState::Blocked).is_ok());
+    assert!(SlotState::Error.transition(SlotState::Occupied).is_ok());
+}
+
+#[test]
+pub fn test_processed_garment_is_final() {
+    assert!(GarmentState::Processed.transition(GarmentState::Processing).is_err());
+    assert!(GarmentState::NotProcessed.transition(GarmentState::Processing).is_ok());
+    assert!(GarmentState::Processing.transition(GarmentState::Processed).is_ok());
+}
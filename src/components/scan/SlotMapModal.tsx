@@ -23,7 +23,8 @@ export default function SlotMapModal({ open, onClose, slotStats, slotMapData, on
             <h3 className="text-xl font-black text-slate-800 uppercase tracking-tight">Slot Map</h3>
             <p className="text-sm text-slate-400 font-semibold mt-0.5">
               {slotMapData.length} of {slotStats.total_slots} slots occupied &mdash;{" "}
-              {Math.round(slotStats.capacity_percentage)}% full
+              {Math.round(slotStats.occupancy_percentage)}% full &mdash;{" "}
+              {slotStats.garment_count} of {slotStats.garment_capacity} garments
             </p>
           </div>
           <button onClick={onClose} className="text-slate-400 hover:text-red-500 text-2xl font-bold leading-none">
@@ -4,6 +4,7 @@ import {
   addConveyorActivityUnloadTauri,
   clearConveyorTauri,
   completeTicketTauri,
+  confirmGarmentLoadTauri,
   getCustomerFromTicket,
   getOccupiedSlotsTauri,
   getSlotManagerStatsTauri,
@@ -11,7 +12,7 @@ import {
   handleScanTauri,
   isLastGarmentTauri,
   isTicketCompleteTauri,
-  loadSensorHanger,
+  onReservationsExpired,
   removeGarmentFromSlotTauri,
   ticketExists,
   updateGarmentSlotTauri,
@@ -61,7 +62,7 @@ export function useScanHandler({ sessionId }: { sessionId?: number | null }) {
   const nextResolveRef = useRef<(() => void) | null>(null);
   const ticketAckResolveRef = useRef<(() => void) | null>(null);
 
-  const conveyorCapacity = slotStats ? Math.round(slotStats.capacity_percentage) : "—";
+  const conveyorCapacity = slotStats ? Math.round(slotStats.occupancy_percentage) : "—";
 
   const waitForNext = () =>
     new Promise<void>((resolve) => {
@@ -103,6 +104,16 @@ export function useScanHandler({ sessionId }: { sessionId?: number | null }) {
     setSlotMapData(occupied);
   };
 
+  useEffect(() => {
+    // Stale reservations are released in the background; keep the slot view current
+    const unlistenPromise = onReservationsExpired(() => {
+      refreshSlotMap().catch(console.error);
+    });
+    return () => {
+      unlistenPromise.then((fn) => fn());
+    };
+  }, []);
+
   const openSlotMap = async () => {
     const occupied = await getOccupiedSlotsTauri();
     setSlotMapData(occupied);
@@ -257,7 +268,8 @@ export function useScanHandler({ sessionId }: { sessionId?: number | null }) {
       setLastScan(code);
 
       if (isLast) {
-        const slotNum = await completeTicketTauri(code);
+        const completion = await completeTicketTauri(code);
+        const slotNum = completion.slot_number;
         setState("ticketcomplete");
         let completedTicketNum: string | null = null;
         let garmentCount = 0;
@@ -306,7 +318,11 @@ export function useScanHandler({ sessionId }: { sessionId?: number | null }) {
         }
 
         try {
-          if (slotNum !== null) await slotRunRequest(slotNum);
+          // Bring every slot the ticket hung on to the operator, last-loaded first
+          const slotsToVisit = [slotNum, ...completion.freed_slots.filter((s) => s !== slotNum)];
+          for (const slot of slotsToVisit) {
+            await slotRunRequest(slot);
+          }
           await UnloadItem(code);
           if (slotNum !== null && completedTicketNum) {
             await removeGarmentFromSlotTauri(completedTicketNum, slotNum);
@@ -321,7 +337,11 @@ export function useScanHandler({ sessionId }: { sessionId?: number | null }) {
 
       let slotNum: number | null;
       try {
-        slotNum = await handleScanTauri(code);
+        const assignment = await handleScanTauri(code);
+        slotNum = assignment.slot_number;
+        if (assignment.newly_reserved) {
+          console.info(`Reserved slot ${slotNum} for ${code}`);
+        }
       } catch (err) {
         console.error("handleScanTauri failed:", err);
         setState("error");
@@ -342,19 +362,27 @@ export function useScanHandler({ sessionId }: { sessionId?: number | null }) {
 
         try {
           const ticket = await getTicketFromGarment(code);
+          setTicketMeta(ticket ?? null);
+
+          await slotRunRequest(slotNum);
+
+          // The slot only becomes occupied once the hanger sensor sees the garment
+          const confirmed = await confirmGarmentLoadTauri(code, slotNum);
+          await refreshSlotStats();
+
           if (ticket) {
-            try { await updateGarmentSlotTauri(code, slotNum); } catch (err) { console.error("updateGarmentSlotTauri failed:", err); }
-            setTicketMeta(ticket);
             const rows = await listGarmentsForTicket(ticket.full_invoice_number);
             setGarments(rows);
           } else {
-            setTicketMeta(null);
             setGarments([]);
           }
 
-          await slotRunRequest(slotNum);
-          const sensorTriggered = await loadSensorHanger();
-          if (sensorTriggered) setState("garmentonconveyor");
+          if (!confirmed) {
+            console.error(`No hanger confirmation for ${code} on slot ${slotNum}`);
+            setState("error");
+            return;
+          }
+          setState("garmentonconveyor");
 
           try { await LoadItem(code); } catch (err) { console.error("LoadItem failed:", err); }
 
@@ -362,6 +390,7 @@ export function useScanHandler({ sessionId }: { sessionId?: number | null }) {
 
         } catch (err) {
           console.error("Hardware operation failed:", err);
+          setState("error");
         }
       } else {
         setState("error");
@@ -22,9 +22,11 @@ export type TicketRow = {
   invoice_pickup_date: string;
   created_at: string;
   garments_processed: number;
-  ticket_status: string;
+  ticket_status: TicketStatus;
 };
 
+export type TicketStatus = "Not Processed" | "Processing" | "Complete" | "Processed";
+
 export type GarmentState = "Not Processed" | "Processing" | "Processed";
 
 export type GarmentRow = {
@@ -52,3 +54,8 @@ export async function listGarmentsForTicket(full_invoice_number: string): Promis
   console.log("Listing garments for ticket:", full_invoice_number);
   return invoke<GarmentRow[]>("data_list_garments_for_ticket", { fullInvoiceNumber: full_invoice_number });
 }
+
+/** Admin only: lets a completed ticket be scanned onto the conveyor again. */
+export async function reopenTicket(full_invoice_number: string): Promise<TicketRow> {
+  return invoke<TicketRow>("reopen_ticket_tauri", { ticket: full_invoice_number });
+}
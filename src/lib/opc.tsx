@@ -12,12 +12,304 @@ export async function setTargetSlot(value: number): Promise<void> {
     return invoke("set_target_slot_tauri", { value });
 }
 
-export async function slotRunRequest(targetSlot: number): Promise<void> {
+export interface RunRequestResult {
+    target_slot: number;
+    outcome: "success" | "timeout" | "fault";
+    detail: string | null;
+    duration_ms: number;
+}
+
+/** Resolves once the conveyor has reached the slot; rejects if it timed out, faulted or could not be asked. */
+export async function slotRunRequest(targetSlot: number): Promise<RunRequestResult> {
     console.log(`Requesting slot run for target slot: ${targetSlot}`);
-    await invoke("slot_run_request_tauri", { targetSlot: Math.trunc(targetSlot) });
+    return invoke<RunRequestResult>("slot_run_request_tauri", { targetSlot: Math.trunc(targetSlot) });
 }
 
 export async function opcConnected(): Promise<boolean> {
     return invoke<boolean>("check_opc_connection_tauri");
 }
 
+export interface OpcTag {
+    browseName: string;
+    nodeId: string;
+    dataType: string;
+}
+
+export interface OpcTagMap {
+    jogForward: OpcTag;
+    runRequest: OpcTag;
+    targetSlot: OpcTag;
+    hangerSensor: OpcTag;
+    numberOfFrames: OpcTag;
+    slotsPerFrame: OpcTag;
+    conveyorGo: OpcTag;
+    conveyorStatus: OpcTag;
+    inverterStatus: OpcTag;
+    conveyorTarget: OpcTag;
+    offsetInches: OpcTag;
+    actualSlot: OpcTag;
+    speed: OpcTag;
+    acceleration: OpcTag;
+}
+
+export interface TagImportReport {
+    tags: OpcTagMap;
+    matched: string[];
+    missing: string[];
+}
+
+export interface TagCheck {
+    signal: string;
+    node_id: string;
+    data_type: string;
+    server_type: string | null;
+    error: string | null;
+}
+
+export async function getOpcTagMap(): Promise<OpcTagMap> {
+    return invoke<OpcTagMap>("get_opc_tag_map_tauri");
+}
+
+export async function saveOpcTagMap(tags: OpcTagMap): Promise<void> {
+    await invoke("save_opc_tag_map_tauri", { tags });
+}
+
+export async function importOpcTagMap(path: string): Promise<TagImportReport> {
+    return invoke<TagImportReport>("import_opc_tag_map_tauri", { path });
+}
+
+export async function verifyOpcTagMap(): Promise<TagCheck[]> {
+    return invoke<TagCheck[]>("verify_opc_tag_map_tauri");
+}
+
+export const OPC_CONNECTION_EVENT = "opc_connection";
+
+export interface OpcHealth {
+    state: "connecting" | "connected" | "disconnected";
+    endpoint_url: string;
+    connected_since: string | null;
+    last_heartbeat_at: string | null;
+    latency_ms: number | null;
+    last_error: string | null;
+    last_error_at: string | null;
+    reconnect_attempts: number;
+    next_retry_ms: number | null;
+}
+
+export async function getOpcHealth(): Promise<OpcHealth> {
+    return invoke<OpcHealth>("get_opc_health_tauri");
+}
+
+export interface OpcSubscriptionStatus {
+    node_id: string;
+    sampling_interval_ms: number;
+    publishing_interval_ms: number;
+    active: boolean;
+    receivers: number;
+    last_value_at: string | null;
+    last_error: string | null;
+}
+
+export async function listOpcSubscriptions(): Promise<OpcSubscriptionStatus[]> {
+    return invoke<OpcSubscriptionStatus[]>("list_opc_subscriptions_tauri");
+}
+
+export async function unsubscribeOpcNode(nodeId: string): Promise<boolean> {
+    return invoke<boolean>("unsubscribe_opc_node_tauri", { nodeId });
+}
+
+export const CONVEYOR_STATUS_EVENT = "conveyor_status";
+
+export interface ConveyorStatus {
+    state: "running" | "idle" | "faulted" | "unknown";
+    fault: string | null;
+    conveyor_go: boolean | null;
+    conveyor_status: number | null;
+    inverter_status: number | null;
+    target_slot: number | null;
+    offset_inches: number | null;
+    actual_slot: number | null;
+    plc_online: boolean;
+    updated_at: string | null;
+}
+
+export async function getConveyorStatus(): Promise<ConveyorStatus> {
+    return invoke<ConveyorStatus>("get_conveyor_status_tauri");
+}
+
+export interface RunRequestSettings {
+    pulseMs: number;
+    startTimeoutMs: number;
+    arrivalTimeoutMs: number;
+}
+
+export interface RunRequestLogEntry {
+    id: number;
+    target_slot: number;
+    outcome: "success" | "timeout" | "fault" | "error";
+    detail: string | null;
+    duration_ms: number;
+    requested_by: number | null;
+    requested_at: string;
+}
+
+export async function getRunRequestSettings(): Promise<RunRequestSettings> {
+    return invoke<RunRequestSettings>("get_run_request_settings_tauri");
+}
+
+export async function saveRunRequestSettings(runRequest: RunRequestSettings): Promise<void> {
+    await invoke("save_run_request_settings_tauri", { runRequest });
+}
+
+export async function listRunRequests(): Promise<RunRequestLogEntry[]> {
+    return invoke<RunRequestLogEntry[]>("list_run_requests_tauri");
+}
+
+export interface OpcSecuritySettings {
+    securityPolicy: "None" | "Basic256Sha256" | "Aes128_Sha256_RsaOaep" | "Aes256_Sha256_RsaPss";
+    securityMode: "none" | "sign" | "signAndEncrypt";
+    username: string;
+    /** Always empty when read; leave empty on save to keep the stored password. */
+    password: string;
+    certificatePath: string;
+    privateKeyPath: string;
+    /** Empty uses the library default; must match the client certificate. */
+    applicationUri: string;
+    trustedCertificatePaths: string[];
+    issuerCertificatePaths: string[];
+    acceptAnyServerCertificate: boolean;
+}
+
+export async function getOpcSecurity(): Promise<OpcSecuritySettings> {
+    return invoke<OpcSecuritySettings>("get_opc_security_tauri");
+}
+
+/** Admin only; applied on the next start of the app. */
+export async function saveOpcSecurity(security: OpcSecuritySettings): Promise<void> {
+    await invoke("save_opc_security_tauri", { security });
+}
+
+export interface ControllerSettings {
+    kind: "opc" | "simulator";
+    travelMsPerSlot: number;
+    startDelayMs: number;
+    /** 0 keeps the simulated hanger sensor off. */
+    hangerDelayMs: number;
+    hangerPulseMs: number;
+}
+
+export async function getControllerSettings(): Promise<ControllerSettings> {
+    return invoke<ControllerSettings>("get_controller_settings_tauri");
+}
+
+/** Admin only; applied on the next start of the app. */
+export async function saveControllerSettings(controller: ControllerSettings): Promise<void> {
+    await invoke("save_controller_settings_tauri", { controller });
+}
+
+/** The controller in use now, which may differ from saved settings until a restart. */
+export async function getActiveController(): Promise<"opc" | "simulator"> {
+    return invoke<"opc" | "simulator">("get_active_controller_tauri");
+}
+
+export const ALARM_EVENT = "alarm";
+
+export interface Alarm {
+    id: number;
+    source: string;
+    code: number;
+    message: string;
+    raised_at: string;
+    cleared_at: string | null;
+    acknowledged_at: string | null;
+    acknowledged_by: number | null;
+}
+
+export interface AlarmMeaning {
+    value: number;
+    message: string;
+}
+
+export interface AlarmTag {
+    name: string;
+    /** Tag map signal to read, such as "inverterStatus"; overrides nodeId and dataType. */
+    signal?: string | null;
+    nodeId: string;
+    dataType: string;
+    kind: "code" | "bits";
+    /** Values of a code tag that are not faults. */
+    normalValues: number[];
+    meanings: AlarmMeaning[];
+}
+
+export interface AlarmSettings {
+    tags: AlarmTag[];
+}
+
+/** Uncleared or unacknowledged alarms, newest first. */
+export async function listActiveAlarms(): Promise<Alarm[]> {
+    return invoke<Alarm[]>("list_active_alarms_tauri");
+}
+
+export async function acknowledgeAlarm(alarmId: number): Promise<Alarm> {
+    return invoke<Alarm>("acknowledge_alarm_tauri", { alarmId });
+}
+
+export async function listAlarmHistory(): Promise<Alarm[]> {
+    return invoke<Alarm[]>("list_alarm_history_tauri");
+}
+
+export async function getAlarmSettings(): Promise<AlarmSettings> {
+    return invoke<AlarmSettings>("get_alarm_settings_tauri");
+}
+
+/** Admin only; applied on the next start of the app. */
+export async function saveAlarmSettings(alarms: AlarmSettings): Promise<void> {
+    await invoke("save_alarm_settings_tauri", { alarms });
+}
+
+export interface MotionProfile {
+    id: number;
+    name: string;
+    speed: number;
+    acceleration: number;
+    created_at: string;
+    updated_at: string;
+}
+
+export interface MotionProfileApplication {
+    id: number;
+    profile_id: number | null;
+    profile_name: string;
+    speed: number;
+    acceleration: number;
+    applied_by: number | null;
+    applied_at: string;
+}
+
+export async function listMotionProfiles(): Promise<MotionProfile[]> {
+    return invoke<MotionProfile[]>("list_motion_profiles_tauri");
+}
+
+/** Admin only; saving under an existing name updates that profile. */
+export async function saveMotionProfile(name: string, speed: number, acceleration: number): Promise<MotionProfile> {
+    return invoke<MotionProfile>("save_motion_profile_tauri", { name, speed, acceleration });
+}
+
+/** Admin only. */
+export async function deleteMotionProfile(profileId: number): Promise<void> {
+    await invoke("delete_motion_profile_tauri", { profileId });
+}
+
+/** Admin only. Writes the profile to the PLC and verifies it; nothing changes if it fails. */
+export async function applyMotionProfile(profileId: number): Promise<MotionProfileApplication> {
+    return invoke<MotionProfileApplication>("apply_motion_profile_tauri", { profileId });
+}
+
+export async function getActiveMotionProfile(): Promise<MotionProfileApplication | null> {
+    return invoke<MotionProfileApplication | null>("get_active_motion_profile_tauri");
+}
+
+export async function listMotionProfileHistory(): Promise<MotionProfileApplication[]> {
+    return invoke<MotionProfileApplication[]>("list_motion_profile_history_tauri");
+}
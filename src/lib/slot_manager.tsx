@@ -2,7 +2,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { customer } from "../types/customer";
 import type { TicketRow } from "./data";
-import type { Slot, SlotManagerStats } from "../types/slotstats";
+import type { AuditApplyReport, AuditReport, ClearReport, ClearScope, ConsistencyReport, ConsolidationRun, ConveyorSnapshot, RetrievalQueue, InventoryAudit, InventoryAuditScan, Slot, SlotHistory, SlotManagerStats } from "../types/slotstats";
 
 
 export async function ticketExists(ticket: string): Promise<boolean> {
@@ -26,8 +26,14 @@ export async function getNumItemsOnTicket(ticket: string): Promise<number> {
     return invoke<number>("get_num_items_on_ticket", { ticket });
 }
 
-export async function handleScanTauri(scan_code: string): Promise<number | null> {
-  return invoke<number | null>("handle_scan_tauri", { scanCode: scan_code });
+export type SlotAssignment = {
+  slot_number: number;
+  /** True when a slot was reserved for this garment rather than reusing one of the ticket's slots. */
+  newly_reserved: boolean;
+};
+
+export async function handleScanTauri(scan_code: string): Promise<SlotAssignment> {
+  return invoke<SlotAssignment>("handle_scan_tauri", { scanCode: scan_code });
 }
 
 export async function loadSensorHanger(timeoutMs = 10_000): Promise<boolean> {
@@ -51,6 +57,11 @@ export async function loadSensorHanger(timeoutMs = 10_000): Promise<boolean> {
     });
 }
 
+/** Resolves true once the hanger sensor confirms the load; false means the slot was flagged as an error. */
+export async function confirmGarmentLoadTauri(barcode: string, slot_number: number): Promise<boolean> {
+    return invoke<boolean>("confirm_garment_load_tauri", { barcode, slotNumber: slot_number });
+}
+
 export async function isLastGarmentTauri(ticket: string): Promise<boolean> {
     return invoke<boolean>("is_last_garment", { ticket });
 }
@@ -63,8 +74,13 @@ export async function getSlotNumberFromBarcodeTauri(ticket: string): Promise<num
     return invoke<number | null>("get_slot_number_from_barcode_tauri", { barcode: ticket });
 }
 
-export async function completeTicketTauri(barcode: string): Promise<number | null> {
-    return invoke<number | null>("complete_ticket_tauri", { barcode });
+export type TicketCompletion = {
+    slot_number: number;
+    freed_slots: number[];
+};
+
+export async function completeTicketTauri(barcode: string): Promise<TicketCompletion> {
+    return invoke<TicketCompletion>("complete_ticket_tauri", { barcode });
 }
 
 export async function garmentTicketOnConveyorTauri(ticket: string): Promise<number | string> {
@@ -80,19 +96,117 @@ type RawSlotManagerStats = {
     occupied_slots: number;
     total_slots: number;
     occupancy_percentage: number;
+    garment_count: number;
+    garment_capacity: number;
+    item_fill_percentage: number;
 };
 
 export async function getSlotManagerStatsTauri(): Promise<SlotManagerStats> {
     const raw = await invoke<RawSlotManagerStats>("get_slot_manager_stats");
     return {
         total_slots: raw.total_slots,
-        slots_used: raw.occupied_slots,
-        capacity_percentage: raw.occupancy_percentage,
+        occupied_slots: raw.occupied_slots,
+        occupancy_percentage: raw.occupancy_percentage,
+        garment_count: raw.garment_count,
+        garment_capacity: raw.garment_capacity,
+        item_fill_percentage: raw.item_fill_percentage,
     };
 }
 
-export async function clearConveyorTauri(): Promise<void> {
-    await invoke<void>("clear_conveyor_tauri");
+export type ZoneFill = {
+    bucket: string;
+    total_slots: number;
+    occupied_slots: number;
+    occupancy_percentage: number;
+};
+
+export async function getPickupZoneReportTauri(): Promise<ZoneFill[]> {
+    return invoke<ZoneFill[]>("get_pickup_zone_report_tauri");
+}
+
+export async function clearConveyorTauri(): Promise<ClearReport> {
+    return invoke<ClearReport>("clear_conveyor_tauri");
+}
+
+export async function clearConveyorScopeTauri(scope: ClearScope): Promise<ClearReport> {
+    return invoke<ClearReport>("clear_conveyor_scope_tauri", { scope });
+}
+
+export async function listConveyorSnapshotsTauri(): Promise<ConveyorSnapshot[]> {
+    return invoke<ConveyorSnapshot[]>("list_conveyor_snapshots_tauri");
+}
+
+export async function restoreConveyorSnapshotTauri(snapshot_id: number): Promise<void> {
+    await invoke<void>("restore_conveyor_snapshot_tauri", { snapshotId: snapshot_id });
+}
+
+export async function startInventoryAuditTauri(): Promise<InventoryAudit> {
+    return invoke<InventoryAudit>("start_inventory_audit_tauri");
+}
+
+export async function recordAuditScanTauri(audit_id: number, slot_number: number, item_id: string): Promise<InventoryAuditScan> {
+    return invoke<InventoryAuditScan>("record_audit_scan_tauri", { auditId: audit_id, slotNumber: slot_number, itemId: item_id });
+}
+
+export async function getInventoryAuditReportTauri(audit_id: number): Promise<AuditReport> {
+    return invoke<AuditReport>("get_inventory_audit_report_tauri", { auditId: audit_id });
+}
+
+export async function finishInventoryAuditTauri(audit_id: number): Promise<AuditReport> {
+    return invoke<AuditReport>("finish_inventory_audit_tauri", { auditId: audit_id });
+}
+
+export async function applyInventoryAuditTauri(audit_id: number): Promise<AuditApplyReport> {
+    return invoke<AuditApplyReport>("apply_inventory_audit_tauri", { auditId: audit_id });
+}
+
+export async function listInventoryAuditsTauri(): Promise<InventoryAudit[]> {
+    return invoke<InventoryAudit[]>("list_inventory_audits_tauri");
+}
+
+export async function startSlotConsolidationTauri(run_length: number): Promise<ConsolidationRun> {
+    return invoke<ConsolidationRun>("start_slot_consolidation_tauri", { runLength: run_length });
+}
+
+export async function getSlotConsolidationTauri(): Promise<ConsolidationRun | null> {
+    return invoke<ConsolidationRun | null>("get_slot_consolidation_tauri");
+}
+
+export async function confirmConsolidationMoveTauri(): Promise<ConsolidationRun> {
+    return invoke<ConsolidationRun>("confirm_consolidation_move_tauri");
+}
+
+export async function skipConsolidationMoveTauri(): Promise<ConsolidationRun> {
+    return invoke<ConsolidationRun>("skip_consolidation_move_tauri");
+}
+
+export async function cancelSlotConsolidationTauri(): Promise<void> {
+    await invoke<void>("cancel_slot_consolidation_tauri");
+}
+
+/** Queues every slot of each ticket; `optimize` reorders the queue to minimise travel. */
+export async function enqueueRetrievalsTauri(tickets: string[], optimize = true): Promise<RetrievalQueue> {
+    return invoke<RetrievalQueue>("enqueue_retrievals_tauri", { tickets, optimize });
+}
+
+export async function getRetrievalQueueTauri(): Promise<RetrievalQueue> {
+    return invoke<RetrievalQueue>("get_retrieval_queue_tauri");
+}
+
+export async function cancelRetrievalTauri(id: number): Promise<RetrievalQueue> {
+    return invoke<RetrievalQueue>("cancel_retrieval_tauri", { id });
+}
+
+export async function reprioritizeRetrievalTauri(id: number, position: number): Promise<RetrievalQueue> {
+    return invoke<RetrievalQueue>("reprioritize_retrieval_tauri", { id, position });
+}
+
+export async function setRetrievalQueuePausedTauri(paused: boolean): Promise<RetrievalQueue> {
+    return invoke<RetrievalQueue>("set_retrieval_queue_paused_tauri", { paused });
+}
+
+export function onRetrievalQueueChanged(handler: (queue: RetrievalQueue) => void) {
+    return listen<RetrievalQueue>("retrieval_queue", (event) => handler(event.payload));
 }
 
 export async function getOccupiedSlotsTauri(): Promise<Slot[]> {
@@ -111,4 +225,57 @@ export async function addConveyorActivityUnloadTauri(ticket: string, garment: st
     await invoke<void>("add_conveyor_activity_unload_tauri", {ticket, garment, slotNum: slotNum, customerIdentifier: customerIdentifier})
 }
 
+export type ExpiredReservation = {
+    slot_number: number;
+    ticket: string | null;
+    reserved_at: string | null;
+};
+
+/** Fires whenever the backend sweeper releases reservations that were never loaded. */
+export function onReservationsExpired(handler: (released: ExpiredReservation[]) => void) {
+    return listen<ExpiredReservation[]>("slot_reservations_expired", (event) => handler(event.payload));
+}
+
+export async function getSlotTimelineTauri(slot_number: number): Promise<SlotHistory[]> {
+    return invoke<SlotHistory[]>("get_slot_timeline_tauri", { slotNumber: slot_number });
+}
+
+export async function getTicketSlotHistoryTauri(ticket: string): Promise<SlotHistory[]> {
+    return invoke<SlotHistory[]>("get_ticket_slot_history_tauri", { ticket });
+}
+
+export async function blockSlotTauri(slot_number: number, reason: string): Promise<void> {
+    await invoke<void>("block_slot_tauri", { slotNumber: slot_number, reason });
+}
+
+export async function unblockSlotTauri(slot_number: number, reason: string): Promise<void> {
+    await invoke<void>("unblock_slot_tauri", { slotNumber: slot_number, reason });
+}
+
+export async function clearSlotErrorTauri(slot_number: number, reason: string): Promise<void> {
+    await invoke<void>("clear_slot_error_tauri", { slotNumber: slot_number, reason });
+}
 
+export async function getOutOfServiceSlotsTauri(): Promise<Slot[]> {
+    return invoke<Slot[]>("get_out_of_service_slots_tauri");
+}
+
+/** Dry run unless `apply` is true; applying requires an admin to be logged in. */
+export async function checkSlotConsistencyTauri(apply = false): Promise<ConsistencyReport> {
+    return invoke<ConsistencyReport>("check_slot_consistency_tauri", { apply });
+}
+
+export type SlotSyncReport = {
+    added: number[];
+    blocked: number[];
+    unblocked: number[];
+    retired: number[];
+    pending_block: number[];
+    pending_retirement: number[];
+    awaiting_removal: number[];
+};
+
+/** Admin only. Also deletes or retires slots removed from the frame configuration. */
+export async function syncSlotTopologyTauri(): Promise<SlotSyncReport> {
+    return invoke<SlotSyncReport>("sync_slot_topology_tauri");
+}
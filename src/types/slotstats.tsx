@@ -1,7 +1,10 @@
 export interface SlotManagerStats {
     total_slots: number;
-    slots_used: number;
-    capacity_percentage: number;
+    occupied_slots: number;
+    occupancy_percentage: number;
+    garment_count: number;
+    garment_capacity: number;
+    item_fill_percentage: number;
 }
 
 export interface SlotStatRow {
@@ -31,4 +34,137 @@ export interface Slot {
     item_id: string | null;
     created_at: string;
     updated_at: string;
-}
\ No newline at end of file
+    frame_number: number;
+    frame_position: number;
+    retired: boolean;
+    garment_count: number;
+    reserved_at: string | null;
+    state_reason: string | null;
+    blocked_by_layout: boolean;
+}
+
+export interface SlotHistory {
+    id: number;
+    slot_number: number;
+    from_state: string | null;
+    to_state: string;
+    ticket: string | null;
+    item_id: string | null;
+    user_id: number | null;
+    session_id: number | null;
+    reason: string;
+    changed_at: string;
+}
+
+export interface ConsistencyIssue {
+    kind: string;
+    description: string;
+    slot_number: number | null;
+    ticket: string | null;
+    repair: { action: string } & Record<string, unknown>;
+}
+
+export interface ConsistencyReport {
+    issues: ConsistencyIssue[];
+    applied: boolean;
+}
+
+export type ClearScope =
+    | { kind: "all" }
+    | { kind: "slotRange"; start: number; end: number }
+    | { kind: "frame"; frameNumber: number }
+    | { kind: "ticket"; ticket: string };
+
+export interface ClearReport {
+    snapshot_id: number | null;
+    freed_slots: number[];
+    garments_removed: number;
+    tickets_reset: string[];
+}
+
+export interface ConveyorSnapshot {
+    id: number;
+    scope: string;
+    slot_count: number;
+    garment_count: number;
+    created_by: number | null;
+    created_at: string;
+    restored_at: string | null;
+    restored_by: number | null;
+}
+
+export type AuditStatus = "open" | "finished" | "applied";
+
+export interface InventoryAudit {
+    id: number;
+    audit_status: AuditStatus;
+    started_by: number | null;
+    started_at: string;
+    finished_at: string | null;
+    applied_by: number | null;
+    applied_at: string | null;
+}
+
+export interface InventoryAuditScan {
+    id: number;
+    audit_id: number;
+    slot_number: number;
+    item_id: string;
+    scanned_at: string;
+}
+
+export interface AuditDiscrepancy {
+    kind: "missing" | "unexpected" | "wrong_slot";
+    item_id: string;
+    expected_slot: number | null;
+    found_slot: number | null;
+}
+
+export interface AuditReport {
+    audit: InventoryAudit;
+    garments_expected: number;
+    garments_scanned: number;
+    discrepancies: AuditDiscrepancy[];
+}
+
+export interface AuditApplyReport {
+    corrected: string[];
+    skipped: string[];
+    slot_repairs: number;
+}
+
+export interface SlotMove {
+    from_slot: number;
+    to_slot: number;
+    ticket: string;
+    garment_count: number;
+}
+
+export interface ConsolidationRun {
+    plan: {
+        run_start: number;
+        run_end: number;
+        moves: SlotMove[];
+    };
+    next_move: number;
+    skipped: number[];
+    feedback_errors: string[];
+}
+
+export interface RetrievalItem {
+    id: number;
+    ticket: string;
+    slot_number: number;
+    state: "pending" | "running" | "arrived" | "failed" | "cancelled";
+    enqueued_at: string;
+    detail: string | null;
+    duration_ms: number | null;
+}
+
+export interface RetrievalQueue {
+    paused: boolean;
+    current: RetrievalItem | null;
+    pending: RetrievalItem[];
+    /** Most recent first. */
+    finished: RetrievalItem[];
+}